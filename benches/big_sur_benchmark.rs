@@ -12,7 +12,7 @@ use macos_unifiedlogs::{
     dsc::SharedCacheStrings,
     parser::{build_log, collect_shared_strings, collect_strings, collect_timesync, parse_log},
     timesync::TimesyncBoot,
-    unified_log::UnifiedLogData,
+    unified_log::{LogOptions, UnifiedLogData},
     uuidtext::UUIDText,
 };
 
@@ -27,14 +27,18 @@ fn bench_build_log(
     timesync_data: &Vec<TimesyncBoot>,
     exclude_missing: bool,
 ) {
+    let options = LogOptions {
+        exclude_missing,
+        ..Default::default()
+    };
     let (_, _) = build_log(
         &log_data,
         &string_results,
         &shared_strings_results,
         &timesync_data,
-        exclude_missing,
         0,
         0,
+        &options,
     );
 }
 
@@ -13,7 +13,7 @@ use macos_unifiedlogs::parser::{
     collect_strings_system, collect_timesync, collect_timesync_system, parse_log,
 };
 use macos_unifiedlogs::timesync::TimesyncBoot;
-use macos_unifiedlogs::unified_log::{LogData, UnifiedLogData};
+use macos_unifiedlogs::unified_log::{LogData, LogOptions, UnifiedLogData};
 use macos_unifiedlogs::uuidtext::UUIDText;
 // use simplelog::{Config, SimpleLogger};
 // use std::error::Error;
@@ -214,7 +214,10 @@ fn parse_trace_file(
     
     // Exclude missing data from returned output. Keep separate until we parse all oversize entries.
     // Then at end, go through all missing data and check all parsed oversize entries again
-    let exclude_missing = true;
+    let options = LogOptions {
+        exclude_missing: true,
+        ..Default::default()
+    };
     let mut missing_data: Vec<UnifiedLogData> = Vec::new();
 
     let mut archive_path = PathBuf::from(path);
@@ -234,9 +237,9 @@ fn parse_trace_file(
             string_results,
             shared_strings_results,
             timesync_data,
-            exclude_missing,
             batteryhealth_string_offset,
-            followup_string_offset
+            followup_string_offset,
+            &options,
         );
         // Track missing data
         missing_data.push(missing_logs);
@@ -289,9 +292,9 @@ fn parse_trace_file(
                     string_results,
                     shared_strings_results,
                     timesync_data,
-                    exclude_missing,
                     batteryhealth_string_offset,
                     followup_string_offset,
+                    &options,
                 );
                 // Track Oversize entries
                 // Track missing logs
@@ -350,9 +353,9 @@ fn parse_trace_file(
                     string_results,
                     shared_strings_results,
                     timesync_data,
-                    exclude_missing,
                     batteryhealth_string_offset,
                     followup_string_offset,
+                    &options,
                 );
 
                 // Track Oversize entries
@@ -0,0 +1,369 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Link activities to their creation event, parent activity and the log entries emitted within them
+//!
+//! Activity IDs are unique per boot, so the graph can be built across all `tracev3` files of an archive.
+//! Activity create events are read from the parsed `UnifiedLogData`, log entries are linked using `LogData.activity_id`.
+//! `build_log` only builds the activity create entries themselves if `LogOptions.activities` is set
+
+use crate::catalog::CatalogChunk;
+use crate::chunks::firehose::activity::FirehoseActivity;
+use crate::dsc::SharedCacheStrings;
+use crate::timesync::TimesyncBoot;
+use crate::unified_log::{LogData, UnifiedLogData};
+use crate::uuidtext::UUIDText;
+use log::warn;
+use std::collections::{HashMap, HashSet};
+
+/// An activity and the log entries emitted within it
+#[derive(Debug, Clone, Default)]
+pub struct Activity {
+    pub activity_id: u64,
+    pub boot_uuid: String,
+    /// Parent (current) activity when the activity was created. 0 if none
+    pub parent_id: u64,
+    pub pid: u64,
+    pub process: String,
+    pub process_uuid: String,
+    /// Format string of the activity create event
+    pub name: String,
+    /// Nanoseconds since UNIXEPOCH of the activity create event, 0 if the create event was not found
+    pub time: f64,
+    pub continuous_time: u64,
+    /// Set if the activity create event was found
+    pub created: bool,
+    /// Child activity IDs in creation order
+    pub children: Vec<u64>,
+    /// Indexes of the log entries added with `ActivityGraph::add_entries()`
+    entries: Vec<usize>,
+}
+
+/// An activity with its log entries and child activities
+#[derive(Debug, Clone)]
+pub struct ActivityTree<'a> {
+    pub activity: &'a Activity,
+    pub entries: Vec<&'a LogData>,
+    pub children: Vec<ActivityTree<'a>>,
+}
+
+/// Activity graph built across the `tracev3` files of an archive
+#[derive(Debug, Default)]
+pub struct ActivityGraph {
+    activities: HashMap<(String, u64), Activity>,
+    entries: Vec<LogData>,
+}
+
+impl ActivityGraph {
+    pub fn new() -> ActivityGraph {
+        ActivityGraph::default()
+    }
+
+    /// Add the activity create events of a parsed `tracev3` file
+    pub fn add_activities(
+        &mut self,
+        unified_log_data: &UnifiedLogData,
+        strings_data: &[UUIDText],
+        shared_strings: &[SharedCacheStrings],
+        timesync_data: &[TimesyncBoot],
+    ) {
+        let Some(header) = unified_log_data.header.first() else {
+            return;
+        };
+        let activity_type = 0x2;
+        for catalog_data in &unified_log_data.catalog_data {
+            for preamble in &catalog_data.firehose {
+                for firehose in &preamble.public_data {
+                    if firehose.unknown_log_activity_type != activity_type {
+                        continue;
+                    }
+                    let activity = &firehose.firehose_activity;
+                    let activity_id = u64::from(activity.unknown_activity_id);
+                    if activity_id == 0 {
+                        continue;
+                    }
+
+                    let continuous_time = preamble.base_continous_time
+                        + (u64::from(firehose.continous_time_delta)
+                            | (u64::from(firehose.continous_time_delta_upper) << 32));
+                    let mut created = Activity {
+                        activity_id,
+                        boot_uuid: header.boot_uuid.to_owned(),
+                        parent_id: u64::from(activity.unknown_activity_id_2),
+                        pid: CatalogChunk::get_pid(
+                            &preamble.first_number_proc_id,
+                            &preamble.second_number_proc_id,
+                            &catalog_data.catalog,
+                        ),
+                        time: TimesyncBoot::get_timestamp(
+                            timesync_data,
+                            &header.boot_uuid,
+                            continuous_time,
+                            preamble.base_continous_time,
+                        ),
+                        continuous_time,
+                        created: true,
+                        ..Default::default()
+                    };
+
+                    match FirehoseActivity::get_firehose_activity_strings(
+                        activity,
+                        strings_data,
+                        shared_strings,
+                        u64::from(firehose.format_string_location),
+                        &preamble.first_number_proc_id,
+                        &preamble.second_number_proc_id,
+                        &catalog_data.catalog,
+                    ) {
+                        Ok((_, results)) => {
+                            created.process = results.process;
+                            created.process_uuid = results.process_uuid;
+                            created.name = results.format_string;
+                        }
+                        Err(err) => {
+                            warn!("[macos-unifiedlogs] Failed to get message string data for firehose activity: {:?}", err);
+                        }
+                    }
+                    self.insert_activity(created);
+                }
+            }
+        }
+    }
+
+    /// Add reconstructed log entries. Entries with an activity ID are linked to their activity
+    pub fn add_entries(&mut self, log_data: &[LogData]) {
+        for entry in log_data {
+            if entry.activity_id == 0 {
+                continue;
+            }
+            let index = self.entries.len();
+            let activity = self.placeholder(&entry.boot_uuid, entry.activity_id);
+            if activity.pid == 0 {
+                activity.pid = entry.pid;
+                activity.process = entry.process.to_owned();
+                activity.process_uuid = entry.process_uuid.to_owned();
+            }
+            activity.entries.push(index);
+            self.entries.push(entry.to_owned());
+        }
+    }
+
+    /// Get an activity by boot UUID and activity ID
+    pub fn activity(&self, boot_uuid: &str, activity_id: u64) -> Option<&Activity> {
+        self.activities.get(&(boot_uuid.to_string(), activity_id))
+    }
+
+    /// Number of activities in the graph
+    pub fn len(&self) -> usize {
+        self.activities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.activities.is_empty()
+    }
+
+    /// Log entries emitted within an activity
+    pub fn entries(&self, activity: &Activity) -> Vec<&LogData> {
+        activity
+            .entries
+            .iter()
+            .filter_map(|index| self.entries.get(*index))
+            .collect()
+    }
+
+    /// Get the parent activities of an activity, from the direct parent to the root
+    pub fn ancestors(&self, boot_uuid: &str, activity_id: u64) -> Vec<&Activity> {
+        let mut ancestors = Vec::new();
+        let mut seen = HashSet::from([activity_id]);
+        let mut current = self.activity(boot_uuid, activity_id);
+        while let Some(activity) = current {
+            if activity.parent_id == 0 || !seen.insert(activity.parent_id) {
+                break;
+            }
+            current = self.activity(boot_uuid, activity.parent_id);
+            if let Some(parent) = current {
+                ancestors.push(parent);
+            }
+        }
+        ancestors
+    }
+
+    /// Get the full activity tree of a log entry: the root activity of the entry with all descendant activities and their entries
+    pub fn tree_for_entry(&self, log_data: &LogData) -> Option<ActivityTree<'_>> {
+        if log_data.activity_id == 0 {
+            return None;
+        }
+        let root = self
+            .ancestors(&log_data.boot_uuid, log_data.activity_id)
+            .last()
+            .map_or(log_data.activity_id, |activity| activity.activity_id);
+        self.tree(&log_data.boot_uuid, root)
+    }
+
+    /// Get the activity tree starting at an activity
+    pub fn tree(&self, boot_uuid: &str, activity_id: u64) -> Option<ActivityTree<'_>> {
+        let mut seen = HashSet::new();
+        self.build_tree(boot_uuid, activity_id, &mut seen)
+    }
+
+    fn build_tree<'a>(
+        &'a self,
+        boot_uuid: &str,
+        activity_id: u64,
+        seen: &mut HashSet<u64>,
+    ) -> Option<ActivityTree<'a>> {
+        if !seen.insert(activity_id) {
+            return None;
+        }
+        let activity = self.activity(boot_uuid, activity_id)?;
+        let children = activity
+            .children
+            .iter()
+            .filter_map(|child| self.build_tree(boot_uuid, *child, seen))
+            .collect();
+        Some(ActivityTree {
+            activity,
+            entries: self.entries(activity),
+            children,
+        })
+    }
+
+    /// Add or complete an activity and link it to its parent
+    fn insert_activity(&mut self, created: Activity) {
+        let boot_uuid = created.boot_uuid.to_owned();
+        let activity_id = created.activity_id;
+        let parent_id = created.parent_id;
+
+        let activity = self.placeholder(&boot_uuid, activity_id);
+        let children = std::mem::take(&mut activity.children);
+        let entries = std::mem::take(&mut activity.entries);
+        *activity = Activity {
+            children,
+            entries,
+            ..created
+        };
+
+        if parent_id != 0 && parent_id != activity_id {
+            let parent = self.placeholder(&boot_uuid, parent_id);
+            if !parent.children.contains(&activity_id) {
+                parent.children.push(activity_id);
+            }
+        }
+    }
+
+    /// Get an activity, adding an empty one if the create event has not been seen yet
+    fn placeholder(&mut self, boot_uuid: &str, activity_id: u64) -> &mut Activity {
+        self.activities
+            .entry((boot_uuid.to_string(), activity_id))
+            .or_insert_with(|| Activity {
+                activity_id,
+                boot_uuid: boot_uuid.to_string(),
+                ..Default::default()
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Activity, ActivityGraph};
+    use crate::unified_log::{self, LogData};
+
+    const BOOT_UUID: &str = "80D194AF56A34C54867449D2130D41BB";
+
+    fn test_log_data(activity_id: u64, message: &str) -> LogData {
+        LogData {
+            activity_id,
+            message: message.to_string(),
+            raw_message: message.to_string(),
+            boot_uuid: String::from(BOOT_UUID),
+            ..unified_log::test_log_data()
+        }
+    }
+
+    fn test_activity(activity_id: u64, parent_id: u64, name: &str) -> Activity {
+        Activity {
+            activity_id,
+            boot_uuid: String::from(BOOT_UUID),
+            parent_id,
+            pid: 45,
+            name: name.to_string(),
+            created: true,
+            ..Default::default()
+        }
+    }
+
+    fn test_graph() -> ActivityGraph {
+        let mut graph = ActivityGraph::new();
+        // Entries may be seen before the activity was created (ex: other tracev3 file)
+        graph.add_entries(&[
+            test_log_data(0x30, "grandchild entry"),
+            test_log_data(0, "no activity"),
+        ]);
+        graph.insert_activity(test_activity(0x10, 0, "root"));
+        graph.insert_activity(test_activity(0x20, 0x10, "child"));
+        graph.insert_activity(test_activity(0x30, 0x20, "grandchild"));
+        graph.insert_activity(test_activity(0x21, 0x10, "second child"));
+        graph.add_entries(&[
+            test_log_data(0x10, "root entry"),
+            test_log_data(0x21, "second child entry"),
+        ]);
+        graph
+    }
+
+    #[test]
+    fn test_activity_graph() {
+        let graph = test_graph();
+        assert_eq!(graph.len(), 4);
+
+        let root = graph.activity(BOOT_UUID, 0x10).unwrap();
+        assert_eq!(root.children, vec![0x20, 0x21]);
+        assert_eq!(graph.entries(root)[0].message, "root entry");
+
+        let grandchild = graph.activity(BOOT_UUID, 0x30).unwrap();
+        assert!(grandchild.created);
+        assert_eq!(grandchild.name, "grandchild");
+        assert_eq!(graph.entries(grandchild)[0].message, "grandchild entry");
+
+        let ancestors: Vec<u64> = graph
+            .ancestors(BOOT_UUID, 0x30)
+            .iter()
+            .map(|activity| activity.activity_id)
+            .collect();
+        assert_eq!(ancestors, vec![0x20, 0x10]);
+        assert!(graph.activity("other boot", 0x10).is_none());
+    }
+
+    #[test]
+    fn test_tree_for_entry() {
+        let graph = test_graph();
+        let tree = graph
+            .tree_for_entry(&test_log_data(0x30, "grandchild entry"))
+            .unwrap();
+        assert_eq!(tree.activity.name, "root");
+        assert_eq!(tree.entries.len(), 1);
+        assert_eq!(tree.children.len(), 2);
+        assert_eq!(tree.children[0].activity.name, "child");
+        assert_eq!(
+            tree.children[0].children[0].entries[0].message,
+            "grandchild entry"
+        );
+        assert_eq!(tree.children[1].activity.name, "second child");
+
+        assert!(graph.tree_for_entry(&test_log_data(0, "none")).is_none());
+    }
+
+    #[test]
+    fn test_activity_cycle() {
+        let mut graph = ActivityGraph::new();
+        graph.insert_activity(test_activity(0x1, 0x2, "first"));
+        graph.insert_activity(test_activity(0x2, 0x1, "second"));
+        assert_eq!(graph.ancestors(BOOT_UUID, 0x1).len(), 1);
+        let tree = graph.tree_for_entry(&test_log_data(0x1, "entry")).unwrap();
+        assert_eq!(tree.children.len(), 1);
+        assert!(tree.children[0].children.is_empty());
+    }
+}
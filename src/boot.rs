@@ -0,0 +1,442 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Consolidated view of the boot sessions in a logarchive
+//!
+//! Combines the `HeaderChunk` of each `tracev3` file (boot UUID, logd pid, build, hardware model, timezone) with the `TimesyncBoot` records (boot time, timebase)
+
+use crate::timesync::TimesyncBoot;
+use crate::unified_log::{LogData, UnifiedLogData};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Mach timebase of the system that wrote the logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Timebase {
+    /// 1/1 timebase
+    Intel,
+    /// 125/3 timebase
+    AppleSilicon,
+    /// Timesync data for the boot was not found
+    Unknown,
+    Other {
+        numerator: u32,
+        denominator: u32,
+    },
+}
+
+impl Timebase {
+    pub fn new(numerator: u32, denominator: u32) -> Timebase {
+        match (numerator, denominator) {
+            (1, 1) => Timebase::Intel,
+            (125, 3) => Timebase::AppleSilicon,
+            _ => Timebase::Other {
+                numerator,
+                denominator,
+            },
+        }
+    }
+}
+
+/// A single boot of the system and the `tracev3` files written during it
+#[derive(Debug, Clone, Serialize)]
+pub struct BootSession {
+    pub boot_uuid: String,
+    /// Nanoseconds since UNIXEPOCH from the timesync data, 0 if the timesync data for the boot was not found
+    pub boot_time: i64,
+    /// Nanoseconds since UNIXEPOCH of the earliest chunkset in the `tracev3` files
+    pub first_entry_time: f64,
+    /// Nanoseconds since UNIXEPOCH of the latest chunkset in the `tracev3` files
+    pub last_entry_time: f64,
+    pub timebase: Timebase,
+    pub build_version: String,
+    pub hardware_model: String,
+    pub timezone_name: String,
+    /// Minutes east of UTC from the `tracev3` header
+    pub timezone_offset_mins: i32,
+    pub logd_pids: Vec<u32>,
+    /// Paths of the `tracev3` files written during the boot
+    pub files: Vec<String>,
+}
+
+impl BootSession {
+    fn new(boot_uuid: &str) -> BootSession {
+        BootSession {
+            boot_uuid: boot_uuid.to_string(),
+            boot_time: 0,
+            first_entry_time: 0.0,
+            last_entry_time: 0.0,
+            timebase: Timebase::Unknown,
+            build_version: String::new(),
+            hardware_model: String::new(),
+            timezone_name: String::new(),
+            timezone_offset_mins: 0,
+            logd_pids: Vec::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Nanoseconds between the boot and a log entry. None if the entry is from another boot or the boot time is unknown
+    pub fn time_since_boot(&self, log_data: &LogData) -> Option<f64> {
+        if log_data.boot_uuid != self.boot_uuid || self.boot_time == 0 {
+            return None;
+        }
+        Some(log_data.time - self.boot_time as f64)
+    }
+
+    /// Nanoseconds between the boot and the last entry in the `tracev3` files
+    pub fn duration(&self) -> f64 {
+        if self.boot_time == 0 {
+            return self.last_entry_time - self.first_entry_time;
+        }
+        self.last_entry_time - self.boot_time as f64
+    }
+
+    fn add_entry_time(&mut self, time: f64) {
+        if time <= 0.0 {
+            return;
+        }
+        if self.first_entry_time == 0.0 || time < self.first_entry_time {
+            self.first_entry_time = time;
+        }
+        if time > self.last_entry_time {
+            self.last_entry_time = time;
+        }
+    }
+}
+
+/// Boot sessions of a logarchive, keyed by boot UUID
+#[derive(Debug, Default)]
+pub struct BootIndex {
+    sessions: HashMap<String, BootSession>,
+}
+
+impl BootIndex {
+    pub fn new() -> BootIndex {
+        BootIndex::default()
+    }
+
+    /// Add the boot time and timebase of each boot in the timesync data
+    pub fn add_timesync(&mut self, timesync_data: &[TimesyncBoot]) {
+        for timesync in timesync_data {
+            let session = self.session_mut(&timesync.boot_uuid);
+            session.boot_time = timesync.boot_time;
+            session.timebase =
+                Timebase::new(timesync.timebase_numerator, timesync.timebase_denominator);
+        }
+    }
+
+    /// Add a parsed `tracev3` file. The entry time range is taken from the catalog chunkset ranges, so the log entries do not need to be built
+    pub fn add_unified_log(
+        &mut self,
+        path: &str,
+        unified_log_data: &UnifiedLogData,
+        timesync_data: &[TimesyncBoot],
+    ) {
+        let Some(header) = unified_log_data.header.first() else {
+            return;
+        };
+        let session = self.session_mut(&header.boot_uuid);
+        if session.timebase == Timebase::Unknown {
+            if let Some(timesync) = timesync_data
+                .iter()
+                .find(|timesync| timesync.boot_uuid == header.boot_uuid)
+            {
+                session.boot_time = timesync.boot_time;
+                session.timebase =
+                    Timebase::new(timesync.timebase_numerator, timesync.timebase_denominator);
+            }
+        }
+        if session.build_version.is_empty() {
+            session.build_version = header.build_version_string.to_owned();
+        }
+        if session.hardware_model.is_empty() {
+            session.hardware_model = header.hardware_model_string.to_owned();
+        }
+        if session.timezone_name.is_empty() {
+            session.timezone_name = header
+                .timezone_path
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            session.timezone_offset_mins = header.timezone_offset().local_minus_utc() / 60;
+        }
+        if !session.logd_pids.contains(&header.logd_pid) {
+            session.logd_pids.push(header.logd_pid);
+        }
+        if !session.files.iter().any(|file| file == path) {
+            session.files.push(path.to_string());
+        }
+
+        let mut continuous_times = Vec::new();
+        for catalog_data in &unified_log_data.catalog_data {
+            for subchunk in &catalog_data.catalog.catalog_subchunks {
+                continuous_times.push(subchunk.start);
+                continuous_times.push(subchunk.end);
+            }
+        }
+        if continuous_times.is_empty() {
+            continuous_times.push(header.continous_time);
+        }
+        for continuous_time in continuous_times {
+            let time = TimesyncBoot::get_timestamp(
+                timesync_data,
+                &header.boot_uuid,
+                continuous_time,
+                continuous_time,
+            );
+            session.add_entry_time(time);
+        }
+    }
+
+    /// Widen the entry time range with built log entries (ex: Simpledump or Statedump entries outside of the chunksets)
+    pub fn add_entries(&mut self, log_data: &[LogData]) {
+        for entry in log_data {
+            self.session_mut(&entry.boot_uuid)
+                .add_entry_time(entry.time);
+        }
+    }
+
+    /// Get a boot session by boot UUID
+    pub fn session(&self, boot_uuid: &str) -> Option<&BootSession> {
+        self.sessions.get(boot_uuid)
+    }
+
+    /// Get the boot session of a log entry
+    pub fn session_for_entry(&self, log_data: &LogData) -> Option<&BootSession> {
+        self.session(&log_data.boot_uuid)
+    }
+
+    /// Get the boot session before the session of a log entry
+    pub fn previous_session(&self, log_data: &LogData) -> Option<&BootSession> {
+        let sessions = self.sessions();
+        let index = sessions
+            .iter()
+            .position(|session| session.boot_uuid == log_data.boot_uuid)?;
+        index.checked_sub(1).map(|previous| sessions[previous])
+    }
+
+    /// All boot sessions sorted by boot time. Sessions without timesync data are sorted by their first entry time
+    pub fn sessions(&self) -> Vec<&BootSession> {
+        let mut sessions: Vec<&BootSession> = self.sessions.values().collect();
+        sessions.sort_by(|first, second| {
+            sort_time(first)
+                .total_cmp(&sort_time(second))
+                .then_with(|| first.boot_uuid.cmp(&second.boot_uuid))
+        });
+        sessions
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    fn session_mut(&mut self, boot_uuid: &str) -> &mut BootSession {
+        self.sessions
+            .entry(boot_uuid.to_string())
+            .or_insert_with(|| BootSession::new(boot_uuid))
+    }
+}
+
+fn sort_time(session: &BootSession) -> f64 {
+    if session.boot_time != 0 {
+        return session.boot_time as f64;
+    }
+    session.first_entry_time
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BootIndex, Timebase};
+    use crate::catalog::{CatalogChunk, CatalogSubchunk};
+    use crate::header::HeaderChunk;
+    use crate::timesync::{Timesync, TimesyncBoot};
+    use crate::unified_log::{self, LogData, UnifiedLogCatalogData, UnifiedLogData};
+
+    const BOOT_UUID: &str = "80D194AF56A34C54867449D2130D41BB";
+    const OLD_BOOT_UUID: &str = "4C3D08CD2E0B4F8E9C5D2A8D1B7A1E11";
+
+    fn test_timesync() -> Vec<TimesyncBoot> {
+        let boot = |boot_uuid: &str, numerator, denominator, boot_time| TimesyncBoot {
+            signature: 0xbbb0,
+            header_size: 48,
+            unknown: 0,
+            boot_uuid: String::from(boot_uuid),
+            timebase_numerator: numerator,
+            timebase_denominator: denominator,
+            boot_time,
+            timezone_offset_mins: 0,
+            daylight_savings: 0,
+            timesync: vec![Timesync {
+                signature: 0x207354,
+                unknown_flags: 0,
+                kernel_time: 0,
+                walltime: boot_time,
+                timezone: 0,
+                daylight_savings: 0,
+            }],
+        };
+        vec![
+            boot(BOOT_UUID, 1, 1, 1642300000000000000),
+            boot(OLD_BOOT_UUID, 125, 3, 1642000000000000000),
+        ]
+    }
+
+    fn test_catalog(start: u64, end: u64) -> CatalogChunk {
+        CatalogChunk {
+            chunk_tag: 0x600b,
+            chunk_sub_tag: 0,
+            chunk_data_size: 0,
+            catalog_subsystem_strings_offset: 0,
+            catalog_process_info_entries_offset: 0,
+            number_process_information_entries: 0,
+            catalog_offset_sub_chunks: 0,
+            number_sub_chunks: 1,
+            unknown: Vec::new(),
+            earliest_firehose_timestamp: start,
+            catalog_uuids: Vec::new(),
+            catalog_subsystem_strings: Vec::new(),
+            catalog_process_info_entries: Vec::new(),
+            catalog_subchunks: vec![CatalogSubchunk {
+                start,
+                end,
+                uncompressed_size: 0,
+                compression_algorithm: 0x100,
+                number_index: 0,
+                indexes: Vec::new(),
+                number_string_offsets: 0,
+                string_offsets: Vec::new(),
+            }],
+        }
+    }
+
+    fn test_unified_log(boot_uuid: &str, logd_pid: u32, start: u64, end: u64) -> UnifiedLogData {
+        UnifiedLogData {
+            header: vec![HeaderChunk {
+                boot_uuid: String::from(boot_uuid),
+                logd_pid,
+                build_version_string: String::from("21C52"),
+                hardware_model_string: String::from("MacBookPro16,1"),
+                timezone_path: String::from("/var/db/timezone/zoneinfo/America/New_York"),
+                bias_min: 300,
+                ..Default::default()
+            }],
+            catalog_data: vec![UnifiedLogCatalogData {
+                catalog: test_catalog(start, end),
+                firehose: Vec::new(),
+                simpledump: Vec::new(),
+                statedump: Vec::new(),
+                oversize: Vec::new(),
+            }],
+            oversize: Vec::new(),
+        }
+    }
+
+    fn test_log_data(time: f64) -> LogData {
+        LogData {
+            time,
+            message: String::from("LOMD Start"),
+            raw_message: String::from("LOMD Start"),
+            boot_uuid: String::from(BOOT_UUID),
+            ..unified_log::test_log_data()
+        }
+    }
+
+    fn test_index() -> BootIndex {
+        let timesync = test_timesync();
+        let mut index = BootIndex::new();
+        index.add_unified_log(
+            "Persist/0000000000000001.tracev3",
+            &test_unified_log(BOOT_UUID, 88, 10000000000, 20000000000),
+            &timesync,
+        );
+        index.add_unified_log(
+            "Persist/0000000000000002.tracev3",
+            &test_unified_log(BOOT_UUID, 88, 30000000000, 40000000000),
+            &timesync,
+        );
+        index.add_unified_log(
+            "Special/0000000000000001.tracev3",
+            &test_unified_log(OLD_BOOT_UUID, 90, 24000000, 48000000),
+            &timesync,
+        );
+        index
+    }
+
+    #[test]
+    fn test_boot_index() {
+        let index = test_index();
+        assert_eq!(index.len(), 2);
+
+        let session = index.session(BOOT_UUID).unwrap();
+        assert_eq!(session.boot_time, 1642300000000000000);
+        assert_eq!(session.timebase, Timebase::Intel);
+        assert_eq!(session.first_entry_time, 1642300010000000000.0);
+        assert_eq!(session.last_entry_time, 1642300040000000000.0);
+        assert_eq!(session.build_version, "21C52");
+        assert_eq!(session.hardware_model, "MacBookPro16,1");
+        assert_eq!(session.timezone_name, "New_York");
+        assert_eq!(session.timezone_offset_mins, -300);
+        assert_eq!(session.logd_pids, vec![88]);
+        assert_eq!(session.files.len(), 2);
+        assert_eq!(session.duration(), 40000000000.0);
+
+        let old_session = index.session(OLD_BOOT_UUID).unwrap();
+        assert_eq!(old_session.timebase, Timebase::AppleSilicon);
+        assert_eq!(old_session.first_entry_time, 1642000001000000000.0);
+        assert_eq!(old_session.last_entry_time, 1642000002000000000.0);
+    }
+
+    #[test]
+    fn test_sessions_for_entry() {
+        let mut index = test_index();
+        let sessions: Vec<&str> = index
+            .sessions()
+            .iter()
+            .map(|session| session.boot_uuid.as_str())
+            .collect();
+        assert_eq!(sessions, vec![OLD_BOOT_UUID, BOOT_UUID]);
+
+        let entry = test_log_data(1642559200000000000.0);
+        let session = index.session_for_entry(&entry).unwrap();
+        // Three days after the boot
+        assert_eq!(session.time_since_boot(&entry), Some(259200000000000.0));
+        assert_eq!(
+            index.previous_session(&entry).unwrap().boot_uuid,
+            OLD_BOOT_UUID
+        );
+
+        index.add_entries(&[entry]);
+        assert_eq!(
+            index.session(BOOT_UUID).unwrap().last_entry_time,
+            1642559200000000000.0
+        );
+    }
+
+    #[test]
+    fn test_timebase() {
+        assert_eq!(Timebase::new(1, 1), Timebase::Intel);
+        assert_eq!(Timebase::new(125, 3), Timebase::AppleSilicon);
+        assert_eq!(
+            Timebase::new(2, 1),
+            Timebase::Other {
+                numerator: 2,
+                denominator: 1
+            }
+        );
+
+        let mut index = BootIndex::new();
+        index.add_timesync(&test_timesync());
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.session(BOOT_UUID).unwrap().first_entry_time, 0.0);
+    }
+}
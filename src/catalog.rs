@@ -540,6 +540,72 @@ impl CatalogChunk {
     }
 }
 
+/// Catalog covering the `start` to `end` continuous time, shared by the filter and exporter tests
+/// Subsystem strings are used by `test_process_info` entries
+#[cfg(test)]
+pub(crate) fn test_catalog(start: u64, end: u64) -> CatalogChunk {
+    CatalogChunk {
+        chunk_tag: 0x600b,
+        chunk_sub_tag: 0,
+        chunk_data_size: 0,
+        catalog_subsystem_strings_offset: 0,
+        catalog_process_info_entries_offset: 0,
+        number_process_information_entries: 0,
+        catalog_offset_sub_chunks: 0,
+        number_sub_chunks: 1,
+        unknown: Vec::new(),
+        earliest_firehose_timestamp: start,
+        catalog_uuids: Vec::new(),
+        catalog_subsystem_strings: b"com.apple.powerd\0battery\0com.apple.lom\0device\0".to_vec(),
+        catalog_process_info_entries: Vec::new(),
+        catalog_subchunks: vec![CatalogSubchunk {
+            start,
+            end,
+            uncompressed_size: 0,
+            compression_algorithm: 0x100,
+            number_index: 0,
+            indexes: Vec::new(),
+            number_string_offsets: 0,
+            string_offsets: Vec::new(),
+        }],
+    }
+}
+
+/// Process entry with the `com.apple.powerd`/`battery` (1) and `com.apple.lom`/`device` (2) subsystems
+#[cfg(test)]
+pub(crate) fn test_process_info(pid: u32, main_uuid: &str) -> ProcessInfoEntry {
+    ProcessInfoEntry {
+        index: 0,
+        unknown: 0,
+        catalog_main_uuid_index: 0,
+        catalog_dsc_uuid_index: 1,
+        first_number_proc_id: u64::from(pid) * 10,
+        second_number_proc_id: pid,
+        pid,
+        effective_user_id: 0,
+        unknown2: 0,
+        number_uuids_entries: 0,
+        unknown3: 0,
+        uuid_info_entries: Vec::new(),
+        number_subsystems: 2,
+        unknown4: 0,
+        subsystem_entries: vec![
+            ProcessInfoSubsystem {
+                identifer: 1,
+                subsystem_offset: 0,
+                category_offset: 17,
+            },
+            ProcessInfoSubsystem {
+                identifer: 2,
+                subsystem_offset: 25,
+                category_offset: 39,
+            },
+        ],
+        main_uuid: main_uuid.to_string(),
+        dsc_uuid: String::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::CatalogChunk;
@@ -11,7 +11,10 @@ use crate::chunks::firehose::loss::FirehoseLoss;
 use crate::chunks::firehose::nonactivity::FirehoseNonActivity;
 use crate::chunks::firehose::signpost::FirehoseSignpost;
 use crate::chunks::firehose::trace::FirehoseTrace;
-use crate::util::{encode_standard, extract_string_size, padding_size, padding_size_four};
+use crate::message_value::MessageValue;
+use crate::util::{
+    decode_standard, encode_standard, extract_string_size, padding_size, padding_size_four,
+};
 use log::{debug, error, warn};
 use nom::bytes::complete::take_while;
 use nom::number::complete::{be_u128, le_i16, le_i32, le_i64, le_i8};
@@ -68,6 +71,7 @@ pub struct FirehoseItemType {
     message_string_size: u16,
     pub message_strings: String,
     pub backtrace_strings: Vec<String>, // Only exists if log entry flag "has_context_data" is set
+    message_value: MessageValue,
 }
 
 #[derive(Debug, Clone)]
@@ -81,6 +85,7 @@ pub struct FirehoseItemInfo {
     pub message_strings: String, // The message entry.
     pub item_type: u8,           // Type of item: strings, numbers, objects, precision
     pub item_size: u16,          // Size of message in bytes
+    pub message_value: MessageValue, // Typed message entry
 }
 
 impl FirehosePreamble {
@@ -215,22 +220,19 @@ impl FirehosePreamble {
                         }
                     }
                 }
-                if firehose_public_data.format_string_location == bh_offset ||
-                    firehose_public_data.format_string_location == fl_offset
-                {
+                if FirehosePreamble::keep_firehose(&firehose_public_data, bh_offset, fl_offset) {
                     firehose_data.public_data.push(firehose_public_data);
                 }
                 break;
             }
-            if firehose_public_data.format_string_location == bh_offset ||
-                firehose_public_data.format_string_location == fl_offset {
+            if FirehosePreamble::keep_firehose(&firehose_public_data, bh_offset, fl_offset) {
                 firehose_data.public_data.push(firehose_public_data);
             }
                 
         }
 
         // If there is private data, go through and update any logs that have private data items
-        /* if firehose_private_data_virtual_offset != 0x1000 {
+        if firehose_private_data_virtual_offset != 0x1000 {
             debug!("[macos-unifiedlogs] Parsing Private Firehose Data");
             // Nom any padding
             let (mut private_input, _) = take_while(|b: u8| b == 0)(input)?;
@@ -247,14 +249,33 @@ impl FirehosePreamble {
                     continue;
                 }
                 // Get the start of private string data
-                let string_offset = data.firehose_non_activity.private_strings_offset
-                    - firehose_private_data_virtual_offset;
+                let string_offset = match data
+                    .firehose_non_activity
+                    .private_strings_offset
+                    .checked_sub(firehose_private_data_virtual_offset)
+                {
+                    Some(result) if usize::from(result) <= private_input.len() => result,
+                    _ => {
+                        error!(
+                            "[macos-unifiedlogs] Private strings offset {} outside of private data at {}",
+                            data.firehose_non_activity.private_strings_offset,
+                            firehose_private_data_virtual_offset
+                        );
+                        continue;
+                    }
+                };
                 let (private_string_start, _) = take(string_offset)(private_input)?;
-                let (_, _) =
-                    FirehosePreamble::parse_private_data(private_string_start, &mut data.message)?;
+                if let Err(err) =
+                    FirehosePreamble::parse_private_data(private_string_start, &mut data.message)
+                {
+                    error!(
+                        "[macos-unifiedlogs] Failed to parse firehose private data: {:?}",
+                        err
+                    );
+                }
             }
             input = private_input;
-        } */
+        }
         Ok((input, firehose_data))
     }
 
@@ -294,6 +315,7 @@ impl FirehosePreamble {
             // Precision items just contain the length for the actual item
             // Ex: %*s
             if precision_items.contains(&item.item_type) {
+                item.message_value = MessageValue::Unsigned(u64::from(item.message_string_size));
                 items_data.push(item);
                 item_count += 1;
                 continue;
@@ -305,6 +327,7 @@ impl FirehosePreamble {
                     FirehosePreamble::parse_item_number(firehose_input, u16::from(item.item_size))?;
 
                 item.message_strings = format!("{}", message_number);
+                item.message_value = MessageValue::Signed(message_number);
                 firehose_input = item_value_input;
                 item_count += 1;
                 items_data.push(item);
@@ -314,6 +337,7 @@ impl FirehosePreamble {
             // A message size of 0 and is an object type is "(null)"
             if item.message_string_size == 0 && object_items.contains(&item.item_type) {
                 item.message_strings = String::from("(null)");
+                item.message_value = MessageValue::Object(String::from("(null)"));
             }
             items_data.push(item);
             item_count += 1;
@@ -359,10 +383,12 @@ impl FirehosePreamble {
                 || sensitive_items.contains(&item.item_type)
             {
                 item.message_strings = String::from("<private>");
+                item.message_value = MessageValue::Private;
                 continue;
             }
 
             if item.item_type == private_number {
+                item.message_value = MessageValue::Private;
                 continue;
             }
 
@@ -385,6 +411,7 @@ impl FirehosePreamble {
                     item.message_string_size,
                 )?;
                 firehose_input = item_value_input;
+                item.message_value = FirehosePreamble::item_string_value(item.item_type, &message_string);
                 item.message_strings = message_string;
             } else {
                 error!(
@@ -402,6 +429,7 @@ impl FirehosePreamble {
                 message_strings: item.message_strings,
                 item_type: item.item_type,
                 item_size: item.message_string_size,
+                message_value: item.message_value,
             };
             firehose_item_data.item_info.push(item_info);
         }
@@ -429,6 +457,7 @@ impl FirehosePreamble {
                             take(private_string_start.len())(private_string_start)?;
                         private_string_start = private_data;
                         firehose_info.message_strings = encode_standard(pointer_object);
+                        firehose_info.message_value = MessageValue::Bytes(pointer_object.to_vec());
 
                         continue;
                     }
@@ -437,6 +466,7 @@ impl FirehosePreamble {
                         take(firehose_info.item_size)(private_string_start)?;
                     private_string_start = private_data;
                     firehose_info.message_strings = encode_standard(pointer_object);
+                    firehose_info.message_value = MessageValue::Bytes(pointer_object.to_vec());
                     continue;
                 }
 
@@ -444,6 +474,8 @@ impl FirehosePreamble {
                     extract_string_size(private_string_start, u64::from(firehose_info.item_size))?;
 
                 private_string_start = private_data;
+                firehose_info.message_value =
+                    FirehosePreamble::item_string_value(firehose_info.item_type, &private_string);
                 firehose_info.message_strings = private_string;
             } else if firehose_info.item_type == private_number {
                 let (private_data, private_string) = FirehosePreamble::parse_item_number(
@@ -452,11 +484,22 @@ impl FirehosePreamble {
                 )?;
                 private_string_start = private_data;
                 firehose_info.message_strings = format!("{}", private_string);
+                firehose_info.message_value = MessageValue::Signed(private_string);
             }
         }
         Ok((private_string_start, ()))
     }
 
+    /// Keep the entries logged with the battery health format strings, the activity and the signpost entries (see `LogOptions.activities` and `LogOptions.signposts`)
+    fn keep_firehose(firehose: &Firehose, bh_offset: u32, fl_offset: u32) -> bool {
+        let activity = 0x2;
+        let signpost = 0x6;
+        firehose.format_string_location == bh_offset
+            || firehose.format_string_location == fl_offset
+            || firehose.unknown_log_activity_type == activity
+            || firehose.unknown_log_activity_type == signpost
+    }
+
     // Parse all the different types of Firehose data (activity, non-activity, loss, trace, signpost)
     fn parse_firehose(data: &[u8]) -> nom::IResult<&[u8], Firehose> {
         let mut firehose_results = Firehose {
@@ -745,6 +788,7 @@ impl FirehosePreamble {
             message_string_size: 0,
             message_strings: String::new(),
             backtrace_strings: Vec::new(),
+            message_value: MessageValue::Missing,
         };
 
         // Firehose string item values
@@ -815,6 +859,22 @@ impl FirehosePreamble {
         Ok((input, message_string))
     }
 
+    // Get the typed value of a string item. Arbitrary data was base64 encoded by parse_item_string
+    fn item_string_value(item_type: u8, message_string: &str) -> MessageValue {
+        let arbitrary = [0x30, 0x31, 0x32, 0x35, 0xf2];
+        let object_items = [0x40, 0x41, 0x42];
+        if arbitrary.contains(&item_type) {
+            return match decode_standard(message_string) {
+                Ok(data) => MessageValue::Bytes(data),
+                Err(_) => MessageValue::String(message_string.to_string()),
+            };
+        }
+        if object_items.contains(&item_type) {
+            return MessageValue::Object(message_string.to_string());
+        }
+        MessageValue::String(message_string.to_string())
+    }
+
     // Parse the Firehose item number
     fn parse_item_number(data: &[u8], item_size: u16) -> nom::IResult<&[u8], i64> {
         let (input, message_data) = take(item_size)(data)?;
@@ -853,6 +913,7 @@ mod tests {
     use std::{fs::File, io::Read, path::PathBuf};
 
     use super::{FirehoseItemData, FirehoseItemInfo, FirehosePreamble};
+    use crate::message_value::MessageValue;
 
     #[test]
     fn test_parse_firehose_preamble() {
@@ -3016,6 +3077,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_keep_firehose() {
+        let test_firehose_data = [
+            4, 0, 45, 2, 64, 239, 31, 18, 78, 235, 0, 0, 0, 0, 0, 0, 183, 221, 36, 35, 16, 0, 99,
+            0, 188, 251, 0, 0, 0, 0, 0, 128, 198, 202, 25, 18, 1, 0, 2, 0, 14, 0, 34, 1, 66, 4, 0,
+            0, 73, 0, 91, 97, 112, 112, 60, 97, 112, 112, 108, 105, 99, 97, 116, 105, 111, 110, 46,
+            99, 111, 109, 46, 111, 98, 106, 101, 99, 116, 105, 118, 101, 45, 115, 101, 101, 46,
+            108, 117, 108, 117, 46, 97, 112, 112, 46, 50, 57, 51, 53, 48, 52, 52, 52, 46, 50, 57,
+            51, 53, 48, 52, 53, 48, 40, 53, 48, 49, 41, 62, 58, 54, 52, 49, 93, 0, 0, 0, 0, 0, 0,
+        ];
+        let (_, mut firehose) = FirehosePreamble::parse_firehose(&test_firehose_data).unwrap();
+        assert!(FirehosePreamble::keep_firehose(&firehose, 304082752, 0));
+        assert!(FirehosePreamble::keep_firehose(&firehose, 0, 304082752));
+        assert!(!FirehosePreamble::keep_firehose(&firehose, 0, 0));
+
+        // Activities and signposts are kept for every format string
+        firehose.unknown_log_activity_type = 0x2;
+        assert!(FirehosePreamble::keep_firehose(&firehose, 0, 0));
+        firehose.unknown_log_activity_type = 0x6;
+        assert!(FirehosePreamble::keep_firehose(&firehose, 0, 0));
+    }
+
     #[test]
     fn test_parse_firehose_private_data_zeros() {
         let data = [
@@ -3243,6 +3326,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_firehose_private_strings() {
+        let test_data = [
+            1, 96, 0, 0, 0, 0, 0, 0, 163, 0, 0, 0, 0, 0, 0, 0, 143, 0, 0, 0, 0, 0, 0, 0, 100, 1, 0,
+            0, 0, 1, 0, 0, 56, 0, 165, 15, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 2, 1, 16, 63,
+            0, 0, 189, 3, 0, 0, 0, 0, 0, 0, 143, 31, 133, 28, 1, 0, 16, 0, 165, 15, 91, 0, 120, 58,
+            0, 0, 67, 1, 33, 4, 0, 0, 91, 0, 82, 101, 99, 114, 101, 97, 116, 105, 110, 103, 32, 83,
+            121, 115, 116, 101, 109, 46, 107, 101, 121, 99, 104, 97, 105, 110, 32, 98, 101, 99, 97,
+            117, 115, 101, 32, 105, 116, 32, 99, 97, 110, 110, 111, 116, 32, 117, 110, 108, 111,
+            99, 107, 59, 32, 115, 101, 101, 32, 47, 117, 115, 114, 47, 108, 105, 98, 101, 120, 101,
+            99, 47, 115, 101, 99, 117, 114, 105, 116, 121, 45, 99, 104, 101, 99, 107, 115, 121,
+            115, 116, 101, 109, 0,
+        ];
+        let format_string_location = 16144;
+        let (_, firehose) =
+            FirehosePreamble::parse_firehose_preamble(&test_data, format_string_location, 0)
+                .unwrap();
+        assert_eq!(firehose.public_data.len(), 1);
+
+        let item = &firehose.public_data[0].message.item_info[0];
+        let private_string = "Recreating System.keychain because it cannot unlock; see /usr/libexec/security-checksystem";
+        assert_eq!(item.item_type, 0x21);
+        assert_eq!(item.message_strings, private_string);
+        assert_eq!(
+            item.message_value,
+            MessageValue::String(String::from(private_string))
+        );
+    }
+
     #[test]
     fn test_get_backtrace() {
         let test_data = [
@@ -3363,6 +3475,7 @@ mod tests {
             message_strings: String::new(),
             item_type: 33,
             item_size: 161,
+            message_value: MessageValue::Private,
         };
         results.item_info.push(firehose_item);
         let (_, _) = FirehosePreamble::parse_private_data(&test_data, &mut results).unwrap();
@@ -3381,6 +3494,7 @@ mod tests {
             message_strings: String::new(),
             item_type: 1,
             item_size: 8,
+            message_value: MessageValue::Private,
         };
         results.item_info.push(firehose_item);
         let (_, _) = FirehosePreamble::parse_private_data(&test_data, &mut results).unwrap();
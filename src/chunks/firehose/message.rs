@@ -569,7 +569,7 @@ impl MessageData {
     }
 
     /// Get the image path from provided `main_uuid` entry
-    fn get_uuid_image_path<'a>(
+    pub(crate) fn get_uuid_image_path<'a>(
         main_uuid: &str,
         entries: &'a [UUIDText],
     ) -> nom::IResult<&'a [u8], String> {
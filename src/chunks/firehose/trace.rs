@@ -12,6 +12,7 @@ use std::mem::size_of;
 use crate::catalog::CatalogChunk;
 use crate::chunks::firehose::firehose_log::{FirehoseItemData, FirehoseItemInfo};
 use crate::chunks::firehose::message::MessageData;
+use crate::message_value::MessageValue;
 use crate::uuidtext::UUIDText;
 
 #[derive(Debug, Clone)]
@@ -69,6 +70,7 @@ impl FirehoseTrace {
             message_strings: format!("{}", message),
             item_type: 0,
             item_size: 0,
+            message_value: MessageValue::Signed(i64::from(message)),
         };
 
         item_data.item_info.push(item_info);
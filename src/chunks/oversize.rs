@@ -129,6 +129,7 @@ impl Oversize {
                         message_strings: message.message_strings.to_owned(),
                         item_type: message.item_type,
                         item_size: message.item_size,
+                        message_value: message.message_value.to_owned(),
                     };
                     message_strings.push(oversize_firehose);
                 }
@@ -148,6 +149,7 @@ mod tests {
 
     use crate::chunks::firehose::firehose_log::{FirehoseItemData, FirehoseItemInfo};
     use crate::chunks::oversize::Oversize;
+    use crate::message_value::MessageValue;
 
     #[test]
     fn test_parse_oversize() {
@@ -384,6 +386,9 @@ mod tests {
                         message_strings: String::from("system kext collection"),
                         item_type: 34,
                         item_size: 0,
+                        message_value: MessageValue::String(String::from(
+                            "system kext collection",
+                        )),
                     },
                     FirehoseItemInfo {
                         message_strings: String::from(
@@ -391,6 +396,9 @@ mod tests {
                         ),
                         item_type: 34,
                         item_size: 0,
+                        message_value: MessageValue::String(String::from(
+                            "/System/Library/KernelCollections/SystemKernelExtensions.kc",
+                        )),
                     },
                 ],
                 backtrace_strings: Vec::new(),
@@ -5,12 +5,15 @@
 // is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and limitations under the License.
 
-use crate::decoders::location;
+use crate::decoders::keyed_archive::decode_plist;
+use crate::decoders::objects::ObjectDecoderRegistry;
+use crate::decoders::protobuf::decode_protobuf;
+use crate::unified_log::LogOptions;
 use crate::util::{clean_uuid, extract_string};
-use log::{error, info};
+use log::{error, info, warn};
 use nom::bytes::complete::take;
 use nom::number::complete::{le_u32, le_u64, le_u8};
-use plist::Value;
+use serde_json::Value;
 use std::mem::size_of;
 
 #[derive(Debug, Clone)]
@@ -114,63 +117,132 @@ impl Statedump {
         Ok((input, statedump_results))
     }
 
-    /// Parse the binary plist file in the log. The plist may be empty
+    /// Parse the binary plist file in the log. The plist may be empty. `NSKeyedArchiver` archives are resolved to the archived object
     pub fn parse_statedump_plist(plist_data: &[u8]) -> String {
         if plist_data.is_empty() {
             info!("[macos-unifiedlogs] Empty plist data in statedump");
             return String::from("Empty plist data");
         }
-        let data: Result<Value, plist::Error> = plist::from_bytes(plist_data);
-        match data {
-            Ok(results) => {
-                let json_data = serde_json::to_string(&results);
-                match json_data {
-                    Ok(json) => json,
+        match decode_plist(plist_data) {
+            Ok(results) => results.to_string(),
+            Err(_err) => String::from("Failed to get plist data"),
+        }
+    }
+
+    /// Decode the protocol buffer data in the log without a schema. Returns JSON
+    pub fn parse_statedump_protobuf(protobuf_data: &[u8]) -> String {
+        if protobuf_data.is_empty() {
+            info!("[macos-unifiedlogs] Empty protocol buffer data in statedump");
+            return String::from("Empty protocol buffer data");
+        }
+        match decode_protobuf(protobuf_data) {
+            Ok(results) => results.to_string(),
+            Err(_err) => String::from("Failed to get protocol buffer data"),
+        }
+    }
+
+    /// Decode the Statedump data based on the data type. Custom objects are decoded with `options.objects`
+    /// and protocol buffers with `options.protobuf_schema` if the Statedump name is mapped to a message type in `options.protobuf_types`
+    pub(crate) fn parse_statedump_data(&self, options: &LogOptions) -> String {
+        match self.unknown_data_type {
+            0x1 => Statedump::parse_statedump_plist(&self.statedump_data),
+            0x2 => {
+                let schema = options
+                    .protobuf_schema
+                    .as_ref()
+                    .zip(options.protobuf_types.get(&self.unknown_name));
+                match schema {
+                    Some((schema, message_type)) => {
+                        match schema.decode(message_type, &self.statedump_data) {
+                            Ok(results) => results.to_string(),
+                            Err(_err) => Statedump::parse_statedump_protobuf(&self.statedump_data),
+                        }
+                    }
+                    None => Statedump::parse_statedump_protobuf(&self.statedump_data),
+                }
+            }
+            0x3 => self.parse_statedump_object(&options.objects).to_string(),
+            _ => {
+                warn!(
+                    "[macos-unifiedlogs] Unknown statedump data type: {}",
+                    self.unknown_data_type
+                );
+                match extract_string(&self.statedump_data) {
+                    Ok((_, string_data)) => string_data,
                     Err(err) => {
                         error!(
-                            "[macos-unifiedlogs] Failed to convert plist to json: {:?}",
+                            "[macos-unifiedlogs] Failed to extract string from statedump: {:?}",
                             err
                         );
-                        String::from("Failed to convert plist data to json")
+                        String::from("Failed to extract string from statedump")
                     }
                 }
             }
-            Err(err) => {
-                error!(
-                    "[macos-unifiedlogs] Failed to parse statedump plist data: {:?}",
-                    err
-                );
-                String::from("Failed to get plist data")
-            }
         }
     }
 
-    /// Parse custom Apple objects
-    pub(crate) fn parse_statedump_object(object_data: &[u8], name: &str) -> String {
-        let message_result = match name {
-            "CLDaemonStatusStateTracker" => location::get_daemon_status_tracker(object_data),
-            "CLClientManagerStateTracker" => location::get_state_tracker_data(object_data),
-            "CLLocationManagerStateTracker" => location::get_location_tracker_state(object_data),
-            _ => {
-                return format!("Unsupported Statedump object: {}", name);
-            }
+    /// Parse custom Apple objects with the decoder registered for the object type or name
+    pub(crate) fn parse_statedump_object(&self, decoders: &ObjectDecoderRegistry) -> Value {
+        let Some(decoder) = decoders.find(
+            &self.unknown_object_type_string_1,
+            &self.unknown_object_type_string_2,
+            &self.unknown_name,
+        ) else {
+            return Value::String(format!(
+                "Unsupported Statedump object: {}",
+                self.unknown_name
+            ));
         };
-        match message_result {
-            Ok((_, result)) => result,
-            Err(err) => {
+        match decoder.decode(&self.statedump_data) {
+            Some(result) => result,
+            None => {
                 error!(
-                    "[macos-unifiedlogs] Failed to parse statedump object {}: {:?}",
-                    name, err
+                    "[macos-unifiedlogs] Failed to parse statedump object {}",
+                    self.unknown_name
                 );
-                format!("Failed to parse statedump object: {}", name)
+                Value::String(format!(
+                    "Failed to parse statedump object: {}",
+                    self.unknown_name
+                ))
             }
         }
     }
 }
 
+/// Statedump with a `location:_CLDaemonStatusStateTrackerState` object, shared by the statedump and build_log tests
+#[cfg(test)]
+pub(crate) fn test_statedump() -> Statedump {
+    Statedump {
+        chunk_tag: 0x6003,
+        chunk_subtag: 0,
+        chunk_data_size: 288,
+        first_proc_id: 113,
+        second_proc_id: 464,
+        ttl: 14,
+        unknown_reserved: vec![0, 0, 0],
+        continuous_time: 3906319117,
+        activity_id: 9223372036854776950,
+        uuid: String::from("5CD8DDEE04383A38887710227C5A0A56"),
+        unknown_data_type: 3,
+        unknown_data_size: 40,
+        unknown_object_type_string_1: String::from("location"),
+        unknown_object_type_string_2: String::from("_CLDaemonStatusStateTrackerState"),
+        unknown_name: String::from("CLDaemonStatusStateTracker"),
+        statedump_data: vec![
+            0, 0, 0, 0, 0, 0, 240, 191, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0,
+            255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0,
+        ],
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::chunks::statedump::Statedump;
+    use crate::chunks::statedump::{test_statedump, Statedump};
+    use crate::decoders::objects::ObjectDecoderRegistry;
+    use crate::decoders::protobuf::ProtobufSchema;
+    use crate::unified_log::LogOptions;
+    use plist::{Dictionary, Uid, Value};
+    use serde_json::json;
 
     #[test]
     fn test_parse_statedump() {
@@ -1056,12 +1128,100 @@ mod tests {
 
     #[test]
     fn test_parse_statedump_object() {
-        let name = "CLDaemonStatusStateTracker";
-        let test_data = [
-            0, 0, 0, 0, 0, 0, 240, 191, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0,
-            255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0,
-        ];
-        let result = Statedump::parse_statedump_object(&test_data, name);
-        assert_eq!(result, "{\"thermalLevel\": -1, \"reachability: \"kReachabilityLarge\", \"airplaneMode\": false, \"batteryData\":{\"wasConnected\": false, \"charged\": false, \"level\": -1, \"connected\": false, \"chargerType\": \"kChargerTypeUnknown\"}, \"restrictedMode\": false, \"batterySaverModeEnabled\": false, \"push_service\":false}");
+        let mut statedump = test_statedump();
+        let decoders = ObjectDecoderRegistry::new();
+        let result = statedump.parse_statedump_object(&decoders);
+        assert_eq!(
+            result,
+            json!({
+                "thermalLevel": -1,
+                "reachability": "kReachabilityLarge",
+                "airplaneMode": false,
+                "batteryData": {
+                    "wasConnected": false,
+                    "charged": false,
+                    "level": -1.0,
+                    "connected": false,
+                    "chargerType": "kChargerTypeUnknown"
+                },
+                "restrictedMode": false,
+                "batterySaverModeEnabled": false,
+                "push_service": false
+            })
+        );
+
+        statedump.unknown_object_type_string_2 = String::from("unknown");
+        statedump.unknown_name = String::from("AcmeTracker");
+        let result = statedump.parse_statedump_object(&decoders);
+        assert_eq!(result, "Unsupported Statedump object: AcmeTracker");
+    }
+
+    #[test]
+    fn test_parse_statedump_data() {
+        let mut options = LogOptions::default();
+        let mut statedump = test_statedump();
+        assert!(statedump
+            .parse_statedump_data(&options)
+            .contains("\"reachability\":\"kReachabilityLarge\""));
+
+        // Field 1 string "internal"
+        statedump.unknown_data_type = 2;
+        statedump.unknown_name = String::from("BatteryState");
+        statedump.statedump_data = [&[10, 8], b"internal".as_slice()].concat();
+        assert_eq!(
+            statedump.parse_statedump_data(&options),
+            "{\"1\":{\"type\":\"string\",\"value\":\"internal\",\"wire_type\":\"length_delimited\"}}"
+        );
+
+        // message test.Battery { string name = 1; }
+        let field = [b"\x0a\x04name".as_slice(), &[24, 1, 32, 1, 40, 9]].concat();
+        let message = [b"\x0a\x07Battery\x12\x0c".as_slice(), &field].concat();
+        let file = [b"\x12\x04test\x22\x17".as_slice(), &message].concat();
+        let descriptor_set = [b"\x0a\x1f".as_slice(), &file].concat();
+        options.protobuf_schema = Some(ProtobufSchema::from_descriptor_set(&descriptor_set).unwrap());
+        assert!(statedump.parse_statedump_data(&options).starts_with("{\"1\":"));
+
+        options
+            .protobuf_types
+            .insert(String::from("BatteryState"), String::from("test.Battery"));
+        assert_eq!(
+            statedump.parse_statedump_data(&options),
+            "{\"name\":\"internal\"}"
+        );
+    }
+
+    #[test]
+    fn test_parse_statedump_data_keyed_archive() {
+        let mut top = Dictionary::new();
+        top.insert(String::from("root"), Value::Uid(Uid::new(1)));
+        let mut archive = Dictionary::new();
+        archive.insert(String::from("$archiver"), Value::from("NSKeyedArchiver"));
+        archive.insert(String::from("$top"), Value::Dictionary(top));
+        archive.insert(
+            String::from("$objects"),
+            Value::Array(vec![Value::from("$null"), Value::from("battery")]),
+        );
+
+        let mut statedump = test_statedump();
+        statedump.unknown_data_type = 1;
+        statedump.statedump_data = Vec::new();
+        Value::Dictionary(archive)
+            .to_writer_binary(&mut statedump.statedump_data)
+            .unwrap();
+        assert_eq!(
+            statedump.parse_statedump_data(&LogOptions::default()),
+            "\"battery\""
+        );
+    }
+
+    #[test]
+    fn test_parse_statedump_protobuf() {
+        // Field 1 varint 150, field 2 string "test"
+        let test_data = [8, 150, 1, 18, 4, 116, 101, 115, 116];
+        let result = Statedump::parse_statedump_protobuf(&test_data);
+        assert_eq!(result, "{\"1\":{\"value\":150,\"wire_type\":\"varint\"},\"2\":{\"type\":\"string\",\"value\":\"test\",\"wire_type\":\"length_delimited\"}}");
+
+        let result = Statedump::parse_statedump_protobuf(&[8]);
+        assert_eq!(result, "Failed to get protocol buffer data");
     }
 }
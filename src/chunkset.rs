@@ -183,15 +183,15 @@ impl ChunksetChunk {
         //             err
         //         ),
         //     }
-        // } else if chunk_type == statedump_chunk {
-        //     let statedump_results = Statedump::parse_statedump(data);
-        //     match statedump_results {
-        //         Ok((_, statedump)) => unified_log_data.statedump.push(statedump),
-        //         Err(err) => error!(
-        //             "[macos-unifiedlogs] Failed to parse statedump log entry (chunk): {:?}",
-        //             err
-        //         ),
-        //     }
+        } else if chunk_type == statedump_chunk {
+            let statedump_results = Statedump::parse_statedump(data);
+            match statedump_results {
+                Ok((_, statedump)) => unified_log_data.statedump.push(statedump),
+                Err(err) => error!(
+                    "[macos-unifiedlogs] Failed to parse statedump log entry (chunk): {:?}",
+                    err
+                ),
+            }
         // } else if chunk_type == simpledump_chunk {
         //     let simpledump_results = SimpleDump::parse_simpledump(data);
         //     match simpledump_results {
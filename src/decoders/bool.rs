@@ -21,18 +21,9 @@ pub(crate) fn lowercase_bool(bool_data: &str) -> String {
     String::from("true")
 }
 
-/// Return int value to bool
-pub(crate) fn lowercase_int_bool(bool_data: &u8) -> String {
-    let false_bool = 0;
-    if bool_data == &false_bool {
-        return String::from("false");
-    }
-    String::from("true")
-}
-
 #[cfg(test)]
 mod tests {
-    use crate::decoders::bool::{lowercase_bool, lowercase_int_bool, uppercase_bool};
+    use crate::decoders::bool::{lowercase_bool, uppercase_bool};
 
     #[test]
     fn test_uppercase_bool() {
@@ -55,11 +46,4 @@ mod tests {
         results = lowercase_bool(test_data);
         assert_eq!(results, "true");
     }
-
-    #[test]
-    fn test_lowercase_int_bool() {
-        let test_data = 0;
-        let results = lowercase_int_bool(&test_data);
-        assert_eq!(results, "false");
-    }
 }
@@ -7,130 +7,196 @@
 
 use log::warn;
 
-/// Convert Darwin errno codes to message
+/// Convert Darwin errno codes to message. Ex: `[32: Broken pipe]`
 pub(crate) fn errno_codes(errno: &str) -> String {
-    // Found at https://github.com/apple/darwin-xnu/blob/main/bsd/sys/errno.h
+    // Kernel internal codes. Found at https://github.com/apple/darwin-xnu/blob/main/bsd/sys/errno.h
     let errno_message = match errno {
-        "0" => "Success",
-        "1" => "Operation not permitted",
-        "2" => "No such file or directory",
-        "3" => "No such process",
-        "4" => "Interrupted system call",
-        "5" => "Input/Output error",
-        "6" => "Device not configured",
-        "7" => "Argument list too long",
-        "8" => "Exec format error",
-        "9" => "Bad file descriptor",
-        "10" => "No child processes",
-        "11" => "Resource deadlock avoided",
-        "12" => "Cannot allocate memory",
-        "13" => "Permission denied",
-        "14" => "Bad address",
-        "15" => "Block device required",
-        "16" => "Device / Resource busy",
-        "17" => "File exists",
-        "18" => "Cross-device link",
-        "19" => "Operation not supported by device",
-        "20" => "Not a directory",
-        "21" => "Is a directory",
-        "22" => "Invalid arguement",
-        "23" => "Too many open files in system",
-        "24" => "Too many open files",
-        "25" => "Inappropriate ioctl for devices",
-        "26" => "Text file busy",
-        "27" => "File too large",
-        "28" => "No space left on device",
-        "29" => "Illegal seek",
-        "30" => "Read-only filesystem",
-        "31" => "Too many link",
-        "32" => "Broken pipe",
-        "33" => "Numerical argument out of domain",
-        "34" => "Result too large",
-        "35" => "Resource temporarily unavailable, operation would block",
-        "36" => "Operation now in progress",
-        "37" => "Operation already in progress",
-        "38" => "Socket operation on non-socket",
-        "39" => "Destination address required",
-        "40" => "Message too long",
-        "41" => "Protocol wrong type for socket",
-        "42" => "Protocol not available",
-        "43" => "Protocol not supported",
-        "44" => "Socket type not supported",
-        "45" => "Operation not supported, Operation not supported on socket",
-        "46" => "Protocol family not supported",
-        "47" => "Address family not supported by protocol family",
-        "48" => "Address already in use",
-        "49" => "Can't assign requested address",
-        "50" => "Network is down",
-        "51" => "Network is unreachable",
-        "52" => "Network dropped connection on reset",
-        "53" => "Software caused connection abort",
-        "54" => "Connection reset by peer",
-        "55" => "No buffer space available",
-        "56" => "Socket is already connected",
-        "57" => "Socket is not connected",
-        "58" => "Can't send after socket shutdown",
-        "59" => "Too many references: can't splice",
-        "60" => "Operation timed out",
-        "61" => "Connection refused",
-        "62" => "Too many levels of symbolic links",
-        "63" => "File name too long",
-        "64" => "Host is down",
-        "65" => "No route to host",
-        "66" => "Directory not empty",
-        "67" => "Too many processes",
-        "68" => "Too many users",
-        "69" => "Disc quota exceeded",
-        "70" => "Stale NFS file handle",
-        "71" => "Too many levels of remote in path",
-        "72" => "RPC struct is bad",
-        "73" => "RPC version wrong",
-        "74" => "RPC prog. not avail",
-        "75" => "Program version wrong",
-        "76" => "Bad procedure for program",
-        "77" => "No locks available",
-        "78" => "Function not implemented",
-        "79" => "Inappropriate file type or format",
-        "80" => "Authentication error",
-        "81" => "Need authenticator",
-        "82" => "Device power is off",
-        "83" => "Device error, e.g. paper out",
-        "84" => "Value too large to be stored in data type",
-        "85" => "Bad executable",
-        "86" => "Bad CPU type in executable",
-        "87" => "Shared library version mismatch",
-        "88" => "Malformed Macho file",
-        "89" => "Operation canceled",
-        "90" => "Identifier removed",
-        "91" => "No message of desired type",
-        "92" => "Illegal byte sequence",
-        "93" => "Attribute not found",
-        "94" => "Bad message",
-        "95" => "Reserved",
-        "96" => "No message available on STREAM",
-        "97" => "Reserved",
-        "98" => "No STREAM resources",
-        "99" => "Not a STREAM",
-        "100" => "Protocol error",
-        "101" => "STREAM ioctl timeout",
-        "102" => "Operation not supported on socket",
-        "103" => "No such policy registered",
-        "104" => "State not recoverable",
-        "105" => "Previous owner died",
-        "106" => "Interface output queue is full, Must be equal largest errno",
-        "-1" => "Restart syscall",
-        "-2" => "Don't modify regs, just return",
-        "-5" => "Restart lookup under heavy vnode pressure/recycling",
-        "-6" => "Red drive open",
-        "-7" => "Keep looking",
-        "-8" => "Data less",
+        "-1" => String::from("Restart syscall"),
+        "-2" => String::from("Don't modify regs, just return"),
+        "-5" => String::from("Restart lookup under heavy vnode pressure/recycling"),
+        "-6" => String::from("Red drive open"),
+        "-7" => String::from("Keep looking"),
+        "-8" => String::from("Data less"),
+        _ => strerror(errno),
+    };
+    format!("[{}: {}]", errno, errno_message)
+}
+
+/// Signal names and descriptions, indexed by signal number
+// Found at https://github.com/apple/darwin-xnu/blob/main/bsd/sys/signal.h
+const SIGNALS: [(&str, &str); 32] = [
+    ("", ""),
+    ("sighup", "Hangup"),
+    ("sigint", "Interrupt"),
+    ("sigquit", "Quit"),
+    ("sigill", "Illegal Instruction"),
+    ("sigtrap", "Trace/BPT Trap"),
+    ("sigabrt", "Abort Trap"),
+    ("sigemt", "EMT Trap"),
+    ("sigfpe", "Floating Point Exception"),
+    ("sigkill", "Killed"),
+    ("sigbus", "Bus Error"),
+    ("sigsegv", "Segmentation Fault"),
+    ("sigsys", "Bad System Call"),
+    ("sigpipe", "Broken Pipe"),
+    ("sigalrm", "Alarm Clock"),
+    ("sigterm", "Terminated"),
+    ("sigurg", "Urgent I/O Condition"),
+    ("sigstop", "Suspended (Signal)"),
+    ("sigtstp", "Suspended"),
+    ("sigcont", "Continued"),
+    ("sigchld", "Child Exited"),
+    ("sigttin", "Stopped (TTY Input)"),
+    ("sigttou", "Stopped (TTY Output)"),
+    ("sigio", "I/O Possible"),
+    ("sigxcpu", "Cputime Limit Exceeded"),
+    ("sigxfsz", "Filesize Limit Exceeded"),
+    ("sigvtalrm", "Virtual Timer Expired"),
+    ("sigprof", "Profiling Timer Expired"),
+    ("sigwinch", "Window Size Changes"),
+    ("siginfo", "Information Request"),
+    ("sigusr1", "User Defined Signal 1"),
+    ("sigusr2", "User Defined Signal 2"),
+];
+
+/// Convert Darwin signal numbers to message. Ex: `[sigsegv: Segmentation Fault]`
+pub(crate) fn signal(signal_number: &str) -> String {
+    match signal_number.parse::<usize>() {
+        Ok(value) if value != 0 && value < SIGNALS.len() => {
+            let (name, description) = SIGNALS[value];
+            format!("[{}: {}]", name, description)
+        }
         _ => {
-            warn!("[macos-unifiedlogs] Unknown darwin errno code: {}", errno);
-            return format!("Unknown errno: {}", errno);
+            warn!(
+                "[macos-unifiedlogs] Unknown darwin signal: {}",
+                signal_number
+            );
+            format!("[{}: Unknown Signal]", signal_number)
         }
-    };
+    }
+}
 
-    errno_message.to_string()
+/// Error strings returned by `strerror()` on macOS, indexed by errno
+// Found at https://github.com/apple-oss-distributions/Libc/blob/main/gen/FreeBSD/errlst.c
+const STRERROR_MESSAGES: [&str; 107] = [
+    "Undefined error: 0",
+    "Operation not permitted",
+    "No such file or directory",
+    "No such process",
+    "Interrupted system call",
+    "Input/output error",
+    "Device not configured",
+    "Argument list too long",
+    "Exec format error",
+    "Bad file descriptor",
+    "No child processes",
+    "Resource deadlock avoided",
+    "Cannot allocate memory",
+    "Permission denied",
+    "Bad address",
+    "Block device required",
+    "Resource busy",
+    "File exists",
+    "Cross-device link",
+    "Operation not supported by device",
+    "Not a directory",
+    "Is a directory",
+    "Invalid argument",
+    "Too many open files in system",
+    "Too many open files",
+    "Inappropriate ioctl for device",
+    "Text file busy",
+    "File too large",
+    "No space left on device",
+    "Illegal seek",
+    "Read-only file system",
+    "Too many links",
+    "Broken pipe",
+    "Numerical argument out of domain",
+    "Result too large",
+    "Resource temporarily unavailable",
+    "Operation now in progress",
+    "Operation already in progress",
+    "Socket operation on non-socket",
+    "Destination address required",
+    "Message too long",
+    "Protocol wrong type for socket",
+    "Protocol not available",
+    "Protocol not supported",
+    "Socket type not supported",
+    "Operation not supported",
+    "Protocol family not supported",
+    "Address family not supported by protocol family",
+    "Address already in use",
+    "Can't assign requested address",
+    "Network is down",
+    "Network is unreachable",
+    "Network dropped connection on reset",
+    "Software caused connection abort",
+    "Connection reset by peer",
+    "No buffer space available",
+    "Socket is already connected",
+    "Socket is not connected",
+    "Can't send after socket shutdown",
+    "Too many references: can't splice",
+    "Operation timed out",
+    "Connection refused",
+    "Too many levels of symbolic links",
+    "File name too long",
+    "Host is down",
+    "No route to host",
+    "Directory not empty",
+    "Too many processes",
+    "Too many users",
+    "Disc quota exceeded",
+    "Stale NFS file handle",
+    "Too many levels of remote in path",
+    "RPC struct is bad",
+    "RPC version wrong",
+    "RPC prog. not avail",
+    "Program version wrong",
+    "Bad procedure for program",
+    "No locks available",
+    "Function not implemented",
+    "Inappropriate file type or format",
+    "Authentication error",
+    "Need authenticator",
+    "Device power is off",
+    "Device error",
+    "Value too large to be stored in data type",
+    "Bad executable (or shared library)",
+    "Bad CPU type in executable",
+    "Shared library version mismatch",
+    "Malformed Mach-o file",
+    "Operation canceled",
+    "Identifier removed",
+    "No message of desired type",
+    "Illegal byte sequence",
+    "Attribute not found",
+    "Bad message",
+    "EMULTIHOP (Reserved)",
+    "No message available on STREAM",
+    "ENOLINK (Reserved)",
+    "No STREAM resources",
+    "Not a STREAM",
+    "Protocol error",
+    "STREAM ioctl timeout",
+    "Operation not supported on socket",
+    "Policy not found",
+    "State not recoverable",
+    "Previous owner died",
+    "Interface output queue is full",
+];
+
+/// Convert an errno value to the `strerror()` message. Used for `%m` formatters, which `log show` renders the same way
+pub(crate) fn strerror(errno: &str) -> String {
+    match errno.parse::<usize>() {
+        Ok(value) if value < STRERROR_MESSAGES.len() => STRERROR_MESSAGES[value].to_string(),
+        _ => {
+            warn!("[macos-unifiedlogs] Unknown darwin errno code: {}", errno);
+            format!("Unknown error: {}", errno)
+        }
+    }
 }
 
 /// Parse UNIX permissions to string version
@@ -153,28 +219,38 @@ pub(crate) fn permission(permissions: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::decoders::darwin::{errno_codes, permission};
+    use crate::decoders::darwin::{errno_codes, permission, signal, strerror};
 
     #[test]
     fn test_errno_codes() {
         let mut test_data = "1";
         let mut result = errno_codes(test_data);
-        assert_eq!(result, "Operation not permitted");
+        assert_eq!(result, "[1: Operation not permitted]");
 
-        test_data = "35";
+        test_data = "32";
         result = errno_codes(test_data);
-        assert_eq!(
-            result,
-            "Resource temporarily unavailable, operation would block"
-        );
+        assert_eq!(result, "[32: Broken pipe]");
 
         test_data = "58";
         result = errno_codes(test_data);
-        assert_eq!(result, "Can't send after socket shutdown");
+        assert_eq!(result, "[58: Can't send after socket shutdown]");
 
         test_data = "82";
         result = errno_codes(test_data);
-        assert_eq!(result, "Device power is off");
+        assert_eq!(result, "[82: Device power is off]");
+
+        test_data = "-1";
+        result = errno_codes(test_data);
+        assert_eq!(result, "[-1: Restart syscall]");
+    }
+
+    #[test]
+    fn test_signal() {
+        assert_eq!(signal("11"), "[sigsegv: Segmentation Fault]");
+        assert_eq!(signal("9"), "[sigkill: Killed]");
+        assert_eq!(signal("31"), "[sigusr2: User Defined Signal 2]");
+        assert_eq!(signal("0"), "[0: Unknown Signal]");
+        assert_eq!(signal("64"), "[64: Unknown Signal]");
     }
 
     #[test]
@@ -195,4 +271,14 @@ mod tests {
         result = permission(test_data);
         assert_eq!(result, "-r--------");
     }
+
+    #[test]
+    fn test_strerror() {
+        assert_eq!(strerror("2"), "No such file or directory");
+        assert_eq!(strerror("35"), "Resource temporarily unavailable");
+        assert_eq!(strerror("106"), "Interface output queue is full");
+        assert_eq!(strerror("0"), "Undefined error: 0");
+        assert_eq!(strerror("107"), "Unknown error: 107");
+        assert_eq!(strerror("-1"), "Unknown error: -1");
+    }
 }
@@ -9,7 +9,7 @@ use crate::{
     chunks::firehose::firehose_log::FirehoseItemInfo,
     decoders::{
         bool::{lowercase_bool, uppercase_bool},
-        darwin::{errno_codes, permission},
+        darwin::{errno_codes, permission, signal},
         dns::{
             dns_acceptable, dns_addrmv, dns_counts, dns_getaddrinfo_opts, dns_idflags, dns_ip_addr,
             dns_protocol, dns_reason, dns_records, dns_yes_no, get_dns_mac_addr, get_domain_name,
@@ -21,19 +21,294 @@ use crate::{
         },
         network::{ipv_four, ipv_six, sockaddr},
         opendirectory::{errors, member_details, member_id_type, sid_details},
-        time::parse_time,
+        time::{parse_time, parse_timespec, parse_timeval},
+        units::{bitrate, bytes, iec_bitrate, iec_bytes},
         uuid::parse_uuid,
     },
 };
+use crate::util::decode_standard;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+type DecoderFn = fn(&str) -> String;
+
+/// Decoder for a custom `%{<annotation>}` log value. Ex: `%{bool}d`, `%{odtypes:mbr_details}d`
+///
+/// Functions with the signature `fn(&str) -> String` implement `ValueDecoder`
+pub trait ValueDecoder: Send + Sync {
+    /// Decode the value string of a log item (numbers as strings, binary data as base64). Return an empty string if the value could not be decoded, the value is then formatted like a regular printf value
+    fn decode(&self, value: &str) -> String;
+}
+
+impl<F> ValueDecoder for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn decode(&self, value: &str) -> String {
+        self(value)
+    }
+}
+
+/// Decoder for binary data (`%.*P`) with an annotation that has no registered `ValueDecoder`. Ex: `%{public, bluetooth:addr}.*P`
+///
+/// Functions with the signature `fn(&str, &[u8]) -> Option<String>` implement `BinaryDecoder`
+pub trait BinaryDecoder: Send + Sync {
+    /// Decode the data for the annotation (ex: `bluetooth:addr`, empty for `%{public}.*P`). Return None if the data could not be decoded, the data is then shown using the `BinaryRendering`
+    fn decode(&self, annotation: &str, data: &[u8]) -> Option<String>;
+}
+
+impl<F> BinaryDecoder for F
+where
+    F: Fn(&str, &[u8]) -> Option<String> + Send + Sync,
+{
+    fn decode(&self, annotation: &str, data: &[u8]) -> Option<String> {
+        self(annotation, data)
+    }
+}
+
+/// How binary data (`%.*P`) that is not decoded is shown in the log message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BinaryRendering {
+    /// Base64 string. Ex: `AQIDBA==`
+    #[default]
+    Base64,
+    /// Hex string. Ex: `01020304`
+    Hex,
+    /// Hexdump lines of 16 bytes like the `log` command. Ex: `00000000: 01 02 03 04 ....`
+    Hexdump,
+    /// Only the size is shown (ex: `<4 bytes>`), the data is in the typed `message_value` of the `LogData.message_entries` item
+    TypedOnly,
+}
+
+impl BinaryRendering {
+    /// Render the data
+    pub fn render(&self, data: &[u8]) -> String {
+        match self {
+            BinaryRendering::Base64 => crate::util::encode_standard(data),
+            BinaryRendering::Hex => data.iter().fold(String::new(), |mut hex, byte| {
+                let _ = write!(hex, "{:02x}", byte);
+                hex
+            }),
+            BinaryRendering::Hexdump => hexdump(data),
+            BinaryRendering::TypedOnly => format!("<{} bytes>", data.len()),
+        }
+    }
+}
+
+// Ex: 00000000: 04 00 04 02 50 f7 cf 2e ea ae 00 00 00 00 00 00 ....P...........
+fn hexdump(data: &[u8]) -> String {
+    let line_size = 16;
+    let mut lines = Vec::new();
+    for (index, line) in data.chunks(line_size).enumerate() {
+        let mut hex = String::new();
+        for byte in line {
+            let _ = write!(hex, "{:02x} ", byte);
+        }
+        let ascii: String = line
+            .iter()
+            .map(|byte| {
+                if byte.is_ascii_graphic() || *byte == b' ' {
+                    char::from(*byte)
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        lines.push(format!(
+            "{:08x}: {:<width$}{}",
+            index * line_size,
+            hex,
+            ascii,
+            width = line_size * 3
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Options for how values are shown in the log message, set with `LogOptions.format`
+#[derive(Default)]
+pub struct FormatOptions {
+    /// Show private values instead of `<private>`. Private values only exist in logs collected with private data enabled (ex: `log config --mode "private_data:on"`), otherwise `<private>` is still shown
+    pub reveal_private: bool,
+    /// How binary data (`%.*P`) that is not decoded is shown. Default is base64
+    pub binary_rendering: BinaryRendering,
+    /// Decoder for binary data (`%.*P`) with an annotation that has no registered decoder. Ex: `%{public}.*P`, `%{public, bluetooth:addr}.*P`
+    pub binary_decoder: Option<Box<dyn BinaryDecoder>>,
+}
+
+impl std::fmt::Debug for FormatOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FormatOptions")
+            .field("reveal_private", &self.reveal_private)
+            .field("binary_rendering", &self.binary_rendering)
+            .field("binary_decoder", &self.binary_decoder.is_some())
+            .finish()
+    }
+}
+
+impl FormatOptions {
+    /// Decode binary data with the binary decoder. The first token that is not a privacy option is the annotation
+    /// A formatter without `%{...}` (ex: `%.*P`) has an empty annotation
+    pub(crate) fn decode_binary(&self, format_string: &str, data: &[u8]) -> Option<String> {
+        let privacy_options = ["public", "private", "sensitive", "mask.hash"];
+        let annotation = if format_string.starts_with("%{") {
+            annotation_tokens(format_string)
+                .find(|token| !token.is_empty() && !privacy_options.contains(token))
+                .unwrap_or_default()
+        } else {
+            ""
+        };
+        self.binary_decoder.as_ref()?.decode(annotation, data)
+    }
+}
+
+/// Value decoders keyed on the exact annotation token in a `%{...}` formatter
+/// Ex: `%{public,uuid_t}.16P` is decoded by the decoder registered for `uuid_t`
+pub struct DecoderRegistry {
+    decoders: HashMap<String, Box<dyn ValueDecoder>>,
+}
+
+impl Default for DecoderRegistry {
+    fn default() -> Self {
+        DecoderRegistry::new()
+    }
+}
+
+impl std::fmt::Debug for DecoderRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DecoderRegistry")
+            .field("annotations", &self.annotations())
+            .finish()
+    }
+}
+
+impl DecoderRegistry {
+    /// Registry with the built-in bool, darwin, odtypes, location, network, mdns, time, unit and uuid decoders
+    pub fn new() -> DecoderRegistry {
+        let mut registry = DecoderRegistry::empty();
+        let builtin: [(&str, DecoderFn); 42] = [
+            ("BOOL", uppercase_bool),
+            ("bool", lowercase_bool),
+            ("uuid_t", parse_uuid),
+            ("darwin.errno", errno_codes),
+            ("errno", errno_codes),
+            ("darwin.mode", permission),
+            ("darwin.signal", signal),
+            ("odtypes:ODError", errors),
+            ("odtypes:mbridtype", member_id_type),
+            ("odtypes:mbr_details", member_details),
+            ("odtypes:nt_sid_t", sid_details),
+            (
+                "location:CLClientAuthorizationStatus",
+                client_authorization_status,
+            ),
+            (
+                "location:CLDaemonStatus_Type::Reachability",
+                daemon_status_type,
+            ),
+            ("location:CLSubHarvesterIdentifier", subharvester_identifier),
+            ("location:SqliteResult", sqlite),
+            (
+                "location:_CLClientManagerStateTrackerState",
+                client_manager_state_tracker_state,
+            ),
+            (
+                "location:_CLLocationManagerStateTrackerState",
+                location_manager_state_tracker_state,
+            ),
+            ("location:IOMessage", io_message),
+            ("network:in6_addr", ipv_six),
+            ("network:in_addr", ipv_four),
+            ("network:sockaddr", sockaddr),
+            ("time_t", parse_time),
+            ("timeval", parse_timeval),
+            ("timespec", parse_timespec),
+            ("bytes", bytes),
+            ("iec-bytes", iec_bytes),
+            ("bitrate", bitrate),
+            ("iec-bitrate", iec_bitrate),
+            ("mdns:dnshdr", parse_dns_header),
+            ("mdns:rd.svcb", get_service_binding),
+            ("mdnsresponder:domain_name", get_domain_name),
+            ("mdnsresponder:mac_addr", get_dns_mac_addr),
+            ("mdnsresponder:ip_addr", dns_ip_addr),
+            ("mdns:addrmv", dns_addrmv),
+            ("mdns:rrtype", dns_records),
+            ("mdns:nreason", dns_reason),
+            ("mdns:protocol", dns_protocol),
+            ("mdns:dns.idflags", dns_idflags),
+            ("mdns:dns.counts", dns_counts),
+            ("mdns:yesno", dns_yes_no),
+            ("mdns:acceptable", dns_acceptable),
+            ("mdns:gaiopts", dns_getaddrinfo_opts),
+        ];
+        for (annotation, decoder) in builtin {
+            registry.register(annotation, decoder);
+        }
+        registry
+    }
+
+    /// Registry without any decoders
+    pub fn empty() -> DecoderRegistry {
+        DecoderRegistry {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Register a decoder for an annotation token (ex: `mycompany:state`). Replaces any existing decoder for the token
+    pub fn register<D: ValueDecoder + 'static>(&mut self, annotation: &str, decoder: D) {
+        self.decoders
+            .insert(annotation.to_string(), Box::new(decoder));
+    }
+
+    /// Remove the decoder for an annotation token. Return false if no decoder was registered
+    pub fn unregister(&mut self, annotation: &str) -> bool {
+        self.decoders.remove(annotation).is_some()
+    }
+
+    /// Get the decoder for an annotation token
+    pub fn get(&self, annotation: &str) -> Option<&dyn ValueDecoder> {
+        self.decoders
+            .get(annotation)
+            .map(|decoder| decoder.as_ref())
+    }
+
+    /// Registered annotation tokens, sorted
+    pub fn annotations(&self) -> Vec<&str> {
+        let mut annotations: Vec<&str> = self.decoders.keys().map(String::as_str).collect();
+        annotations.sort_unstable();
+        annotations
+    }
+
+    /// Decode a value with the first registered token of a formatter. Ex: `%{public,uuid_t}.16P` or `%{public,uuid_t`
+    pub(crate) fn decode(&self, format_string: &str, value: &str) -> Option<String> {
+        annotation_tokens(format_string)
+            .find_map(|token| self.get(token))
+            .map(|decoder| decoder.decode(value))
+    }
+}
+
+// Tokens of the %{...} annotation. Ex: %{public, uuid_t}.16P -> ["public", "uuid_t"]
+fn annotation_tokens(format_string: &str) -> impl Iterator<Item = &str> {
+    format_string
+        .trim_start_matches('%')
+        .trim_start_matches('{')
+        .split('}')
+        .next()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+}
 
 /// Check if we support one of Apple's custom logging objects
 pub(crate) fn check_objects(
     format_string: &str,
-    message_values: &Vec<FirehoseItemInfo>,
+    message_values: &[FirehoseItemInfo],
     item_type: &u8,
     item_index: usize,
+    decoders: &DecoderRegistry,
+    format_options: &FormatOptions,
 ) -> String {
-    let mut message_value = String::new();
     let mut index = item_index;
     let precision_item = 0x12;
 
@@ -46,91 +321,37 @@ pub(crate) fn check_objects(
     }
 
     let masked_hash_type = 0xf2;
-    // Check if the log value is hashed or marked private
-    if (format_string.contains("mask.hash") && message_values[index].item_type == masked_hash_type)
-        || message_values[index].message_strings == "<private>"
-    {
+    // Hashed values are shown like log show. Ex: %{private, mask.hash}s -> <mask.hash: 'uwXwI1tMkCg3RDH4FxW3vA=='>
+    if message_values[index].item_type == masked_hash_type {
+        return format!("<mask.hash: '{}'>", message_values[index].message_strings);
+    }
+    // Check if the log value is marked private
+    if message_values[index].message_strings == "<private>" {
         return message_values[index].message_strings.to_owned();
     }
 
-    // Check if log value contains one the supported decoders
-    if format_string.contains("BOOL") {
-        message_value = uppercase_bool(&message_values[index].message_strings);
-    } else if format_string.contains("bool") {
-        message_value = lowercase_bool(&message_values[index].message_strings);
-    } else if format_string.contains("uuid_t") {
-        message_value = parse_uuid(&message_values[index].message_strings);
-    } else if format_string.contains("darwin.errno") {
-        message_value = errno_codes(&message_values[index].message_strings);
-    } else if format_string.contains("darwin.mode") {
-        message_value = permission(&message_values[index].message_strings);
-    } else if format_string.contains("odtypes:ODError") {
-        message_value = errors(&message_values[index].message_strings);
-    } else if format_string.contains("odtypes:mbridtype") {
-        message_value = member_id_type(&message_values[index].message_strings);
-    } else if format_string.contains("odtypes:mbr_details") {
-        message_value = member_details(&message_values[index].message_strings);
-    } else if format_string.contains("odtypes:nt_sid_t") {
-        message_value = sid_details(&message_values[index].message_strings);
-    } else if format_string.contains("location:CLClientAuthorizationStatus") {
-        message_value = client_authorization_status(&message_values[index].message_strings)
-    } else if format_string.contains("location:CLDaemonStatus_Type::Reachability") {
-        message_value = daemon_status_type(&message_values[index].message_strings);
-    } else if format_string.contains("location:CLSubHarvesterIdentifier") {
-        message_value = subharvester_identifier(&message_values[index].message_strings);
-    } else if format_string.contains("location:SqliteResult") {
-        message_value = sqlite(&message_values[index].message_strings);
-    } else if format_string.contains("location:_CLClientManagerStateTrackerState") {
-        message_value = client_manager_state_tracker_state(&message_values[index].message_strings);
-    } else if format_string.contains("location:_CLLocationManagerStateTrackerState") {
-        message_value =
-            location_manager_state_tracker_state(&message_values[index].message_strings);
-    } else if format_string.contains("network:in6_addr") {
-        message_value = ipv_six(&message_values[index].message_strings);
-    } else if format_string.contains("network:in_addr") {
-        message_value = ipv_four(&message_values[index].message_strings);
-    } else if format_string.contains("network:sockaddr") {
-        message_value = sockaddr(&message_values[index].message_strings);
-    } else if format_string.contains("time_t") {
-        message_value = parse_time(&message_values[index].message_strings);
-    } else if format_string.contains("mdns:dnshdr") {
-        message_value = parse_dns_header(&message_values[index].message_strings);
-    } else if format_string.contains("mdns:rd.svcb") {
-        message_value = get_service_binding(&message_values[index].message_strings);
-    } else if format_string.contains("location:IOMessage") {
-        message_value = io_message(&message_values[index].message_strings);
-    } else if format_string.contains("mdnsresponder:domain_name") {
-        message_value = get_domain_name(&message_values[index].message_strings);
-    } else if format_string.contains("mdnsresponder:mac_addr") {
-        message_value = get_dns_mac_addr(&message_values[index].message_strings);
-    } else if format_string.contains("mdnsresponder:ip_addr") {
-        message_value = dns_ip_addr(&message_values[index].message_strings);
-    } else if format_string.contains("mdns:addrmv") {
-        message_value = dns_addrmv(&message_values[index].message_strings);
-    } else if format_string.contains("mdns:rrtype") {
-        message_value = dns_records(&message_values[index].message_strings);
-    } else if format_string.contains("mdns:nreason") {
-        message_value = dns_reason(&message_values[index].message_strings);
-    } else if format_string.contains("mdns:protocol") {
-        message_value = dns_protocol(&message_values[index].message_strings);
-    } else if format_string.contains("mdns:dns.idflags") {
-        message_value = dns_idflags(&message_values[index].message_strings);
-    } else if format_string.contains("mdns:dns.counts") {
-        message_value = dns_counts(&message_values[index].message_strings);
-    } else if format_string.contains("mdns:yesno") {
-        message_value = dns_yes_no(&message_values[index].message_strings);
-    } else if format_string.contains("mdns:acceptable") {
-        message_value = dns_acceptable(&message_values[index].message_strings);
-    } else if format_string.contains("mdns:gaiopts") {
-        message_value = dns_getaddrinfo_opts(&message_values[index].message_strings);
-    }
-    message_value
+    // Check if log value contains one the registered decoders
+    if let Some(message) = decoders.decode(format_string, &message_values[index].message_strings) {
+        return message;
+    }
+
+    // Binary data without a registered decoder. Ex: %{public}.*P
+    let arbitrary_items = [0x30, 0x31, 0x32];
+    if format_string.ends_with('P') && arbitrary_items.contains(&message_values[index].item_type) {
+        if let Ok(data) = decode_standard(&message_values[index].message_strings) {
+            return format_options
+                .decode_binary(format_string, &data)
+                .unwrap_or_default();
+        }
+    }
+    String::new()
 }
 
 #[cfg(test)]
 mod tests {
-    use super::check_objects;
+    use super::{check_objects, BinaryRendering, DecoderRegistry, FormatOptions};
     use crate::chunks::firehose::firehose_log::FirehoseItemInfo;
+    use crate::message_value::MessageValue;
 
     #[test]
     fn test_check_objects_lowercase_bool() {
@@ -139,11 +360,19 @@ mod tests {
             message_strings: String::from("1"),
             item_type: 0,
             item_size: 4,
+            message_value: MessageValue::Signed(1),
         };
         let test_type = 0;
         let test_index = 0;
 
-        let results = check_objects(test_format, &vec![test_item_info], &test_type, test_index);
+        let results = check_objects(
+            test_format,
+            &[test_item_info],
+            &test_type,
+            test_index,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
         assert_eq!(results, "true")
     }
 
@@ -154,11 +383,19 @@ mod tests {
             message_strings: String::from("1"),
             item_type: 0,
             item_size: 4,
+            message_value: MessageValue::Signed(1),
         };
         let test_type = 0;
         let test_index = 0;
 
-        let results = check_objects(test_format, &vec![test_item_info], &test_type, test_index);
+        let results = check_objects(
+            test_format,
+            &[test_item_info],
+            &test_type,
+            test_index,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
         assert_eq!(results, "YES")
     }
 
@@ -169,11 +406,21 @@ mod tests {
             message_strings: String::from("I/7///8vTG9jYWwvRGVmYXVsdAA="),
             item_type: 50,
             item_size: 0,
+            message_value: MessageValue::Bytes(
+                crate::util::decode_standard("I/7///8vTG9jYWwvRGVmYXVsdAA=").unwrap(),
+            ),
         };
         let test_type = 50; // 0x32
         let test_index = 0;
 
-        let results = check_objects(test_format, &vec![test_item_info], &test_type, test_index);
+        let results = check_objects(
+            test_format,
+            &[test_item_info],
+            &test_type,
+            test_index,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
         assert_eq!(results, "user: -2@/Local/Default");
     }
 
@@ -184,11 +431,21 @@ mod tests {
             message_strings: String::from("hZV+HTbETtKGqAZXvN3ikw=="),
             item_type: 50,
             item_size: 16,
+            message_value: MessageValue::Bytes(
+                crate::util::decode_standard("hZV+HTbETtKGqAZXvN3ikw==").unwrap(),
+            ),
         };
         let test_type = 50; // 0x32
         let test_index = 0;
 
-        let results = check_objects(test_format, &vec![test_item_info], &test_type, test_index);
+        let results = check_objects(
+            test_format,
+            &[test_item_info],
+            &test_type,
+            test_index,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
         assert_eq!(results, "85957E1D36C44ED286A80657BCDDE293")
     }
 
@@ -199,11 +456,19 @@ mod tests {
             message_strings: String::from("<private>"),
             item_type: 50,
             item_size: 16,
+            message_value: MessageValue::Private,
         };
         let test_type = 50; // 0x32
         let test_index = 0;
 
-        let results = check_objects(test_format, &vec![test_item_info], &test_type, test_index);
+        let results = check_objects(
+            test_format,
+            &[test_item_info],
+            &test_type,
+            test_index,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
         assert_eq!(results, "<private>")
     }
 
@@ -214,11 +479,172 @@ mod tests {
             message_strings: String::from("hash"),
             item_type: 242,
             item_size: 16,
+            message_value: MessageValue::Missing,
         };
         let test_type = 242; // 0x32
         let test_index = 0;
 
-        let results = check_objects(test_format, &vec![test_item_info], &test_type, test_index);
-        assert_eq!(results, "hash")
+        let results = check_objects(
+            test_format,
+            &[test_item_info],
+            &test_type,
+            test_index,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
+        assert_eq!(results, "<mask.hash: 'hash'>")
+    }
+
+    #[test]
+    fn test_registry_exact_token() {
+        let registry = DecoderRegistry::new();
+        assert_eq!(registry.annotations().len(), 42);
+        assert_eq!(
+            registry.decode("%{public, BOOL}d", "0"),
+            Some(String::from("NO"))
+        );
+        assert_eq!(
+            registry.decode("%{time_t}d", "0"),
+            Some(String::from("1970-01-01 00:00:00"))
+        );
+        // Substrings of a registered token are not decoded
+        assert_eq!(registry.decode("%{public,boolean}d", "1"), None);
+        assert_eq!(registry.decode("%{public,mybool}d", "1"), None);
+        assert_eq!(registry.decode("%{public}s", "1"), None);
+    }
+
+    #[test]
+    fn test_registry_custom_decoder() {
+        let mut registry = DecoderRegistry::new();
+        registry.register("acme:state", |value: &str| match value {
+            "1" => String::from("running"),
+            _ => String::new(),
+        });
+        assert!(registry.get("acme:state").is_some());
+
+        let test_item_info = FirehoseItemInfo {
+            message_strings: String::from("1"),
+            item_type: 0,
+            item_size: 4,
+            message_value: MessageValue::Signed(1),
+        };
+        let results = check_objects(
+            "%{public,acme:state}d",
+            &[test_item_info],
+            &0,
+            0,
+            &registry,
+            &FormatOptions::default(),
+        );
+        assert_eq!(results, "running");
+
+        assert!(registry.unregister("bool"));
+        assert!(!registry.unregister("bool"));
+        assert_eq!(registry.decode("%{bool}d", "1"), None);
+        assert!(DecoderRegistry::empty().annotations().is_empty());
+    }
+
+    #[test]
+    fn test_check_objects_timeval() {
+        let test_format = "%{public,timeval}.*P";
+        let test_item_info = vec![
+            FirehoseItemInfo {
+                message_strings: String::from("16"),
+                item_type: 0x12,
+                item_size: 0,
+                message_value: MessageValue::Unsigned(16),
+            },
+            FirehoseItemInfo {
+                message_strings: String::from("8VaVVgAAAABc0AsAAAAAAA=="),
+                item_type: 0x32,
+                item_size: 16,
+                message_value: MessageValue::Bytes(
+                    crate::util::decode_standard("8VaVVgAAAABc0AsAAAAAAA==").unwrap(),
+                ),
+            },
+        ];
+
+        let results = check_objects(
+            test_format,
+            &test_item_info,
+            &0x12,
+            0,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
+        assert_eq!(results, "2016-01-12 19:41:37.774236")
+    }
+
+    #[test]
+    fn test_binary_rendering() {
+        let test_data = b"\x01\x02\x03\x04abcdefghijkl\xff!";
+        assert_eq!(BinaryRendering::Base64.render(&test_data[..4]), "AQIDBA==");
+        assert_eq!(BinaryRendering::Hex.render(&test_data[..4]), "01020304");
+        assert_eq!(BinaryRendering::TypedOnly.render(test_data), "<18 bytes>");
+        assert_eq!(
+            BinaryRendering::Hexdump.render(test_data),
+            "00000000: 01 02 03 04 61 62 63 64 65 66 67 68 69 6a 6b 6c ....abcdefghijkl\n00000010: ff 21                                           .!"
+        );
+    }
+
+    #[test]
+    fn test_check_objects_binary_decoder() {
+        let test_item_info = vec![
+            FirehoseItemInfo {
+                message_strings: String::new(),
+                item_type: 0x12,
+                item_size: 6,
+                message_value: MessageValue::Unsigned(6),
+            },
+            FirehoseItemInfo {
+                message_strings: String::from("ABEiM0RV"),
+                item_type: 0x32,
+                item_size: 6,
+                message_value: MessageValue::Bytes(vec![0, 0x11, 0x22, 0x33, 0x44, 0x55]),
+            },
+        ];
+        let test_format = "%{public, bluetooth:addr}.*P";
+
+        // Not decoded, the binary rendering is used when formatting
+        let registry = DecoderRegistry::new();
+        let mut format_options = FormatOptions::default();
+        let results = check_objects(
+            test_format,
+            &test_item_info,
+            &0x12,
+            0,
+            &registry,
+            &format_options,
+        );
+        assert_eq!(results, "");
+
+        format_options.binary_decoder = Some(Box::new(
+            |annotation: &str, data: &[u8]| -> Option<String> {
+                if annotation != "bluetooth:addr" {
+                    return None;
+                }
+                let bytes: Vec<String> = data.iter().map(|byte| format!("{:02X}", byte)).collect();
+                Some(bytes.join(":"))
+            },
+        ));
+        let results = check_objects(
+            test_format,
+            &test_item_info,
+            &0x12,
+            0,
+            &registry,
+            &format_options,
+        );
+        assert_eq!(results, "00:11:22:33:44:55");
+
+        let results = check_objects(
+            "%{public}.*P",
+            &test_item_info,
+            &0x12,
+            0,
+            &registry,
+            &format_options,
+        );
+        assert_eq!(results, "");
     }
 }
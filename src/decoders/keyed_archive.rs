@@ -0,0 +1,449 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Decode plist data (ex: Statedump plists and `%@` object arguments) to JSON
+//!
+//! `NSKeyedArchiver` archives store objects in a flat `$objects` array that reference each other by UID.
+//! The object graph is rebuilt starting from `$top`, common Foundation classes are converted to plain JSON values
+
+use crate::error::ParserError;
+use crate::util::{clean_uuid, decode_standard, encode_standard, hyphenate_uuid};
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use log::error;
+use plist::{Dictionary, Value};
+use serde_json::{json, Map};
+use std::cell::Cell;
+use std::time::SystemTime;
+
+/// Objects nested deeper than this are not resolved
+const MAX_DEPTH: usize = 64;
+/// UIDs resolved per archive. Objects referenced more than once are copied into each parent,
+/// so without a limit an archive sharing objects at every level expands exponentially
+const MAX_RESOLVED: usize = 4096;
+/// Seconds between UNIXEPOCH and 2001-01-01 (`NSDate` reference date)
+const APPLE_EPOCH: f64 = 978307200.0;
+
+/// Decode plist data to JSON. `NSKeyedArchiver` archives are resolved to the archived object
+pub fn decode_plist(data: &[u8]) -> Result<serde_json::Value, ParserError> {
+    let plist_data: Value = match plist::from_bytes(data) {
+        Ok(result) => result,
+        Err(err) => {
+            error!("[macos-unifiedlogs] Failed to parse plist data: {:?}", err);
+            return Err(ParserError::Plist);
+        }
+    };
+    if is_keyed_archive(&plist_data) {
+        return resolve_keyed_archive(&plist_data);
+    }
+    Ok(plist_to_json(&plist_data))
+}
+
+/// Check if a plist is an `NSKeyedArchiver` archive
+pub fn is_keyed_archive(plist_data: &Value) -> bool {
+    plist_data
+        .as_dictionary()
+        .and_then(|archive| archive.get("$archiver"))
+        .and_then(Value::as_string)
+        == Some("NSKeyedArchiver")
+}
+
+/// Rebuild the object graph of an `NSKeyedArchiver` archive. An archive with only a `root` object returns the root object
+pub fn resolve_keyed_archive(plist_data: &Value) -> Result<serde_json::Value, ParserError> {
+    let archive = match plist_data.as_dictionary() {
+        Some(result) if is_keyed_archive(plist_data) => result,
+        _ => {
+            error!("[macos-unifiedlogs] Plist data is not an NSKeyedArchiver archive");
+            return Err(ParserError::Plist);
+        }
+    };
+    let (Some(objects), Some(top)) = (
+        archive.get("$objects").and_then(Value::as_array),
+        archive.get("$top").and_then(Value::as_dictionary),
+    ) else {
+        error!("[macos-unifiedlogs] NSKeyedArchiver archive missing $objects or $top");
+        return Err(ParserError::Plist);
+    };
+
+    let resolver = KeyedArchive {
+        objects,
+        resolved: Cell::new(0),
+    };
+    let mut stack = Vec::new();
+    if top.len() == 1 {
+        if let Some(root) = top.get("root") {
+            return Ok(resolver.resolve(root, &mut stack));
+        }
+    }
+    let mut results = Map::new();
+    for (key, value) in top {
+        results.insert(key.to_owned(), resolver.resolve(value, &mut stack));
+    }
+    Ok(serde_json::Value::Object(results))
+}
+
+/// Decode a base64 encoded message item if it is a binary plist. Returns the JSON string
+pub(crate) fn parse_plist_item(data: &str) -> Option<String> {
+    let decoded_data = decode_standard(data).ok()?;
+    if !decoded_data.starts_with(b"bplist") {
+        return None;
+    }
+    decode_plist(&decoded_data)
+        .ok()
+        .map(|results| results.to_string())
+}
+
+struct KeyedArchive<'a> {
+    objects: &'a [Value],
+    /// Number of UIDs resolved so far
+    resolved: Cell<usize>,
+}
+
+impl KeyedArchive<'_> {
+    /// Resolve a value, following UIDs into `$objects`. The stack contains the UIDs being resolved to stop reference cycles
+    /// Once `MAX_RESOLVED` UIDs are resolved the remaining references are returned as `$ref`
+    fn resolve(&self, value: &Value, stack: &mut Vec<u64>) -> serde_json::Value {
+        let Some(uid) = value.as_uid().map(|uid| uid.get()) else {
+            return self.resolve_object(value, stack);
+        };
+        if stack.contains(&uid) || stack.len() >= MAX_DEPTH || self.resolved.get() >= MAX_RESOLVED {
+            return json!({ "$ref": uid });
+        }
+        self.resolved.set(self.resolved.get() + 1);
+        let Some(object) = usize::try_from(uid)
+            .ok()
+            .and_then(|index| self.objects.get(index))
+        else {
+            return serde_json::Value::Null;
+        };
+
+        stack.push(uid);
+        let results = self.resolve_object(object, stack);
+        stack.pop();
+        results
+    }
+
+    fn resolve_object(&self, object: &Value, stack: &mut Vec<u64>) -> serde_json::Value {
+        match object {
+            Value::String(value) if value == "$null" => serde_json::Value::Null,
+            Value::Array(values) => serde_json::Value::Array(
+                values
+                    .iter()
+                    .map(|value| self.resolve(value, stack))
+                    .collect(),
+            ),
+            Value::Dictionary(dictionary) => match dictionary.get("$class") {
+                Some(class) => self.resolve_class(dictionary, class, stack),
+                None => {
+                    let mut results = Map::new();
+                    for (key, value) in dictionary {
+                        results.insert(key.to_owned(), self.resolve(value, stack));
+                    }
+                    serde_json::Value::Object(results)
+                }
+            },
+            _ => plist_to_json(object),
+        }
+    }
+
+    /// Convert an archived object to JSON based on its class
+    fn resolve_class(
+        &self,
+        dictionary: &Dictionary,
+        class: &Value,
+        stack: &mut Vec<u64>,
+    ) -> serde_json::Value {
+        let class_info = class
+            .as_uid()
+            .and_then(|uid| usize::try_from(uid.get()).ok())
+            .and_then(|index| self.objects.get(index))
+            .and_then(Value::as_dictionary);
+        let class_name = class_info
+            .and_then(|info| info.get("$classname"))
+            .and_then(Value::as_string)
+            .unwrap_or_default();
+        // $classes contains the class and its superclasses. Ex: NSMutableDictionary, NSDictionary, NSObject
+        let classes: Vec<&str> = class_info
+            .and_then(|info| info.get("$classes"))
+            .and_then(Value::as_array)
+            .map(|classes| classes.iter().filter_map(Value::as_string).collect())
+            .unwrap_or_else(|| vec![class_name]);
+        let is_class = |name: &str| classes.contains(&name);
+
+        let field = |key: &str| dictionary.get(key);
+        if is_class("NSDictionary") {
+            if let (Some(keys), Some(values)) = (
+                field("NS.keys").and_then(Value::as_array),
+                field("NS.objects").and_then(Value::as_array),
+            ) {
+                let mut results = Map::new();
+                for (key, value) in keys.iter().zip(values) {
+                    let key = match self.resolve(key, stack) {
+                        serde_json::Value::String(key) => key,
+                        key => key.to_string(),
+                    };
+                    results.insert(key, self.resolve(value, stack));
+                }
+                return serde_json::Value::Object(results);
+            }
+        }
+        if is_class("NSArray") || is_class("NSSet") || is_class("NSOrderedSet") {
+            if let Some(values) = field("NS.objects").and_then(Value::as_array) {
+                return serde_json::Value::Array(
+                    values
+                        .iter()
+                        .map(|value| self.resolve(value, stack))
+                        .collect(),
+                );
+            }
+        }
+        if is_class("NSString") || is_class("NSAttributedString") {
+            if let Some(value) = field("NS.string").or_else(|| field("NSString")) {
+                return self.resolve(value, stack);
+            }
+            if let Some(bytes) = field("NS.bytes").and_then(Value::as_data) {
+                return json!(String::from_utf8_lossy(bytes));
+            }
+        }
+        if is_class("NSDate") {
+            if let Some(time) = field("NS.time").and_then(Value::as_real) {
+                return json!(apple_time(time));
+            }
+        }
+        if is_class("NSData") {
+            if let Some(data) = field("NS.data") {
+                return self.resolve(data, stack);
+            }
+        }
+        if is_class("NSUUID") {
+            if let Some(bytes) = field("NS.uuidbytes").and_then(Value::as_data) {
+                return json!(hyphenate_uuid(&clean_uuid(&format!("{:02X?}", bytes))));
+            }
+        }
+        if is_class("NSURL") {
+            let relative = field("NS.relative").map(|value| self.resolve(value, stack));
+            let base = field("NS.base").map(|value| self.resolve(value, stack));
+            match (base, relative) {
+                (
+                    Some(serde_json::Value::String(base)),
+                    Some(serde_json::Value::String(relative)),
+                ) => return json!(format!("{}{}", base, relative)),
+                (_, Some(relative)) => return relative,
+                _ => {}
+            }
+        }
+        if is_class("NSNull") {
+            return serde_json::Value::Null;
+        }
+
+        // Other classes are kept as objects with their class name and resolved fields
+        let mut results = Map::new();
+        results.insert(String::from("$class"), json!(class_name));
+        for (key, value) in dictionary {
+            if key == "$class" {
+                continue;
+            }
+            results.insert(key.to_owned(), self.resolve(value, stack));
+        }
+        serde_json::Value::Object(results)
+    }
+}
+
+/// Convert plist values to JSON. Data is base64 encoded and dates are ISO-8601 strings
+fn plist_to_json(plist_data: &Value) -> serde_json::Value {
+    match plist_data {
+        Value::Array(values) => {
+            serde_json::Value::Array(values.iter().map(plist_to_json).collect())
+        }
+        Value::Dictionary(dictionary) => {
+            let mut results = Map::new();
+            for (key, value) in dictionary {
+                results.insert(key.to_owned(), plist_to_json(value));
+            }
+            serde_json::Value::Object(results)
+        }
+        Value::Boolean(value) => json!(value),
+        Value::Data(data) => json!(encode_standard(data)),
+        Value::Date(date) => json!(DateTime::<Utc>::from(SystemTime::from(*date))
+            .to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+        Value::Real(value) => json!(value),
+        Value::Integer(value) => match value.as_signed() {
+            Some(value) => json!(value),
+            None => json!(value.as_unsigned()),
+        },
+        Value::String(value) => json!(value),
+        Value::Uid(uid) => json!({ "$ref": uid.get() }),
+        _ => serde_json::Value::Null,
+    }
+}
+
+/// Convert seconds since 2001-01-01 to an ISO-8601 string
+fn apple_time(time: f64) -> String {
+    let nanoseconds = (time + APPLE_EPOCH) * 1000000000.0;
+    Utc.timestamp_nanos(nanoseconds as i64)
+        .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_plist, parse_plist_item, resolve_keyed_archive};
+    use crate::util::encode_standard;
+    use plist::{Dictionary, Uid, Value};
+    use serde_json::json;
+
+    fn class(name: &str, superclasses: &[&str]) -> Value {
+        let mut classes = vec![Value::from(name)];
+        classes.extend(superclasses.iter().map(|class| Value::from(*class)));
+        let mut info = Dictionary::new();
+        info.insert(String::from("$classname"), Value::from(name));
+        info.insert(String::from("$classes"), Value::Array(classes));
+        Value::Dictionary(info)
+    }
+
+    fn object(class: u64, fields: Vec<(&str, Value)>) -> Value {
+        let mut dictionary = Dictionary::new();
+        dictionary.insert(String::from("$class"), Value::Uid(Uid::new(class)));
+        for (key, value) in fields {
+            dictionary.insert(key.to_string(), value);
+        }
+        Value::Dictionary(dictionary)
+    }
+
+    fn uids(values: &[u64]) -> Value {
+        Value::Array(
+            values
+                .iter()
+                .map(|uid| Value::Uid(Uid::new(*uid)))
+                .collect(),
+        )
+    }
+
+    fn test_archive() -> Value {
+        let objects = vec![
+            Value::from("$null"),
+            // 1: NSDictionary root
+            object(
+                2,
+                vec![
+                    ("NS.keys", uids(&[3, 4, 5, 6])),
+                    ("NS.objects", uids(&[7, 8, 11, 13])),
+                ],
+            ),
+            class("NSMutableDictionary", &["NSDictionary", "NSObject"]),
+            Value::from("name"),
+            Value::from("items"),
+            Value::from("created"),
+            Value::from("identifier"),
+            Value::from("battery"),
+            // 8: NSArray with an integer, NSData and $null
+            object(9, vec![("NS.objects", uids(&[10, 15, 0]))]),
+            class("NSArray", &["NSObject"]),
+            Value::from(80),
+            // 11: NSDate
+            object(12, vec![("NS.time", Value::Real(700000000.5))]),
+            class("NSDate", &["NSObject"]),
+            // 13: NSUUID
+            object(
+                14,
+                vec![(
+                    "NS.uuidbytes",
+                    Value::Data(vec![
+                        133, 149, 126, 29, 54, 196, 78, 210, 134, 168, 6, 87, 188, 221, 226, 147,
+                    ]),
+                )],
+            ),
+            class("NSUUID", &["NSObject"]),
+            // 15: NSData
+            object(16, vec![("NS.data", Value::Data(vec![0, 255]))]),
+            class("NSMutableData", &["NSData", "NSObject"]),
+        ];
+
+        let mut top = Dictionary::new();
+        top.insert(String::from("root"), Value::Uid(Uid::new(1)));
+        let mut archive = Dictionary::new();
+        archive.insert(String::from("$archiver"), Value::from("NSKeyedArchiver"));
+        archive.insert(String::from("$version"), Value::from(100000));
+        archive.insert(String::from("$top"), Value::Dictionary(top));
+        archive.insert(String::from("$objects"), Value::Array(objects));
+        Value::Dictionary(archive)
+    }
+
+    #[test]
+    fn test_resolve_keyed_archive() {
+        let result = resolve_keyed_archive(&test_archive()).unwrap();
+        assert_eq!(
+            result,
+            json!({
+                "name": "battery",
+                "items": [80, "AP8=", null],
+                "created": "2023-03-08T20:26:40.500Z",
+                "identifier": "85957E1D-36C4-4ED2-86A8-0657BCDDE293"
+            })
+        );
+
+        assert!(resolve_keyed_archive(&Value::from("test")).is_err());
+    }
+
+    #[test]
+    fn test_resolve_keyed_archive_cycle() {
+        let mut archive = test_archive();
+        let objects = archive
+            .as_dictionary_mut()
+            .and_then(|archive| archive.get_mut("$objects"))
+            .and_then(Value::as_array_mut)
+            .unwrap();
+        // Array that contains itself
+        objects[8] = object(9, vec![("NS.objects", uids(&[8]))]);
+
+        let result = resolve_keyed_archive(&archive).unwrap();
+        assert_eq!(result["items"], json!([{ "$ref": 8 }]));
+    }
+
+    #[test]
+    fn test_resolve_keyed_archive_shared_objects() {
+        // Each array references the next array twice. Expanding all references would create 2^64 arrays
+        let mut objects = vec![Value::from("$null")];
+        for uid in 1..=64 {
+            objects.push(uids(&[uid + 1, uid + 1]));
+        }
+        objects.push(Value::from("leaf"));
+
+        let mut top = Dictionary::new();
+        top.insert(String::from("root"), Value::Uid(Uid::new(1)));
+        let mut archive = Dictionary::new();
+        archive.insert(String::from("$archiver"), Value::from("NSKeyedArchiver"));
+        archive.insert(String::from("$top"), Value::Dictionary(top));
+        archive.insert(String::from("$objects"), Value::Array(objects));
+
+        let result = resolve_keyed_archive(&Value::Dictionary(archive)).unwrap();
+        let text = result.to_string();
+        assert!(text.starts_with("[[[[[[[[[["));
+        assert!(text.contains("{\"$ref\":"));
+        assert!(text.matches('[').count() <= super::MAX_RESOLVED);
+    }
+
+    #[test]
+    fn test_decode_plist() {
+        let mut data = Vec::new();
+        test_archive().to_writer_binary(&mut data).unwrap();
+        let result = decode_plist(&data).unwrap();
+        assert_eq!(result["name"], "battery");
+
+        let result = parse_plist_item(&encode_standard(&data)).unwrap();
+        assert!(result.starts_with("{\"created\":\"2023-03-08T20:26:40.500Z\""));
+
+        // Not a plist
+        assert_eq!(parse_plist_item("AP8="), None);
+
+        let mut plain = Dictionary::new();
+        plain.insert(String::from("level"), Value::from(-1));
+        let mut data = Vec::new();
+        Value::Dictionary(plain)
+            .to_writer_binary(&mut data)
+            .unwrap();
+        assert_eq!(decode_plist(&data).unwrap(), json!({ "level": -1 }));
+    }
+}
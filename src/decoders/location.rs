@@ -7,14 +7,33 @@
 
 use crate::util::decode_standard;
 
-use super::bool::{lowercase_bool, lowercase_int_bool};
 use log::{error, warn};
 use nom::{
     bytes::complete::take,
     number::complete::{le_f64, le_i32, le_i64, le_u32, le_u8},
 };
+use serde_json::{json, Value};
 use std::mem::size_of;
 
+struct ClientManagerState {
+    location_enabled: u32,
+    location_restricted: u32,
+}
+
+struct DaemonStatusTracker {
+    level: f64,
+    charged: u8,
+    connected: u8,
+    charger_type: u32,
+    was_connected: u8,
+    reachability: u32,
+    thermal_level: i32,
+    airplane: u8,
+    battery_saver: u8,
+    push_service: u8,
+    restricted: u8,
+}
+
 struct LocationTrackerState {
     distance_filter: f64,
     desired_accuracy: f64,
@@ -198,21 +217,30 @@ pub(crate) fn client_manager_state_tracker_state(status: &str) -> String {
         }
     };
 
-    let message_result = get_state_tracker_data(&decoded_data);
-    match message_result {
-        Ok((_, result)) => result,
+    match state_tracker_value(&decoded_data) {
+        Some(result) => result.to_string(),
+        None => String::from("Failed to get client tracker data"),
+    }
+}
+
+/// Get the tracker data as JSON. Ex: statedump `CLClientManagerStateTracker` objects
+pub(crate) fn state_tracker_value(data: &[u8]) -> Option<Value> {
+    match parse_state_tracker(data) {
+        Ok((_, state)) => Some(json!({
+            "locationRestricted": state.location_restricted != 0,
+            "locationServicesenabledStatus": state.location_enabled,
+        })),
         Err(err) => {
             error!(
-                "[macos-unifiedlogs] Failed to get client tracker data {}, error: {:?}",
-                status, err
+                "[macos-unifiedlogs] Failed to parse client manager tracker state: {:?}",
+                err
             );
-            String::from("Failed to get client tracker data")
+            None
         }
     }
 }
 
-/// Get the tracker data
-pub(crate) fn get_state_tracker_data(data: &[u8]) -> nom::IResult<&[u8], String> {
+fn parse_state_tracker(data: &[u8]) -> nom::IResult<&[u8], ClientManagerState> {
     let (location_data, location_enabled_data) = take(size_of::<u32>())(data)?;
     let (location_data, location_restricted_data) = take(size_of::<u32>())(location_data)?;
 
@@ -221,11 +249,10 @@ pub(crate) fn get_state_tracker_data(data: &[u8]) -> nom::IResult<&[u8], String>
 
     Ok((
         location_data,
-        format!(
-            "{{\"locationRestricted\":{}, \"locationServicesenabledStatus\":{}}}",
-            lowercase_bool(&format!("{}", location_restricted)),
-            location_enabled
-        ),
+        ClientManagerState {
+            location_enabled,
+            location_restricted,
+        },
     ))
 }
 
@@ -240,21 +267,56 @@ pub(crate) fn location_manager_state_tracker_state(status: &str) -> String {
         }
     };
 
-    let message_result = get_location_tracker_state(&decoded_data);
-    match message_result {
-        Ok((_, result)) => result,
+    match location_tracker_value(&decoded_data) {
+        Some(result) => result.to_string(),
+        None => String::from("Failed to get logon manager trackder data"),
+    }
+}
+
+/// Get the location state data as JSON. Ex: statedump `CLLocationManagerStateTracker` objects
+pub(crate) fn location_tracker_value(data: &[u8]) -> Option<Value> {
+    match parse_location_tracker_state(data) {
+        Ok((_, tracker)) => Some(json!({
+            "distanceFilter": tracker.distance_filter,
+            "desiredAccuracy": tracker.desired_accuracy,
+            "updatingLocation": tracker.updating_location != 0,
+            "requestingLocation": tracker.requesting_location != 0,
+            "requestingRanging": tracker.requesting_ranging != 0,
+            "updatingRanging": tracker.updating_ranging != 0,
+            "updatingHeading": tracker.updating_heading != 0,
+            "headingFilter": tracker.heading_filter,
+            "allowsLocationPrompts": tracker.allows_location_prompts != 0,
+            "allowsAlteredAccessoryLocations": tracker.allows_altered_locations != 0,
+            "dynamicAccuracyReductionEnabled": tracker.dynamic_accuracy != 0,
+            "previousAuthorizationStatusValid": tracker.previous_authorization_status_valid != 0,
+            "previousAuthorizationStatus": tracker.previous_authorization_status,
+            "limitsPrecision": tracker.limits_precision != 0,
+            "activityType": tracker.activity_type,
+            "pausesLocationUpdatesAutomatically": tracker.pauses_location_updates,
+            "paused": tracker.paused != 0,
+            "allowsBackgroundLocationUpdates": tracker.allows_background_updates != 0,
+            "showsBackgroundLocationIndicator": tracker.shows_background_location != 0,
+            "allowsMapCorrection": tracker.allows_map_correction != 0,
+            "batchingLocation": tracker.batching_location != 0,
+            "updatingVehicleSpeed": tracker.updating_vehicle_speed != 0,
+            "updatingVehicleHeading": tracker.updating_vehicle_heading != 0,
+            "matchInfoEnabled": tracker.match_info != 0,
+            "groundAltitudeEnabled": tracker.ground_altitude != 0,
+            "fusionInfoEnabled": tracker.fusion_info != 0,
+            "courtesyPromptNeeded": tracker.courtesy_prompt != 0,
+            "isAuthorizedForWidgetUpdates": tracker.is_authorized_for_widgets != 0,
+        })),
         Err(err) => {
             error!(
-                "[macos-unifiedlogs] Failed to get location tracker data {}, error: {:?}",
-                status, err
+                "[macos-unifiedlogs] Failed to parse location manager tracker state: {:?}",
+                err
             );
-            String::from("Failed to get logon manager trackder data")
+            None
         }
     }
 }
 
-/// Get the location state data
-pub(crate) fn get_location_tracker_state(data: &[u8]) -> nom::IResult<&[u8], String> {
+fn parse_location_tracker_state(data: &[u8]) -> nom::IResult<&[u8], LocationTrackerState> {
     // Found at https://github.com/cmsj/ApplePrivateHeaders/blob/main/macOS/11.3/System/Library/Frameworks/CoreLocation.framework/Versions/A/CoreLocation/CoreLocation-Structs.h and in dyldcache
     let (location_data, distance_filter_data) = take(size_of::<u64>())(data)?;
     let (location_data, desired_accuracy_data) = take(size_of::<u64>())(location_data)?;
@@ -352,7 +414,7 @@ pub(crate) fn get_location_tracker_state(data: &[u8]) -> nom::IResult<&[u8], Str
     // Return early if we only have 64 bytes to work with
     let catalina_size = 64;
     if data.len() == catalina_size {
-        return Ok((location_data, location_tracker_object(&tracker)));
+        return Ok((location_data, tracker));
     }
 
     let (location_data, batching_location_data) = take(size_of::<u8>())(location_data)?;
@@ -385,71 +447,7 @@ pub(crate) fn get_location_tracker_state(data: &[u8]) -> nom::IResult<&[u8], Str
     tracker.courtesy_prompt = courtesy_prompt;
     tracker.is_authorized_for_widgets = is_authorized;
 
-    Ok((location_data, location_tracker_object(&tracker)))
-}
-
-/// Create the location tracker json object
-fn location_tracker_object(tracker: &LocationTrackerState) -> String {
-    format!(
-        "{{
-            \"distanceFilter\":{}, 
-            \"desiredAccuracy\":{}, 
-            \"updatingLocation\":{}, 
-            \"requestingLocation\":{}, 
-            \"requestingRanging\":{}, 
-            \"updatingRanging\":{},
-            \"updatingHeading\":{},
-            \"headingFilter\":{},
-            \"allowsLocationPrompts\":{},
-            \"allowsAlteredAccessoryLocations\":{},
-            \"dynamicAccuracyReductionEnabled\":{},
-            \"previousAuthorizationStatusValid\":{},
-            \"previousAuthorizationStatus\":{},
-            \"limitsPrecision\":{},
-            \"activityType\":{},
-            \"pausesLocationUpdatesAutomatically\":{},
-            \"paused\":{},
-            \"allowsBackgroundLocationUpdates\":{},
-            \"showsBackgroundLocationIndicator\":{},
-            \"allowsMapCorrection\":{},
-            \"batchingLocation\":{},
-            \"updatingVehicleSpeed\":{},
-            \"updatingVehicleHeading\":{},
-            \"matchInfoEnabled\":{},
-            \"groundAltitudeEnabled\":{},
-            \"fusionInfoEnabled\":{},
-            \"courtesyPromptNeeded\":{},
-            \"isAuthorizedForWidgetUpdates\":{},
-        }}",
-        tracker.distance_filter,
-        tracker.desired_accuracy,
-        lowercase_int_bool(&tracker.updating_location),
-        lowercase_int_bool(&tracker.requesting_location),
-        lowercase_int_bool(&tracker.requesting_ranging),
-        lowercase_int_bool(&tracker.updating_ranging),
-        lowercase_int_bool(&tracker.updating_heading),
-        tracker.heading_filter,
-        lowercase_int_bool(&tracker.allows_location_prompts),
-        lowercase_int_bool(&tracker.allows_altered_locations),
-        lowercase_int_bool(&tracker.dynamic_accuracy),
-        lowercase_int_bool(&tracker.previous_authorization_status_valid),
-        tracker.previous_authorization_status,
-        lowercase_int_bool(&tracker.limits_precision),
-        tracker.activity_type,
-        tracker.pauses_location_updates,
-        lowercase_int_bool(&tracker.paused),
-        lowercase_int_bool(&tracker.allows_background_updates),
-        lowercase_int_bool(&tracker.shows_background_location),
-        lowercase_int_bool(&tracker.allows_map_correction),
-        lowercase_int_bool(&tracker.batching_location),
-        lowercase_int_bool(&tracker.updating_vehicle_speed),
-        lowercase_int_bool(&tracker.updating_vehicle_heading),
-        lowercase_int_bool(&tracker.match_info),
-        lowercase_int_bool(&tracker.ground_altitude),
-        lowercase_int_bool(&tracker.fusion_info),
-        lowercase_int_bool(&tracker.courtesy_prompt),
-        lowercase_int_bool(&tracker.is_authorized_for_widgets),
-    )
+    Ok((location_data, tracker))
 }
 
 /// Parse location tracker state data
@@ -488,8 +486,35 @@ pub(crate) fn io_message(data: &str) -> String {
     message.to_string()
 }
 
-/// Parse and get the location Daemon tracker
-pub(crate) fn get_daemon_status_tracker(data: &[u8]) -> nom::IResult<&[u8], String> {
+/// Parse and get the location Daemon tracker as JSON. Ex: statedump `CLDaemonStatusStateTracker` objects
+pub(crate) fn daemon_status_tracker_value(data: &[u8]) -> Option<Value> {
+    match parse_daemon_status_tracker(data) {
+        Ok((_, tracker)) => Some(json!({
+            "thermalLevel": tracker.thermal_level,
+            "reachability": reachability_name(tracker.reachability),
+            "airplaneMode": tracker.airplane != 0,
+            "batteryData": {
+                "wasConnected": tracker.was_connected != 0,
+                "charged": tracker.charged != 0,
+                "level": tracker.level,
+                "connected": tracker.connected != 0,
+                "chargerType": charger_type_name(tracker.charger_type),
+            },
+            "restrictedMode": tracker.restricted != 0,
+            "batterySaverModeEnabled": tracker.battery_saver != 0,
+            "push_service": tracker.push_service != 0,
+        })),
+        Err(err) => {
+            error!(
+                "[macos-unifiedlogs] Failed to parse daemon status tracker: {:?}",
+                err
+            );
+            None
+        }
+    }
+}
+
+fn parse_daemon_status_tracker(data: &[u8]) -> nom::IResult<&[u8], DaemonStatusTracker> {
     // https://gist.github.com/razvand/578f94748b624f4d47c1533f5a02b095
     let (location_data, level_data) = take(size_of::<u64>())(data)?;
     let (location_data, charged_data) = take(size_of::<u8>())(location_data)?;
@@ -520,7 +545,26 @@ pub(crate) fn get_daemon_status_tracker(data: &[u8]) -> nom::IResult<&[u8], Stri
     let (_, push_service) = le_u8(push_service_data)?;
     let (_, restricted) = le_u8(restricted_data)?;
 
-    let reachability_str = match reachability {
+    Ok((
+        location_data,
+        DaemonStatusTracker {
+            level,
+            charged,
+            connected,
+            charger_type,
+            was_connected,
+            reachability,
+            thermal_level,
+            airplane,
+            battery_saver,
+            push_service,
+            restricted,
+        },
+    ))
+}
+
+fn reachability_name(reachability: u32) -> &'static str {
+    match reachability {
         2 => "kReachabilityLarge",
         _ => {
             warn!(
@@ -529,9 +573,11 @@ pub(crate) fn get_daemon_status_tracker(data: &[u8]) -> nom::IResult<&[u8], Stri
             );
             "Unknown reachability value"
         }
-    };
+    }
+}
 
-    let charger_type_str = match charger_type {
+fn charger_type_name(charger_type: u32) -> &'static str {
+    match charger_type {
         0 => "kChargerTypeUnknown",
         _ => {
             warn!(
@@ -540,34 +586,16 @@ pub(crate) fn get_daemon_status_tracker(data: &[u8]) -> nom::IResult<&[u8], Stri
             );
             "Unknown charger type value"
         }
-    };
-
-    let message = format!(
-        r#"{{"thermalLevel": {}, "reachability: "{}", "airplaneMode": {}, "batteryData":{{"wasConnected": {}, "charged": {}, "level": {}, "connected": {}, "chargerType": "{}"}}, "restrictedMode": {}, "batterySaverModeEnabled": {}, "push_service":{}}}"#,
-        thermal_level,
-        reachability_str,
-        lowercase_int_bool(&airplane),
-        lowercase_int_bool(&was_connected),
-        lowercase_int_bool(&charged),
-        level,
-        lowercase_int_bool(&connected),
-        charger_type_str,
-        lowercase_int_bool(&restricted),
-        lowercase_int_bool(&battery_saver),
-        lowercase_int_bool(&push_service)
-    );
-
-    Ok((location_data, message))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
         decoders::location::{
-            client_manager_state_tracker_state, daemon_status_type, get_daemon_status_tracker,
-            get_location_tracker_state, get_sqlite_data, get_state_tracker_data, io_message,
-            location_manager_state_tracker_state, location_tracker_object, sqlite,
-            subharvester_identifier, LocationTrackerState,
+            client_manager_state_tracker_state, daemon_status_tracker_value, daemon_status_type,
+            get_sqlite_data, io_message, location_manager_state_tracker_state,
+            location_tracker_value, sqlite, state_tracker_value, subharvester_identifier,
         },
         util::decode_standard,
     };
@@ -623,61 +651,19 @@ mod tests {
 
         assert_eq!(
             result,
-            "{\"locationRestricted\":false, \"locationServicesenabledStatus\":1}"
-        )
-    }
-
-    #[test]
-    fn test_location_tracker_object() {
-        let test_data = LocationTrackerState {
-            distance_filter: 0.0,
-            desired_accuracy: 0.0,
-            updating_location: 0,
-            requesting_location: 0,
-            requesting_ranging: 0,
-            updating_ranging: 0,
-            updating_heading: 0,
-            heading_filter: 0.0,
-            allows_location_prompts: 0,
-            allows_altered_locations: 0,
-            dynamic_accuracy: 0,
-            previous_authorization_status_valid: 0,
-            previous_authorization_status: 0,
-            limits_precision: 0,
-            activity_type: 0,
-            pauses_location_updates: 0,
-            paused: 0,
-            allows_background_updates: 0,
-            shows_background_location: 0,
-            allows_map_correction: 0,
-            batching_location: 0,
-            updating_vehicle_speed: 0,
-            updating_vehicle_heading: 0,
-            match_info: 0,
-            ground_altitude: 0,
-            fusion_info: 0,
-            courtesy_prompt: 0,
-            is_authorized_for_widgets: 0,
-        };
-        let result = location_tracker_object(&test_data);
-
-        assert_eq!(
-            result,
-            "{\n            \"distanceFilter\":0, \n            \"desiredAccuracy\":0, \n            \"updatingLocation\":false, \n            \"requestingLocation\":false, \n            \"requestingRanging\":false, \n            \"updatingRanging\":false,\n            \"updatingHeading\":false,\n            \"headingFilter\":0,\n            \"allowsLocationPrompts\":false,\n            \"allowsAlteredAccessoryLocations\":false,\n            \"dynamicAccuracyReductionEnabled\":false,\n            \"previousAuthorizationStatusValid\":false,\n            \"previousAuthorizationStatus\":0,\n            \"limitsPrecision\":false,\n            \"activityType\":0,\n            \"pausesLocationUpdatesAutomatically\":0,\n            \"paused\":false,\n            \"allowsBackgroundLocationUpdates\":false,\n            \"showsBackgroundLocationIndicator\":false,\n            \"allowsMapCorrection\":false,\n            \"batchingLocation\":false,\n            \"updatingVehicleSpeed\":false,\n            \"updatingVehicleHeading\":false,\n            \"matchInfoEnabled\":false,\n            \"groundAltitudeEnabled\":false,\n            \"fusionInfoEnabled\":false,\n            \"courtesyPromptNeeded\":false,\n            \"isAuthorizedForWidgetUpdates\":false,\n        }"
+            "{\"locationRestricted\":false,\"locationServicesenabledStatus\":1}"
         )
     }
 
     #[test]
-    fn test_get_state_tracker_data() {
+    fn test_state_tracker_value() {
         let test_data = "AQAAAAAAAAA=";
         let decoded_data_result = decode_standard(test_data).unwrap();
 
-        let (_, result) = get_state_tracker_data(&decoded_data_result).unwrap();
-
-        assert_eq!(
-            result,
-            "{\"locationRestricted\":false, \"locationServicesenabledStatus\":1}"
-        )
+        let result = state_tracker_value(&decoded_data_result).unwrap();
+        assert_eq!(result["locationRestricted"], false);
+        assert_eq!(result["locationServicesenabledStatus"], 1);
+        assert_eq!(state_tracker_value(&decoded_data_result[..4]), None);
     }
 
     #[test]
@@ -687,20 +673,7 @@ mod tests {
 
         assert_eq!(
             result,
-            "{\n            \"distanceFilter\":-1, \n            \"desiredAccuracy\":100, \n            \"updatingLocation\":false, \n            \"requestingLocation\":false, \n            \"requestingRanging\":false, \n            \"updatingRanging\":false,\n            \"updatingHeading\":false,\n            \"headingFilter\":1,\n            \"allowsLocationPrompts\":true,\n            \"allowsAlteredAccessoryLocations\":false,\n            \"dynamicAccuracyReductionEnabled\":false,\n            \"previousAuthorizationStatusValid\":false,\n            \"previousAuthorizationStatus\":0,\n            \"limitsPrecision\":false,\n            \"activityType\":0,\n            \"pausesLocationUpdatesAutomatically\":1,\n            \"paused\":false,\n            \"allowsBackgroundLocationUpdates\":false,\n            \"showsBackgroundLocationIndicator\":false,\n            \"allowsMapCorrection\":true,\n            \"batchingLocation\":false,\n            \"updatingVehicleSpeed\":false,\n            \"updatingVehicleHeading\":false,\n            \"matchInfoEnabled\":false,\n            \"groundAltitudeEnabled\":false,\n            \"fusionInfoEnabled\":false,\n            \"courtesyPromptNeeded\":false,\n            \"isAuthorizedForWidgetUpdates\":false,\n        }"       
-        )
-    }
-
-    #[test]
-    fn test_get_location_tracker_state() {
-        let test_data = "AAAAAAAA8L8AAAAAAABZQAAAAAAAAAAAAAAAAAAA8D8BAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABAAAAAAAAAQAAAAAAAAAA";
-        let decoded_data_result = decode_standard(test_data).unwrap();
-
-        let (_, result) = get_location_tracker_state(&decoded_data_result).unwrap();
-
-        assert_eq!(
-            result,
-            "{\n            \"distanceFilter\":-1, \n            \"desiredAccuracy\":100, \n            \"updatingLocation\":false, \n            \"requestingLocation\":false, \n            \"requestingRanging\":false, \n            \"updatingRanging\":false,\n            \"updatingHeading\":false,\n            \"headingFilter\":1,\n            \"allowsLocationPrompts\":true,\n            \"allowsAlteredAccessoryLocations\":false,\n            \"dynamicAccuracyReductionEnabled\":false,\n            \"previousAuthorizationStatusValid\":false,\n            \"previousAuthorizationStatus\":0,\n            \"limitsPrecision\":false,\n            \"activityType\":0,\n            \"pausesLocationUpdatesAutomatically\":1,\n            \"paused\":false,\n            \"allowsBackgroundLocationUpdates\":false,\n            \"showsBackgroundLocationIndicator\":false,\n            \"allowsMapCorrection\":true,\n            \"batchingLocation\":false,\n            \"updatingVehicleSpeed\":false,\n            \"updatingVehicleHeading\":false,\n            \"matchInfoEnabled\":false,\n            \"groundAltitudeEnabled\":false,\n            \"fusionInfoEnabled\":false,\n            \"courtesyPromptNeeded\":false,\n            \"isAuthorizedForWidgetUpdates\":false,\n        }"       
+            "{\"activityType\":0,\"allowsAlteredAccessoryLocations\":false,\"allowsBackgroundLocationUpdates\":false,\"allowsLocationPrompts\":true,\"allowsMapCorrection\":true,\"batchingLocation\":false,\"courtesyPromptNeeded\":false,\"desiredAccuracy\":100.0,\"distanceFilter\":-1.0,\"dynamicAccuracyReductionEnabled\":false,\"fusionInfoEnabled\":false,\"groundAltitudeEnabled\":false,\"headingFilter\":1.0,\"isAuthorizedForWidgetUpdates\":false,\"limitsPrecision\":false,\"matchInfoEnabled\":false,\"paused\":false,\"pausesLocationUpdatesAutomatically\":1,\"previousAuthorizationStatus\":0,\"previousAuthorizationStatusValid\":false,\"requestingLocation\":false,\"requestingRanging\":false,\"showsBackgroundLocationIndicator\":false,\"updatingHeading\":false,\"updatingLocation\":false,\"updatingRanging\":false,\"updatingVehicleHeading\":false,\"updatingVehicleSpeed\":false}"
         )
     }
 
@@ -713,13 +686,26 @@ mod tests {
     }
 
     #[test]
-    fn test_get_daemon_status_tracker() {
+    fn test_daemon_status_tracker_value() {
         let test_data = [
             0, 0, 0, 0, 0, 0, 240, 191, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0,
             255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0,
         ];
-        let (_, result) = get_daemon_status_tracker(&test_data).unwrap();
+        let result = daemon_status_tracker_value(&test_data).unwrap();
+
+        assert_eq!(result.to_string(), "{\"airplaneMode\":false,\"batteryData\":{\"charged\":false,\"chargerType\":\"kChargerTypeUnknown\",\"connected\":false,\"level\":-1.0,\"wasConnected\":false},\"batterySaverModeEnabled\":false,\"push_service\":false,\"reachability\":\"kReachabilityLarge\",\"restrictedMode\":false,\"thermalLevel\":-1}");
+        assert_eq!(daemon_status_tracker_value(&test_data[..8]), None);
+    }
+
+    #[test]
+    fn test_location_tracker_value() {
+        let test_data = "AAAAAAAA8L8AAAAAAABZQAAAAAAAAAAAAAAAAAAA8D8BAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABAAAAAAAAAQAAAAAAAAAA";
+        let decoded_data_result = decode_standard(test_data).unwrap();
 
-        assert_eq!(result, "{\"thermalLevel\": -1, \"reachability: \"kReachabilityLarge\", \"airplaneMode\": false, \"batteryData\":{\"wasConnected\": false, \"charged\": false, \"level\": -1, \"connected\": false, \"chargerType\": \"kChargerTypeUnknown\"}, \"restrictedMode\": false, \"batterySaverModeEnabled\": false, \"push_service\":false}")
+        let result = location_tracker_value(&decoded_data_result).unwrap();
+        assert_eq!(result["distanceFilter"], -1.0);
+        assert_eq!(result["allowsLocationPrompts"], true);
+        assert_eq!(result["pausesLocationUpdatesAutomatically"], 1);
+        assert_eq!(result["isAuthorizedForWidgetUpdates"], false);
     }
 }
@@ -6,11 +6,15 @@
 // See the License for the specific language governing permissions and limitations under the License.
 
 mod bool;
-mod darwin;
-pub(crate) mod decoder;
+pub(crate) mod darwin;
+pub mod decoder;
 mod dns;
+pub mod keyed_archive;
 pub(crate) mod location;
 mod network;
+pub mod objects;
 mod opendirectory;
+pub mod protobuf;
 mod time;
+mod units;
 mod uuid;
@@ -0,0 +1,165 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+use crate::decoders::location::{
+    daemon_status_tracker_value, location_tracker_value, state_tracker_value,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+
+type ObjectDecoderFn = fn(&[u8]) -> Option<Value>;
+
+/// Decoder for a custom object in a Statedump entry (data type 3)
+///
+/// Functions with the signature `fn(&[u8]) -> Option<Value>` implement `ObjectDecoder`
+pub trait ObjectDecoder: Send + Sync {
+    /// Decode the raw object data. Return None if the data could not be decoded
+    fn decode(&self, data: &[u8]) -> Option<Value>;
+}
+
+impl<F> ObjectDecoder for F
+where
+    F: Fn(&[u8]) -> Option<Value> + Send + Sync,
+{
+    fn decode(&self, data: &[u8]) -> Option<Value> {
+        self(data)
+    }
+}
+
+/// Statedump object decoders keyed on the object type (ex: `location:_CLDaemonStatusStateTrackerState`) or the Statedump name (ex: `CLDaemonStatusStateTracker`)
+///
+/// The object type is the two type strings of the Statedump joined by `:`, the same token used by the matching inline `%{...}` value decoder
+pub struct ObjectDecoderRegistry {
+    decoders: HashMap<String, Box<dyn ObjectDecoder>>,
+}
+
+impl Default for ObjectDecoderRegistry {
+    fn default() -> Self {
+        ObjectDecoderRegistry::new()
+    }
+}
+
+impl std::fmt::Debug for ObjectDecoderRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ObjectDecoderRegistry")
+            .field("keys", &self.keys())
+            .finish()
+    }
+}
+
+impl ObjectDecoderRegistry {
+    /// Registry with the built-in location daemon state tracker decoders
+    pub fn new() -> ObjectDecoderRegistry {
+        let mut registry = ObjectDecoderRegistry::empty();
+        let builtin: [(&str, &str, ObjectDecoderFn); 3] = [
+            (
+                "location:_CLDaemonStatusStateTrackerState",
+                "CLDaemonStatusStateTracker",
+                daemon_status_tracker_value,
+            ),
+            (
+                "location:_CLClientManagerStateTrackerState",
+                "CLClientManagerStateTracker",
+                state_tracker_value,
+            ),
+            (
+                "location:_CLLocationManagerStateTrackerState",
+                "CLLocationManagerStateTracker",
+                location_tracker_value,
+            ),
+        ];
+        for (object_type, name, decoder) in builtin {
+            registry.register(object_type, decoder);
+            registry.register(name, decoder);
+        }
+        registry
+    }
+
+    /// Registry without any decoders
+    pub fn empty() -> ObjectDecoderRegistry {
+        ObjectDecoderRegistry {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Register a decoder for an object type or Statedump name. Replaces any existing decoder for the key
+    pub fn register<D: ObjectDecoder + 'static>(&mut self, key: &str, decoder: D) {
+        self.decoders.insert(key.to_string(), Box::new(decoder));
+    }
+
+    /// Remove the decoder for a key. Return false if no decoder was registered
+    pub fn unregister(&mut self, key: &str) -> bool {
+        self.decoders.remove(key).is_some()
+    }
+
+    /// Get the decoder for an object type or Statedump name
+    pub fn get(&self, key: &str) -> Option<&dyn ObjectDecoder> {
+        self.decoders.get(key).map(|decoder| decoder.as_ref())
+    }
+
+    /// Registered keys, sorted
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self.decoders.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    /// Get the decoder for a Statedump object. The object type is checked before the name
+    pub fn find(
+        &self,
+        object_type_string_1: &str,
+        object_type_string_2: &str,
+        name: &str,
+    ) -> Option<&dyn ObjectDecoder> {
+        self.get(&format!(
+            "{}:{}",
+            object_type_string_1, object_type_string_2
+        ))
+        .or_else(|| self.get(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ObjectDecoderRegistry;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_object_registry() {
+        let registry = ObjectDecoderRegistry::new();
+        assert_eq!(registry.keys().len(), 6);
+
+        let test_data = [1, 0, 0, 0, 0, 0, 0, 0];
+        let decoder = registry
+            .find("location", "_CLClientManagerStateTrackerState", "")
+            .unwrap();
+        assert_eq!(
+            decoder.decode(&test_data),
+            Some(json!({"locationRestricted": false, "locationServicesenabledStatus": 1}))
+        );
+
+        // Name is used if the object type is unknown
+        assert!(registry
+            .find("", "", "CLClientManagerStateTracker")
+            .is_some());
+        assert!(registry.find("location", "unknown", "unknown").is_none());
+        assert_eq!(decoder.decode(&[1]), None);
+    }
+
+    #[test]
+    fn test_register_object_decoder() {
+        let mut registry = ObjectDecoderRegistry::empty();
+        registry.register("acme:state", |data: &[u8]| -> Option<Value> {
+            Some(json!({ "size": data.len() }))
+        });
+        let decoder = registry.find("acme", "state", "AcmeTracker").unwrap();
+        assert_eq!(decoder.decode(&[0, 1]), Some(json!({ "size": 2 })));
+
+        assert!(registry.unregister("acme:state"));
+        assert!(!registry.unregister("acme:state"));
+    }
+}
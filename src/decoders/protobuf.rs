@@ -0,0 +1,662 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Decode protocol buffer data (ex: Statedump type 2 payloads) to JSON
+//!
+//! Without a schema each field is rendered by field number and wire type. Length-delimited fields are shown as a nested message, string or base64 bytes.
+//! With a `ProtobufSchema` built from a compiled descriptor set (`protoc --descriptor_set_out`) fields are rendered by name and type
+
+use crate::error::ParserError;
+use crate::util::encode_standard;
+use log::error;
+use nom::bytes::complete::take;
+use nom::number::complete::{le_u32, le_u64};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Nested messages deeper than this are shown as bytes
+const MAX_DEPTH: usize = 32;
+
+/// Field numbers and values of a message in wire order
+type Fields<'a> = Vec<(u32, WireValue<'a>)>;
+
+/// Field value as encoded on the wire
+#[derive(Debug, Clone)]
+enum WireValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    LengthDelimited(&'a [u8]),
+    Group(Fields<'a>),
+    Fixed32(u32),
+}
+
+/// Decode protocol buffer data without a schema
+pub fn decode_protobuf(data: &[u8]) -> Result<Value, ParserError> {
+    match parse_message(data, 0) {
+        Ok((_, fields)) => Ok(render_fields(&fields, 0)),
+        Err(err) => {
+            error!(
+                "[macos-unifiedlogs] Failed to decode protocol buffer data: {:?}",
+                err
+            );
+            Err(ParserError::Protobuf)
+        }
+    }
+}
+
+/// Parse a base 128 varint
+fn parse_varint(data: &[u8]) -> nom::IResult<&[u8], u64> {
+    let mut value: u64 = 0;
+    for (index, byte) in data.iter().enumerate() {
+        // A varint is at most 10 bytes
+        if index == 10 {
+            break;
+        }
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((&data[index + 1..], value));
+        }
+    }
+    Err(nom::Err::Error(nom::error::Error::new(
+        data,
+        nom::error::ErrorKind::TooLarge,
+    )))
+}
+
+/// Parse all fields of a message. Fails if the data is not a complete message
+fn parse_message(data: &[u8], depth: usize) -> nom::IResult<&[u8], Fields<'_>> {
+    let (input, (fields, end_group)) = parse_fields(data, depth)?;
+    if end_group.is_some() {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            data,
+            nom::error::ErrorKind::Tag,
+        )));
+    }
+    Ok((input, fields))
+}
+
+/// Parse fields until the end of the data or an end group tag. Return the field number of the end group tag
+fn parse_fields(data: &[u8], depth: usize) -> nom::IResult<&[u8], (Fields<'_>, Option<u32>)> {
+    let mut fields = Vec::new();
+    let mut input = data;
+    while !input.is_empty() {
+        let (remaining, tag) = parse_varint(input)?;
+        let field_number = tag >> 3;
+        if field_number == 0 || field_number > u64::from(u32::MAX >> 3) {
+            return Err(nom::Err::Error(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::Verify,
+            )));
+        }
+        let field_number = field_number as u32;
+
+        let (remaining, value) = match tag & 0x7 {
+            0 => {
+                let (remaining, value) = parse_varint(remaining)?;
+                (remaining, WireValue::Varint(value))
+            }
+            1 => {
+                let (remaining, value) = le_u64(remaining)?;
+                (remaining, WireValue::Fixed64(value))
+            }
+            2 => {
+                let (remaining, size) = parse_varint(remaining)?;
+                let (remaining, value) = take(size)(remaining)?;
+                (remaining, WireValue::LengthDelimited(value))
+            }
+            3 => {
+                if depth >= MAX_DEPTH {
+                    return Err(nom::Err::Error(nom::error::Error::new(
+                        input,
+                        nom::error::ErrorKind::TooLarge,
+                    )));
+                }
+                let (remaining, (group, end_group)) = parse_fields(remaining, depth + 1)?;
+                if end_group != Some(field_number) {
+                    return Err(nom::Err::Error(nom::error::Error::new(
+                        input,
+                        nom::error::ErrorKind::Tag,
+                    )));
+                }
+                (remaining, WireValue::Group(group))
+            }
+            4 => return Ok((remaining, (fields, Some(field_number)))),
+            5 => {
+                let (remaining, value) = le_u32(remaining)?;
+                (remaining, WireValue::Fixed32(value))
+            }
+            _ => {
+                return Err(nom::Err::Error(nom::error::Error::new(
+                    input,
+                    nom::error::ErrorKind::Switch,
+                )))
+            }
+        };
+        fields.push((field_number, value));
+        input = remaining;
+    }
+    Ok((input, (fields, None)))
+}
+
+/// Render fields by field number. Fields seen more than once become arrays
+fn render_fields(fields: &[(u32, WireValue<'_>)], depth: usize) -> Value {
+    let mut message = Map::new();
+    for (field_number, value) in fields {
+        insert_unknown_field(
+            &mut message,
+            &field_number.to_string(),
+            render_wire_value(value, depth),
+        );
+    }
+    Value::Object(message)
+}
+
+/// Schemaless values are always objects, so an existing array holds earlier occurrences of the field
+fn insert_unknown_field(message: &mut Map<String, Value>, key: &str, value: Value) {
+    match message.get_mut(key) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            message.insert(key.to_string(), value);
+        }
+    }
+}
+
+/// Repeated fields are always arrays. Otherwise the last value wins
+fn insert_field(message: &mut Map<String, Value>, key: &str, value: Value, repeated: bool) {
+    if !repeated {
+        message.insert(key.to_string(), value);
+        return;
+    }
+    match message.get_mut(key) {
+        Some(Value::Array(values)) => values.push(value),
+        _ => {
+            message.insert(key.to_string(), Value::Array(vec![value]));
+        }
+    }
+}
+
+fn render_wire_value(value: &WireValue<'_>, depth: usize) -> Value {
+    match value {
+        WireValue::Varint(value) => json!({ "wire_type": "varint", "value": value }),
+        WireValue::Fixed64(value) => json!({ "wire_type": "fixed64", "value": value }),
+        WireValue::Fixed32(value) => json!({ "wire_type": "fixed32", "value": value }),
+        WireValue::Group(fields) => {
+            json!({ "wire_type": "group", "value": render_fields(fields, depth + 1) })
+        }
+        WireValue::LengthDelimited(data) => render_length_delimited(data, depth),
+    }
+}
+
+/// Guess if length-delimited data is a string, nested message or bytes
+fn render_length_delimited(data: &[u8], depth: usize) -> Value {
+    if let Ok(text) = std::str::from_utf8(data) {
+        if text
+            .chars()
+            .all(|c| !c.is_control() || matches!(c, '\n' | '\r' | '\t'))
+        {
+            return json!({ "wire_type": "length_delimited", "type": "string", "value": text });
+        }
+    }
+    if !data.is_empty() && depth < MAX_DEPTH {
+        if let Ok((_, fields)) = parse_message(data, depth + 1) {
+            return json!({
+                "wire_type": "length_delimited",
+                "type": "message",
+                "value": render_fields(&fields, depth + 1)
+            });
+        }
+    }
+    json!({ "wire_type": "length_delimited", "type": "bytes", "value": encode_standard(data) })
+}
+
+/// Field types from `google/protobuf/descriptor.proto`
+const TYPE_DOUBLE: u64 = 1;
+const TYPE_FLOAT: u64 = 2;
+const TYPE_INT64: u64 = 3;
+const TYPE_UINT64: u64 = 4;
+const TYPE_INT32: u64 = 5;
+const TYPE_FIXED64: u64 = 6;
+const TYPE_FIXED32: u64 = 7;
+const TYPE_BOOL: u64 = 8;
+const TYPE_STRING: u64 = 9;
+const TYPE_GROUP: u64 = 10;
+const TYPE_MESSAGE: u64 = 11;
+const TYPE_BYTES: u64 = 12;
+const TYPE_UINT32: u64 = 13;
+const TYPE_ENUM: u64 = 14;
+const TYPE_SFIXED32: u64 = 15;
+const TYPE_SFIXED64: u64 = 16;
+const TYPE_SINT32: u64 = 17;
+const TYPE_SINT64: u64 = 18;
+
+const LABEL_REPEATED: u64 = 3;
+
+#[derive(Debug, Clone, Default)]
+struct FieldDescriptor {
+    name: String,
+    field_type: u64,
+    label: u64,
+    type_name: String,
+}
+
+/// Message and enum types from a compiled descriptor set (`FileDescriptorSet`)
+#[derive(Debug, Clone, Default)]
+pub struct ProtobufSchema {
+    messages: HashMap<String, HashMap<u32, FieldDescriptor>>,
+    enums: HashMap<String, HashMap<i32, String>>,
+}
+
+impl ProtobufSchema {
+    /// Load the message types of a `FileDescriptorSet`. Ex: created with `protoc --include_imports --descriptor_set_out=schema.pb`
+    pub fn from_descriptor_set(data: &[u8]) -> Result<ProtobufSchema, ParserError> {
+        let fields = match parse_message(data, 0) {
+            Ok((_, fields)) => fields,
+            Err(err) => {
+                error!(
+                    "[macos-unifiedlogs] Failed to parse protocol buffer descriptor set: {:?}",
+                    err
+                );
+                return Err(ParserError::Protobuf);
+            }
+        };
+
+        let mut schema = ProtobufSchema::default();
+        // FileDescriptorSet.file
+        for file in length_delimited(&fields, 1) {
+            let Ok((_, file_fields)) = parse_message(file, 0) else {
+                error!("[macos-unifiedlogs] Failed to parse protocol buffer file descriptor");
+                return Err(ParserError::Protobuf);
+            };
+            // FileDescriptorProto.package
+            let package = length_delimited(&file_fields, 2)
+                .next()
+                .map(|value| String::from_utf8_lossy(value).to_string())
+                .unwrap_or_default();
+            // FileDescriptorProto.message_type
+            for message in length_delimited(&file_fields, 4) {
+                schema.add_message(&package, message)?;
+            }
+            // FileDescriptorProto.enum_type
+            for enum_type in length_delimited(&file_fields, 5) {
+                schema.add_enum(&package, enum_type)?;
+            }
+        }
+        Ok(schema)
+    }
+
+    /// Full names of the message types in the schema
+    pub fn message_types(&self) -> Vec<&str> {
+        let mut types: Vec<&str> = self.messages.keys().map(String::as_str).collect();
+        types.sort_unstable();
+        types
+    }
+
+    /// Decode protocol buffer data as a message type (ex: `com.apple.Example`). Fields not in the schema are rendered by field number
+    pub fn decode(&self, message_type: &str, data: &[u8]) -> Result<Value, ParserError> {
+        let message_type = message_type.trim_start_matches('.');
+        if !self.messages.contains_key(message_type) {
+            error!(
+                "[macos-unifiedlogs] Protocol buffer message type not in schema: {}",
+                message_type
+            );
+            return Err(ParserError::Protobuf);
+        }
+        match parse_message(data, 0) {
+            Ok((_, fields)) => Ok(self.render_message(message_type, &fields, 0)),
+            Err(err) => {
+                error!(
+                    "[macos-unifiedlogs] Failed to decode protocol buffer data as {}: {:?}",
+                    message_type, err
+                );
+                Err(ParserError::Protobuf)
+            }
+        }
+    }
+
+    /// Add a `DescriptorProto` and its nested types
+    fn add_message(&mut self, scope: &str, data: &[u8]) -> Result<(), ParserError> {
+        let Ok((_, fields)) = parse_message(data, 0) else {
+            error!("[macos-unifiedlogs] Failed to parse protocol buffer message descriptor");
+            return Err(ParserError::Protobuf);
+        };
+        let name = full_name(scope, &string_field(&fields, 1));
+
+        let mut message_fields = HashMap::new();
+        for field in length_delimited(&fields, 2) {
+            let Ok((_, field_data)) = parse_message(field, 0) else {
+                error!("[macos-unifiedlogs] Failed to parse protocol buffer field descriptor");
+                return Err(ParserError::Protobuf);
+            };
+            let number = varint_field(&field_data, 3) as u32;
+            message_fields.insert(
+                number,
+                FieldDescriptor {
+                    name: string_field(&field_data, 1),
+                    label: varint_field(&field_data, 4),
+                    field_type: varint_field(&field_data, 5),
+                    type_name: string_field(&field_data, 6)
+                        .trim_start_matches('.')
+                        .to_string(),
+                },
+            );
+        }
+        self.messages.insert(name.to_owned(), message_fields);
+
+        for nested in length_delimited(&fields, 3) {
+            self.add_message(&name, nested)?;
+        }
+        for enum_type in length_delimited(&fields, 4) {
+            self.add_enum(&name, enum_type)?;
+        }
+        Ok(())
+    }
+
+    /// Add an `EnumDescriptorProto`
+    fn add_enum(&mut self, scope: &str, data: &[u8]) -> Result<(), ParserError> {
+        let Ok((_, fields)) = parse_message(data, 0) else {
+            error!("[macos-unifiedlogs] Failed to parse protocol buffer enum descriptor");
+            return Err(ParserError::Protobuf);
+        };
+        let mut values = HashMap::new();
+        for value in length_delimited(&fields, 2) {
+            if let Ok((_, value_fields)) = parse_message(value, 0) {
+                values.insert(
+                    varint_field(&value_fields, 2) as i32,
+                    string_field(&value_fields, 1),
+                );
+            }
+        }
+        self.enums
+            .insert(full_name(scope, &string_field(&fields, 1)), values);
+        Ok(())
+    }
+
+    fn render_message(
+        &self,
+        message_type: &str,
+        fields: &[(u32, WireValue<'_>)],
+        depth: usize,
+    ) -> Value {
+        let Some(descriptors) = self.messages.get(message_type) else {
+            return render_fields(fields, depth);
+        };
+
+        let mut message = Map::new();
+        for (field_number, value) in fields {
+            let Some(descriptor) = descriptors.get(field_number) else {
+                insert_unknown_field(
+                    &mut message,
+                    &field_number.to_string(),
+                    render_wire_value(value, depth),
+                );
+                continue;
+            };
+            let repeated = descriptor.label == LABEL_REPEATED;
+
+            // Packed repeated scalars are stored in one length-delimited field
+            if let WireValue::LengthDelimited(data) = value {
+                if repeated && is_packable(descriptor.field_type) {
+                    if let Some(values) = unpack(data, descriptor.field_type) {
+                        for packed in values {
+                            let rendered = self.render_value(descriptor, &packed, depth);
+                            insert_field(&mut message, &descriptor.name, rendered, true);
+                        }
+                        continue;
+                    }
+                }
+            }
+            let rendered = self.render_value(descriptor, value, depth);
+            insert_field(&mut message, &descriptor.name, rendered, repeated);
+        }
+        Value::Object(message)
+    }
+
+    fn render_value(
+        &self,
+        descriptor: &FieldDescriptor,
+        value: &WireValue<'_>,
+        depth: usize,
+    ) -> Value {
+        match (descriptor.field_type, value) {
+            (TYPE_DOUBLE, WireValue::Fixed64(value)) => json!(f64::from_bits(*value)),
+            (TYPE_FLOAT, WireValue::Fixed32(value)) => json!(f32::from_bits(*value)),
+            (TYPE_INT64, WireValue::Varint(value)) => json!(signed(*value)),
+            (TYPE_UINT64, WireValue::Varint(value)) => json!(value),
+            (TYPE_INT32, WireValue::Varint(value)) => json!(*value as i32),
+            (TYPE_FIXED64, WireValue::Fixed64(value)) => json!(value),
+            (TYPE_FIXED32, WireValue::Fixed32(value)) => json!(value),
+            (TYPE_BOOL, WireValue::Varint(value)) => json!(*value != 0),
+            (TYPE_STRING, WireValue::LengthDelimited(data)) => {
+                json!(String::from_utf8_lossy(data))
+            }
+            (TYPE_BYTES, WireValue::LengthDelimited(data)) => json!(encode_standard(data)),
+            (TYPE_UINT32, WireValue::Varint(value)) => json!(*value as u32),
+            (TYPE_ENUM, WireValue::Varint(value)) => {
+                let number = *value as i32;
+                match self
+                    .enums
+                    .get(&descriptor.type_name)
+                    .and_then(|values| values.get(&number))
+                {
+                    Some(name) => json!(name),
+                    None => json!(number),
+                }
+            }
+            (TYPE_SFIXED32, WireValue::Fixed32(value)) => {
+                json!(i32::from_le_bytes(value.to_le_bytes()))
+            }
+            (TYPE_SFIXED64, WireValue::Fixed64(value)) => json!(signed(*value)),
+            (TYPE_SINT32, WireValue::Varint(value)) => json!(zigzag(*value) as i32),
+            (TYPE_SINT64, WireValue::Varint(value)) => json!(zigzag(*value)),
+            (TYPE_MESSAGE, WireValue::LengthDelimited(data)) if depth < MAX_DEPTH => {
+                match parse_message(data, depth + 1) {
+                    Ok((_, fields)) => {
+                        self.render_message(&descriptor.type_name, &fields, depth + 1)
+                    }
+                    Err(_) => render_length_delimited(data, depth),
+                }
+            }
+            (TYPE_GROUP, WireValue::Group(fields)) => {
+                self.render_message(&descriptor.type_name, fields, depth + 1)
+            }
+            // Wire type does not match the schema
+            _ => render_wire_value(value, depth),
+        }
+    }
+}
+
+/// Reinterpret varint or fixed64 bits as a two's complement value
+fn signed(value: u64) -> i64 {
+    i64::from_le_bytes(value.to_le_bytes())
+}
+
+/// Decode `sint32` and `sint64` values
+fn zigzag(value: u64) -> i64 {
+    signed(value >> 1) ^ -signed(value & 1)
+}
+
+fn is_packable(field_type: u64) -> bool {
+    !matches!(
+        field_type,
+        TYPE_STRING | TYPE_BYTES | TYPE_MESSAGE | TYPE_GROUP
+    )
+}
+
+/// Split a packed repeated field into its values
+fn unpack(data: &[u8], field_type: u64) -> Option<Vec<WireValue<'_>>> {
+    let mut values = Vec::new();
+    let mut input = data;
+    while !input.is_empty() {
+        let (remaining, value) = match field_type {
+            TYPE_DOUBLE | TYPE_FIXED64 | TYPE_SFIXED64 => {
+                let (remaining, value) = le_u64::<_, nom::error::Error<&[u8]>>(input).ok()?;
+                (remaining, WireValue::Fixed64(value))
+            }
+            TYPE_FLOAT | TYPE_FIXED32 | TYPE_SFIXED32 => {
+                let (remaining, value) = le_u32::<_, nom::error::Error<&[u8]>>(input).ok()?;
+                (remaining, WireValue::Fixed32(value))
+            }
+            _ => {
+                let (remaining, value) = parse_varint(input).ok()?;
+                (remaining, WireValue::Varint(value))
+            }
+        };
+        values.push(value);
+        input = remaining;
+    }
+    Some(values)
+}
+
+fn full_name(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", scope, name)
+    }
+}
+
+fn length_delimited<'a>(
+    fields: &'a [(u32, WireValue<'a>)],
+    field_number: u32,
+) -> impl Iterator<Item = &'a [u8]> {
+    fields
+        .iter()
+        .filter_map(move |(number, value)| match value {
+            WireValue::LengthDelimited(data) if *number == field_number => Some(*data),
+            _ => None,
+        })
+}
+
+fn string_field(fields: &[(u32, WireValue<'_>)], field_number: u32) -> String {
+    length_delimited(fields, field_number)
+        .next()
+        .map(|value| String::from_utf8_lossy(value).to_string())
+        .unwrap_or_default()
+}
+
+fn varint_field(fields: &[(u32, WireValue<'_>)], field_number: u32) -> u64 {
+    fields
+        .iter()
+        .find_map(|(number, value)| match value {
+            WireValue::Varint(value) if *number == field_number => Some(*value),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_protobuf, ProtobufSchema};
+    use serde_json::json;
+
+    fn tag(number: u8, wire_type: u8) -> u8 {
+        number << 3 | wire_type
+    }
+
+    fn bytes_field(number: u8, data: &[u8]) -> Vec<u8> {
+        let mut field = vec![tag(number, 2), data.len() as u8];
+        field.extend_from_slice(data);
+        field
+    }
+
+    fn varint_field(number: u8, value: u8) -> Vec<u8> {
+        vec![tag(number, 0), value]
+    }
+
+    #[test]
+    fn test_decode_protobuf() {
+        let mut test_data = vec![8, 150, 1];
+        test_data.extend(bytes_field(2, b"apple"));
+        test_data.extend(bytes_field(3, &[8, 1, 8, 2]));
+        test_data.extend(bytes_field(4, &[0, 255]));
+        test_data.extend([tag(5, 5), 1, 0, 0, 0]);
+
+        let result = decode_protobuf(&test_data).unwrap();
+        assert_eq!(
+            result,
+            json!({
+                "1": { "wire_type": "varint", "value": 150 },
+                "2": { "wire_type": "length_delimited", "type": "string", "value": "apple" },
+                "3": { "wire_type": "length_delimited", "type": "message", "value": {
+                    "1": [
+                        { "wire_type": "varint", "value": 1 },
+                        { "wire_type": "varint", "value": 2 }
+                    ]
+                }},
+                "4": { "wire_type": "length_delimited", "type": "bytes", "value": "AP8=" },
+                "5": { "wire_type": "fixed32", "value": 1 }
+            })
+        );
+
+        assert!(decode_protobuf(&[8]).is_err());
+        assert!(decode_protobuf(&[18, 10, 1]).is_err());
+    }
+
+    #[test]
+    fn test_protobuf_schema() {
+        // enum Level { LOW = 0; HIGH = 1; }
+        let mut level_enum = bytes_field(1, b"Level");
+        level_enum.extend(bytes_field(
+            2,
+            &[bytes_field(1, b"LOW"), varint_field(2, 0)].concat(),
+        ));
+        level_enum.extend(bytes_field(
+            2,
+            &[bytes_field(1, b"HIGH"), varint_field(2, 1)].concat(),
+        ));
+
+        // message Battery { string name = 1; sint32 delta = 2; repeated uint32 cycles = 3; Level level = 4; }
+        let mut battery = bytes_field(1, b"Battery");
+        for (name, number, label, field_type, type_name) in [
+            ("name", 1, 1, 9, ""),
+            ("delta", 2, 1, 17, ""),
+            ("cycles", 3, 3, 13, ""),
+            ("level", 4, 1, 14, ".test.Level"),
+        ] {
+            let mut field = bytes_field(1, name.as_bytes());
+            field.extend(varint_field(3, number));
+            field.extend(varint_field(4, label));
+            field.extend(varint_field(5, field_type));
+            if !type_name.is_empty() {
+                field.extend(bytes_field(6, type_name.as_bytes()));
+            }
+            battery.extend(bytes_field(2, &field));
+        }
+
+        let mut file = bytes_field(2, b"test");
+        file.extend(bytes_field(4, &battery));
+        file.extend(bytes_field(5, &level_enum));
+        let descriptor_set = bytes_field(1, &file);
+
+        let schema = ProtobufSchema::from_descriptor_set(&descriptor_set).unwrap();
+        assert_eq!(schema.message_types(), vec!["test.Battery"]);
+
+        let mut test_data = bytes_field(1, b"internal");
+        test_data.extend(varint_field(2, 3));
+        test_data.extend(bytes_field(3, &[1, 2, 3]));
+        test_data.extend(varint_field(4, 1));
+        test_data.extend(varint_field(9, 7));
+
+        let result = schema.decode(".test.Battery", &test_data).unwrap();
+        assert_eq!(
+            result,
+            json!({
+                "name": "internal",
+                "delta": -2,
+                "cycles": [1, 2, 3],
+                "level": "HIGH",
+                "9": { "wire_type": "varint", "value": 7 }
+            })
+        );
+
+        assert!(schema.decode("test.Missing", &test_data).is_err());
+    }
+}
@@ -5,10 +5,15 @@
 // is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and limitations under the License.
 
-use chrono::{LocalResult, SecondsFormat, TimeZone, Utc};
+use crate::util::decode_standard;
+use chrono::{LocalResult, TimeZone, Utc};
 use log::error;
+use nom::number::complete::{le_i32, le_i64};
+use nom::sequence::tuple;
 
-/// Parse time data object
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Parse time data object. Ex: `2016-01-12 19:41:37`
 pub(crate) fn parse_time(data: &str) -> String {
     let timestamp_result = data.parse::<i64>();
     let timestamp = match timestamp_result {
@@ -21,28 +26,121 @@ pub(crate) fn parse_time(data: &str) -> String {
             return data.to_string();
         }
     };
+    format_time(timestamp, None, data)
+}
 
+/// Parse `struct timeval` binary data (`%{timeval}.*P`). Ex: `2016-01-12 19:41:37.774236`
+pub(crate) fn parse_timeval(data: &str) -> String {
+    let decoded_data = match decode_standard(data) {
+        Ok(result) => result,
+        Err(err) => {
+            error!(
+                "[macos-unifiedlogs] Failed to base64 decode timeval data {}, error: {:?}",
+                data, err
+            );
+            return String::from("Failed to base64 decode timeval details");
+        }
+    };
+    let timeval_results: nom::IResult<&[u8], (i64, i32)> =
+        tuple((le_i64, le_i32))(&decoded_data);
+    match timeval_results {
+        Ok((_, (seconds, microseconds))) => {
+            let fraction = format!("{:06}", microseconds);
+            format_time(seconds, Some(&fraction), data)
+        }
+        Err(err) => {
+            error!(
+                "[macos-unifiedlogs] Failed to parse timeval data {}, error: {:?}",
+                data, err
+            );
+            String::from("Failed to get timeval")
+        }
+    }
+}
+
+/// Parse `struct timespec` binary data (`%{timespec}.*P`). Ex: `2016-01-12 19:41:37.238238282`
+pub(crate) fn parse_timespec(data: &str) -> String {
+    let decoded_data = match decode_standard(data) {
+        Ok(result) => result,
+        Err(err) => {
+            error!(
+                "[macos-unifiedlogs] Failed to base64 decode timespec data {}, error: {:?}",
+                data, err
+            );
+            return String::from("Failed to base64 decode timespec details");
+        }
+    };
+    let timespec_results: nom::IResult<&[u8], (i64, i64)> =
+        tuple((le_i64, le_i64))(&decoded_data);
+    match timespec_results {
+        Ok((_, (seconds, nanoseconds))) => {
+            let fraction = format!("{:09}", nanoseconds);
+            format_time(seconds, Some(&fraction), data)
+        }
+        Err(err) => {
+            error!(
+                "[macos-unifiedlogs] Failed to parse timespec data {}, error: {:?}",
+                data, err
+            );
+            String::from("Failed to get timespec")
+        }
+    }
+}
+
+/// Format seconds since UNIXEPOCH with an optional fraction of a second
+fn format_time(seconds: i64, fraction: Option<&str>, data: &str) -> String {
     // Format to UTC, the log command will format to whatever the local time is for the system
-    let date_time_result = Utc.timestamp_opt(timestamp, 0);
-    match date_time_result {
-        LocalResult::None => format!("Could not parse time: {}", data),
-        LocalResult::Single(date_time) => date_time.to_rfc3339_opts(SecondsFormat::Millis, true),
-        LocalResult::Ambiguous(date_time, date_time2) => format!(
-            "Ambiguous time: {} or {}",
-            date_time.to_rfc3339_opts(SecondsFormat::Millis, true),
-            date_time2.to_rfc3339_opts(SecondsFormat::Millis, true)
-        ),
+    let date_time_result = Utc.timestamp_opt(seconds, 0);
+    let date_time = match date_time_result {
+        LocalResult::Single(date_time) => date_time.format(TIME_FORMAT).to_string(),
+        LocalResult::None => return format!("Could not parse time: {}", data),
+        LocalResult::Ambiguous(date_time, date_time2) => {
+            return format!(
+                "Ambiguous time: {} or {}",
+                date_time.format(TIME_FORMAT),
+                date_time2.format(TIME_FORMAT)
+            )
+        }
+    };
+    match fraction {
+        Some(value) => format!("{}.{}", date_time, value),
+        None => date_time,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::parse_time;
+    use super::{parse_time, parse_timespec, parse_timeval};
 
     #[test]
     fn test_parse_time() {
         let test_data = "1642302428";
         let result = parse_time(test_data);
-        assert_eq!(result, "2022-01-16T03:07:08.000Z")
+        assert_eq!(result, "2022-01-16 03:07:08");
+
+        let result = parse_time("1452627697");
+        assert_eq!(result, "2016-01-12 19:41:37");
+    }
+
+    #[test]
+    fn test_parse_timeval() {
+        // tv_sec 1452627697, tv_usec 774236 and padding
+        let test_data = "8VaVVgAAAABc0AsAAAAAAA==";
+        let result = parse_timeval(test_data);
+        assert_eq!(result, "2016-01-12 19:41:37.774236");
+
+        let result = parse_timeval("AAAA");
+        assert_eq!(result, "Failed to get timeval");
+    }
+
+    #[test]
+    fn test_parse_timespec() {
+        // tv_sec 1452627697, tv_nsec 238238282
+        let test_data = "8VaVVgAAAABKOjMOAAAAAA==";
+        let result = parse_timespec(test_data);
+        assert_eq!(result, "2016-01-12 19:41:37.238238282");
+
+        let result = parse_timespec("not base64!");
+        assert_eq!(result, "Failed to base64 decode timespec details");
     }
 }
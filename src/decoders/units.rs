@@ -0,0 +1,105 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+use log::error;
+
+const BYTE_UNITS: [&str; 7] = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+const IEC_BYTE_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+const BITRATE_UNITS: [&str; 7] = ["bps", "kbps", "Mbps", "Gbps", "Tbps", "Pbps", "Ebps"];
+const IEC_BITRATE_UNITS: [&str; 7] = ["bps", "Kibps", "Mibps", "Gibps", "Tibps", "Pibps", "Eibps"];
+
+/// Format a byte count using SI units. Ex: `4.72 kB`
+pub(crate) fn bytes(data: &str) -> String {
+    format_units(data, 1000.0, &BYTE_UNITS)
+}
+
+/// Format a byte count using IEC units. Ex: `4.61 KiB`
+pub(crate) fn iec_bytes(data: &str) -> String {
+    format_units(data, 1024.0, &IEC_BYTE_UNITS)
+}
+
+/// Format a bit rate using SI units. Ex: `123 kbps`
+pub(crate) fn bitrate(data: &str) -> String {
+    format_units(data, 1000.0, &BITRATE_UNITS)
+}
+
+/// Format a bit rate using IEC units. Ex: `118 Kibps`
+pub(crate) fn iec_bitrate(data: &str) -> String {
+    format_units(data, 1024.0, &IEC_BITRATE_UNITS)
+}
+
+/// Scale a value to the largest unit below `base` and show three significant digits
+fn format_units(data: &str, base: f64, units: &[&str]) -> String {
+    let value = match data.parse::<i64>() {
+        Ok(result) => result,
+        Err(err) => {
+            error!(
+                "[macos-unifiedlogs] Failed to parse unit value {}. Error: {:?}",
+                data, err
+            );
+            return data.to_string();
+        }
+    };
+    if value.unsigned_abs() < base as u64 {
+        return format!("{} {}", value, units[0]);
+    }
+
+    let mut scaled = value as f64;
+    let mut unit_index = 0;
+    // Round before checking the unit, 999.9 kB is shown as 1.00 MB
+    while unit_index + 1 < units.len() && round_significant(scaled.abs()) >= base {
+        scaled /= base;
+        unit_index += 1;
+    }
+
+    let precision = match scaled.abs() {
+        value if value >= 99.95 => 0,
+        value if value >= 9.995 => 1,
+        _ => 2,
+    };
+    format!("{:.*} {}", precision, scaled, units[unit_index])
+}
+
+/// Round to three significant digits
+fn round_significant(value: f64) -> f64 {
+    if value >= 99.95 {
+        value.round()
+    } else if value >= 9.995 {
+        (value * 10.0).round() / 10.0
+    } else {
+        (value * 100.0).round() / 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bitrate, bytes, iec_bitrate, iec_bytes};
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(bytes("4720"), "4.72 kB");
+        assert_eq!(bytes("999"), "999 B");
+        assert_eq!(bytes("12345678"), "12.3 MB");
+        assert_eq!(bytes("999999"), "1.00 MB");
+        assert_eq!(bytes("-4720"), "-4.72 kB");
+        assert_eq!(bytes("abc"), "abc");
+    }
+
+    #[test]
+    fn test_iec_bytes() {
+        assert_eq!(iec_bytes("4720"), "4.61 KiB");
+        assert_eq!(iec_bytes("1023"), "1023 B");
+        assert_eq!(iec_bytes("1073741824"), "1.00 GiB");
+    }
+
+    #[test]
+    fn test_bitrate() {
+        assert_eq!(bitrate("123000"), "123 kbps");
+        assert_eq!(bitrate("500"), "500 bps");
+        assert_eq!(iec_bitrate("121000"), "118 Kibps");
+    }
+}
@@ -79,6 +79,14 @@ pub enum ParserError {
     Timesync,
     Dsc,
     UUIDText,
+    Write,
+    Sqlite,
+    Arrow,
+    Network,
+    Predicate,
+    Query,
+    Protobuf,
+    Plist,
 }
 
 impl std::error::Error for ParserError {}
@@ -93,6 +101,14 @@ impl fmt::Display for ParserError {
             ParserError::Timesync => write!(f, "Failed to parse timesync file"),
             ParserError::Dsc => write!(f, "Failed to parse dsc file"),
             ParserError::UUIDText => write!(f, "Failedto parse UUIDtext file"),
+            ParserError::Write => write!(f, "Failed to write log data"),
+            ParserError::Sqlite => write!(f, "Failed to write SQLite database"),
+            ParserError::Arrow => write!(f, "Failed to write Arrow or Parquet data"),
+            ParserError::Network => write!(f, "Failed to send log data"),
+            ParserError::Predicate => write!(f, "Failed to parse predicate"),
+            ParserError::Query => write!(f, "Invalid query option"),
+            ParserError::Protobuf => write!(f, "Failed to decode protocol buffer data"),
+            ParserError::Plist => write!(f, "Failed to decode plist data"),
         }
     }
 }
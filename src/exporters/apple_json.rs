@@ -0,0 +1,349 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Write reconstructed Unified Log entries using the field names of Apple's `log show --style ndjson` and `--style json`
+//!
+//! Only values this crate reconstructs are written. Fields such as `source`, `backtrace`, `senderProgramCounter`
+//! and `parentActivityIdentifier` are not emitted
+
+use crate::error::ParserError;
+use crate::unified_log::LogData;
+use crate::util::{hyphenate_uuid, unixepoch_to_local};
+use chrono::{FixedOffset, Offset, Utc};
+use log::error;
+use serde::Serialize;
+use std::io::Write;
+
+/// Output layout matching the `--style` argument of `log show`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AppleJsonStyle {
+    /// One JSON object per line (`--style ndjson`)
+    #[default]
+    Ndjson,
+    /// A single JSON array containing every entry (`--style json`)
+    Json,
+}
+
+/// Options for `AppleJsonWriter`
+#[derive(Debug, Clone, Copy)]
+pub struct AppleJsonOptions {
+    pub style: AppleJsonStyle,
+    /// Offset used to render the `timestamp` field. `log show` uses the local timezone of the system running it
+    pub timezone: FixedOffset,
+}
+
+impl Default for AppleJsonOptions {
+    fn default() -> Self {
+        AppleJsonOptions {
+            style: AppleJsonStyle::default(),
+            timezone: Utc.fix(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppleJsonRecord<'a> {
+    #[serde(rename = "traceID")]
+    trace_id: u64,
+    event_message: &'a str,
+    event_type: &'static str,
+    format_string: &'a str,
+    activity_identifier: u64,
+    subsystem: &'a str,
+    category: &'a str,
+    #[serde(rename = "threadID")]
+    thread_id: u64,
+    #[serde(rename = "senderImageUUID")]
+    sender_image_uuid: String,
+    #[serde(rename = "bootUUID")]
+    boot_uuid: String,
+    process_image_path: &'a str,
+    sender_image_path: &'a str,
+    timestamp: String,
+    mach_timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_type: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signpost_scope: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signpost_type: Option<&'static str>,
+    #[serde(rename = "processImageUUID")]
+    process_image_uuid: String,
+    #[serde(rename = "processID")]
+    process_id: u64,
+    timezone_name: &'a str,
+}
+
+/// Stream `LogData` entries to any writer using the schema of `log show --style ndjson/json`
+pub struct AppleJsonWriter<W: Write> {
+    writer: W,
+    options: AppleJsonOptions,
+    entries_written: u64,
+}
+
+impl<W: Write> AppleJsonWriter<W> {
+    /// Create a writer producing `--style ndjson` output with UTC timestamps
+    pub fn new(writer: W) -> AppleJsonWriter<W> {
+        AppleJsonWriter::with_options(writer, AppleJsonOptions::default())
+    }
+
+    /// Create a writer with the provided options
+    pub fn with_options(writer: W, options: AppleJsonOptions) -> AppleJsonWriter<W> {
+        AppleJsonWriter {
+            writer,
+            options,
+            entries_written: 0,
+        }
+    }
+
+    /// Write a single log entry
+    pub fn write_entry(&mut self, log_data: &LogData) -> Result<(), ParserError> {
+        let (signpost_scope, signpost_type) = signpost_info(&log_data.log_type);
+        let record = AppleJsonRecord {
+            trace_id: log_data.trace_id,
+            event_message: &log_data.message,
+            event_type: event_type(&log_data.event_type, &log_data.log_type),
+            format_string: &log_data.raw_message,
+            activity_identifier: log_data.activity_id,
+            subsystem: &log_data.subsystem,
+            category: &log_data.category,
+            thread_id: log_data.thread_id,
+            sender_image_uuid: hyphenate_uuid(&log_data.library_uuid),
+            boot_uuid: hyphenate_uuid(&log_data.boot_uuid),
+            process_image_path: &log_data.process,
+            sender_image_path: &log_data.library,
+            timestamp: apple_timestamp(&log_data.time, &self.options.timezone),
+            mach_timestamp: log_data.continuous_time,
+            message_type: message_type(&log_data.event_type, &log_data.log_type),
+            signpost_scope,
+            signpost_type,
+            process_image_uuid: hyphenate_uuid(&log_data.process_uuid),
+            process_id: log_data.pid,
+            timezone_name: &log_data.timezone_name,
+        };
+
+        let separator: &[u8] = match self.options.style {
+            AppleJsonStyle::Ndjson => b"",
+            AppleJsonStyle::Json if self.entries_written == 0 => b"[",
+            AppleJsonStyle::Json => b",\n",
+        };
+        if let Err(err) = self.writer.write_all(separator) {
+            error!(
+                "[macos-unifiedlogs] Failed to write JSON entry separator: {:?}",
+                err
+            );
+            return Err(ParserError::Write);
+        }
+        if let Err(err) = serde_json::to_writer(&mut self.writer, &record) {
+            error!(
+                "[macos-unifiedlogs] Failed to serialize log entry to Apple JSON: {:?}",
+                err
+            );
+            return Err(ParserError::Write);
+        }
+        if self.options.style == AppleJsonStyle::Ndjson {
+            if let Err(err) = self.writer.write_all(b"\n") {
+                error!(
+                    "[macos-unifiedlogs] Failed to write JSON line separator: {:?}",
+                    err
+                );
+                return Err(ParserError::Write);
+            }
+        }
+        self.entries_written += 1;
+        Ok(())
+    }
+
+    /// Write all log entries
+    pub fn write_entries(&mut self, log_data: &[LogData]) -> Result<(), ParserError> {
+        for entry in log_data {
+            self.write_entry(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Close the JSON array (`--style json` only) and flush the underlying writer. Call once after the last entry
+    pub fn finish(&mut self) -> Result<(), ParserError> {
+        let closing: &[u8] = match self.options.style {
+            AppleJsonStyle::Ndjson => b"",
+            AppleJsonStyle::Json if self.entries_written == 0 => b"[]\n",
+            AppleJsonStyle::Json => b"]\n",
+        };
+        if let Err(err) = self.writer.write_all(closing) {
+            error!("[macos-unifiedlogs] Failed to close JSON array: {:?}", err);
+            return Err(ParserError::Write);
+        }
+        if let Err(err) = self.writer.flush() {
+            error!("[macos-unifiedlogs] Failed to flush Apple JSON: {:?}", err);
+            return Err(ParserError::Write);
+        }
+        Ok(())
+    }
+
+    /// Number of log entries written so far
+    pub fn entries_written(&self) -> u64 {
+        self.entries_written
+    }
+
+    /// Return the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Format the timestamp like `log show` (ex: `2022-01-15 19:05:26.434850-0800`)
+fn apple_timestamp(time: &f64, timezone: &FixedOffset) -> String {
+    unixepoch_to_local(time, timezone, "%Y-%m-%d %H:%M:%S%.6f%z")
+}
+
+/// Map the `LogData` event type to the `eventType` names used by `log show`
+fn event_type(event_type: &str, log_type: &str) -> &'static str {
+    match event_type {
+        "Log" if log_type == "Useraction" => "userActionEvent",
+        "Log" | "Simpledump" => "logEvent",
+        "Activity" => "activityCreateEvent",
+        "Trace" => "traceEvent",
+        "Signpost" => "signpostEvent",
+        "Loss" => "lossEvent",
+        "Statedump" => "stateEvent",
+        _ => "unknownEvent",
+    }
+}
+
+/// Only log and trace events have a `messageType`
+fn message_type<'a>(event_type: &str, log_type: &'a str) -> Option<&'a str> {
+    match event_type {
+        "Log" | "Trace" | "Simpledump" if log_type != "Useraction" => {
+            if log_type.is_empty() {
+                Some("Default")
+            } else {
+                Some(log_type)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Split the signpost log type (ex: `Process Signpost Start`) into `signpostScope` and `signpostType`
+fn signpost_info(log_type: &str) -> (Option<&'static str>, Option<&'static str>) {
+    let mut parts = log_type.split(" Signpost ");
+    let (Some(scope), Some(kind)) = (parts.next(), parts.next()) else {
+        return (None, None);
+    };
+    let scope = match scope {
+        "Process" => "process",
+        "Thread" => "thread",
+        "System" => "system",
+        _ => return (None, None),
+    };
+    let kind = match kind {
+        "Start" => "begin",
+        "End" => "end",
+        "Event" => "event",
+        _ => return (None, None),
+    };
+    (Some(scope), Some(kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apple_timestamp, signpost_info, AppleJsonOptions, AppleJsonStyle, AppleJsonWriter,
+    };
+    use crate::unified_log::test_log_data;
+    use chrono::FixedOffset;
+    use serde_json::Value;
+
+    #[test]
+    fn test_write_entry_ndjson() {
+        let mut writer = AppleJsonWriter::new(Vec::new());
+        writer.write_entry(&test_log_data()).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(writer.entries_written(), 1);
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(output.lines().count(), 1);
+
+        let result: Value = serde_json::from_str(output.trim_end()).unwrap();
+        assert_eq!(result["eventMessage"], "LOMD Start 1");
+        assert_eq!(result["formatString"], "LOMD Start %d");
+        assert_eq!(result["eventType"], "logEvent");
+        assert_eq!(result["messageType"], "Default");
+        assert_eq!(
+            result["processImagePath"],
+            "/usr/libexec/lightsoutmanagementd"
+        );
+        assert_eq!(
+            result["senderImagePath"],
+            "/usr/libexec/lightsoutmanagementd"
+        );
+        assert_eq!(
+            result["senderImageUUID"],
+            "6C3ADF99-1F03-3C1C-96C4-ADFAA12D8CED"
+        );
+        assert_eq!(result["bootUUID"], "80D194AF-56A3-4C54-8674-49D2130D41BB");
+        assert_eq!(result["subsystem"], "com.apple.lom");
+        assert_eq!(result["category"], "device");
+        assert_eq!(result["machTimestamp"], 11318876544_u64);
+        assert_eq!(result["traceID"], 0x93773c8310004_u64);
+        assert_eq!(result["threadID"], 588);
+        assert_eq!(result["processID"], 45);
+        assert_eq!(result["timezoneName"], "Pacific");
+        assert_eq!(result["timestamp"], "2022-01-16 03:05:26.434850+0000");
+        assert!(result.get("signpostType").is_none());
+    }
+
+    #[test]
+    fn test_write_entries_json() {
+        let options = AppleJsonOptions {
+            style: AppleJsonStyle::Json,
+            ..Default::default()
+        };
+        let mut writer = AppleJsonWriter::with_options(Vec::new(), options);
+        writer
+            .write_entries(&[test_log_data(), test_log_data()])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        let result: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(result.as_array().unwrap().len(), 2);
+        assert_eq!(result[1]["eventMessage"], "LOMD Start 1");
+    }
+
+    #[test]
+    fn test_finish_empty_json() {
+        let options = AppleJsonOptions {
+            style: AppleJsonStyle::Json,
+            ..Default::default()
+        };
+        let mut writer = AppleJsonWriter::with_options(Vec::new(), options);
+        writer.finish().unwrap();
+        assert_eq!(writer.into_inner(), b"[]\n");
+    }
+
+    #[test]
+    fn test_apple_timestamp() {
+        let pacific = FixedOffset::west_opt(8 * 3600).unwrap();
+        let result = apple_timestamp(&1.6423023264348508e18, &pacific);
+        assert_eq!(result, "2022-01-15 19:05:26.434850-0800");
+    }
+
+    #[test]
+    fn test_signpost_info() {
+        assert_eq!(
+            signpost_info("Process Signpost Start"),
+            (Some("process"), Some("begin"))
+        );
+        assert_eq!(
+            signpost_info("Thread Signpost End"),
+            (Some("thread"), Some("end"))
+        );
+        assert_eq!(signpost_info("Default"), (None, None));
+    }
+}
@@ -0,0 +1,350 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Convert reconstructed Unified Log entries to Arrow `RecordBatch`es and write Parquet files (requires the `arrow` feature)
+//!
+//! Timestamps are stored as nanoseconds in UTC. Low cardinality strings (process, subsystem, category, format string, etc)
+//! are dictionary encoded. `ParquetWriter` can be fed directly from `parser::stream_log()`
+
+use crate::chunks::firehose::firehose_log::FirehoseItemInfo;
+use crate::error::ParserError;
+use crate::unified_log::LogData;
+use arrow_array::types::Int32Type;
+use arrow_array::{
+    ArrayRef, DictionaryArray, ListArray, RecordBatch, StringArray, StructArray,
+    TimestampNanosecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+};
+use arrow_buffer::OffsetBuffer;
+use arrow_schema::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+use log::error;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Default number of log entries per `RecordBatch` written by `ParquetWriter`
+pub const DEFAULT_BATCH_SIZE: usize = 65536;
+
+fn dictionary_field(name: &str) -> Field {
+    Field::new(
+        name,
+        DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+        false,
+    )
+}
+
+fn message_entry_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("message_strings", DataType::Utf8, false),
+        Field::new("item_type", DataType::UInt8, false),
+        Field::new("item_size", DataType::UInt16, false),
+    ])
+}
+
+fn message_entry_item() -> Arc<Field> {
+    Arc::new(Field::new(
+        "item",
+        DataType::Struct(message_entry_fields()),
+        false,
+    ))
+}
+
+/// Arrow schema used for `LogData` record batches
+pub fn log_data_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new(
+            "time",
+            DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".into())),
+            false,
+        ),
+        Field::new("continuous_time", DataType::UInt64, false),
+        Field::new("trace_id", DataType::UInt64, false),
+        dictionary_field("boot_uuid"),
+        dictionary_field("timezone_name"),
+        dictionary_field("event_type"),
+        dictionary_field("log_type"),
+        Field::new("pid", DataType::UInt64, false),
+        Field::new("euid", DataType::UInt32, false),
+        Field::new("thread_id", DataType::UInt64, false),
+        Field::new("activity_id", DataType::UInt64, false),
+        Field::new("ttl", DataType::UInt8, false),
+        dictionary_field("process"),
+        dictionary_field("process_uuid"),
+        dictionary_field("library"),
+        dictionary_field("library_uuid"),
+        dictionary_field("subsystem"),
+        dictionary_field("category"),
+        dictionary_field("raw_message"),
+        Field::new("message", DataType::Utf8, false),
+        Field::new(
+            "message_entries",
+            DataType::List(message_entry_item()),
+            false,
+        ),
+    ]))
+}
+
+/// Convert log entries into a single Arrow `RecordBatch` using `log_data_schema()`
+pub fn to_record_batch(log_data: &[LogData]) -> Result<RecordBatch, ParserError> {
+    let dictionary = |value: fn(&LogData) -> &str| -> ArrayRef {
+        Arc::new(
+            log_data
+                .iter()
+                .map(value)
+                .collect::<DictionaryArray<Int32Type>>(),
+        )
+    };
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            TimestampNanosecondArray::from_iter_values(
+                log_data.iter().map(|entry| entry.time as i64),
+            )
+            .with_timezone("UTC"),
+        ),
+        Arc::new(UInt64Array::from_iter_values(
+            log_data.iter().map(|entry| entry.continuous_time),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            log_data.iter().map(|entry| entry.trace_id),
+        )),
+        dictionary(|entry| &entry.boot_uuid),
+        dictionary(|entry| &entry.timezone_name),
+        dictionary(|entry| &entry.event_type),
+        dictionary(|entry| &entry.log_type),
+        Arc::new(UInt64Array::from_iter_values(
+            log_data.iter().map(|entry| entry.pid),
+        )),
+        Arc::new(UInt32Array::from_iter_values(
+            log_data.iter().map(|entry| entry.euid),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            log_data.iter().map(|entry| entry.thread_id),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            log_data.iter().map(|entry| entry.activity_id),
+        )),
+        Arc::new(UInt8Array::from_iter_values(
+            log_data.iter().map(|entry| entry.ttl),
+        )),
+        dictionary(|entry| &entry.process),
+        dictionary(|entry| &entry.process_uuid),
+        dictionary(|entry| &entry.library),
+        dictionary(|entry| &entry.library_uuid),
+        dictionary(|entry| &entry.subsystem),
+        dictionary(|entry| &entry.category),
+        dictionary(|entry| &entry.raw_message),
+        Arc::new(StringArray::from_iter_values(
+            log_data.iter().map(|entry| &entry.message),
+        )),
+        message_entries_array(log_data)?,
+    ];
+
+    match RecordBatch::try_new(log_data_schema(), columns) {
+        Ok(batch) => Ok(batch),
+        Err(err) => {
+            error!(
+                "[macos-unifiedlogs] Failed to create Arrow record batch: {:?}",
+                err
+            );
+            Err(ParserError::Arrow)
+        }
+    }
+}
+
+/// Build the `message_entries` list column
+fn message_entries_array(log_data: &[LogData]) -> Result<ArrayRef, ParserError> {
+    let items: Vec<&FirehoseItemInfo> = log_data
+        .iter()
+        .flat_map(|entry| &entry.message_entries)
+        .collect();
+    let values = StructArray::try_new(
+        message_entry_fields(),
+        vec![
+            Arc::new(StringArray::from_iter_values(
+                items.iter().map(|item| &item.message_strings),
+            )),
+            Arc::new(UInt8Array::from_iter_values(
+                items.iter().map(|item| item.item_type),
+            )),
+            Arc::new(UInt16Array::from_iter_values(
+                items.iter().map(|item| item.item_size),
+            )),
+        ],
+        None,
+    );
+    let values = match values {
+        Ok(result) => result,
+        Err(err) => {
+            error!(
+                "[macos-unifiedlogs] Failed to create Arrow message entries: {:?}",
+                err
+            );
+            return Err(ParserError::Arrow);
+        }
+    };
+
+    let offsets =
+        OffsetBuffer::from_lengths(log_data.iter().map(|entry| entry.message_entries.len()));
+    match ListArray::try_new(message_entry_item(), offsets, Arc::new(values), None) {
+        Ok(result) => Ok(Arc::new(result)),
+        Err(err) => {
+            error!(
+                "[macos-unifiedlogs] Failed to create Arrow message entries list: {:?}",
+                err
+            );
+            Err(ParserError::Arrow)
+        }
+    }
+}
+
+/// Stream `LogData` entries to a Parquet file
+pub struct ParquetWriter<W: Write + Send> {
+    writer: ArrowWriter<W>,
+    batch_size: usize,
+    entries_written: u64,
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    /// Create a Parquet writer using Snappy compression and `DEFAULT_BATCH_SIZE` entries per record batch
+    pub fn new(writer: W) -> Result<ParquetWriter<W>, ParserError> {
+        ParquetWriter::with_batch_size(writer, DEFAULT_BATCH_SIZE)
+    }
+
+    /// Create a Parquet writer that splits written entries into record batches of `batch_size` entries
+    pub fn with_batch_size(writer: W, batch_size: usize) -> Result<ParquetWriter<W>, ParserError> {
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        match ArrowWriter::try_new(writer, log_data_schema(), Some(properties)) {
+            Ok(result) => Ok(ParquetWriter {
+                writer: result,
+                batch_size: batch_size.max(1),
+                entries_written: 0,
+            }),
+            Err(err) => {
+                error!(
+                    "[macos-unifiedlogs] Failed to create Parquet writer: {:?}",
+                    err
+                );
+                Err(ParserError::Arrow)
+            }
+        }
+    }
+
+    /// Write log entries. Can be called once per chunkset from `parser::stream_log()`
+    pub fn write_entries(&mut self, log_data: &[LogData]) -> Result<(), ParserError> {
+        for entries in log_data.chunks(self.batch_size) {
+            let batch = to_record_batch(entries)?;
+            if let Err(err) = self.writer.write(&batch) {
+                error!(
+                    "[macos-unifiedlogs] Failed to write Parquet record batch: {:?}",
+                    err
+                );
+                return Err(ParserError::Arrow);
+            }
+            self.entries_written += entries.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Number of log entries written so far
+    pub fn entries_written(&self) -> u64 {
+        self.entries_written
+    }
+
+    /// Write the Parquet footer and return the underlying writer. Must be called once after the last entry
+    pub fn close(self) -> Result<W, ParserError> {
+        match self.writer.into_inner() {
+            Ok(result) => Ok(result),
+            Err(err) => {
+                error!(
+                    "[macos-unifiedlogs] Failed to close Parquet writer: {:?}",
+                    err
+                );
+                Err(ParserError::Arrow)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{log_data_schema, to_record_batch, ParquetWriter};
+    use crate::unified_log::test_log_data;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Int32Type, TimestampNanosecondType, UInt64Type};
+    use arrow_array::Array;
+    use arrow_schema::DataType;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use std::fs::{self, File};
+
+    #[test]
+    fn test_to_record_batch() {
+        let mut second_entry = test_log_data();
+        second_entry.message_entries = Vec::new();
+        let batch = to_record_batch(&[test_log_data(), second_entry]).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema(), log_data_schema());
+
+        let time = batch
+            .column_by_name("time")
+            .unwrap()
+            .as_primitive::<TimestampNanosecondType>();
+        assert_eq!(time.value(0), 1642302326434850816);
+
+        let trace_id = batch
+            .column_by_name("trace_id")
+            .unwrap()
+            .as_primitive::<UInt64Type>();
+        assert_eq!(trace_id.value(1), 0x93773c8310004);
+
+        let subsystem = batch
+            .column_by_name("subsystem")
+            .unwrap()
+            .as_dictionary::<Int32Type>();
+        assert_eq!(subsystem.values().len(), 1);
+        assert_eq!(
+            subsystem.values().as_string::<i32>().value(0),
+            "com.apple.lom"
+        );
+
+        let message_entries = batch
+            .column_by_name("message_entries")
+            .unwrap()
+            .as_list::<i32>();
+        assert_eq!(message_entries.value_length(0), 1);
+        assert_eq!(message_entries.value_length(1), 0);
+    }
+
+    #[test]
+    fn test_parquet_writer() {
+        let path = std::env::temp_dir().join(format!(
+            "macos_unifiedlogs_parquet_{}.parquet",
+            std::process::id()
+        ));
+        let mut writer = ParquetWriter::with_batch_size(File::create(&path).unwrap(), 2).unwrap();
+        writer
+            .write_entries(&[test_log_data(), test_log_data(), test_log_data()])
+            .unwrap();
+        assert_eq!(writer.entries_written(), 3);
+        writer.close().unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<_> = reader.map(|batch| batch.unwrap()).collect();
+        let rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        assert_eq!(rows, 3);
+
+        let process = batches[0].column_by_name("process").unwrap();
+        assert!(matches!(process.data_type(), DataType::Dictionary(_, _)));
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Write reconstructed Unified Log entries as JSON Lines (one JSON object per line)
+//!
+//! Every line carries a `schema_version` field. Field names and types are only changed
+//! together with a bump of [`JSONL_SCHEMA_VERSION`]
+
+use crate::chunks::firehose::firehose_log::FirehoseItemInfo;
+use crate::error::ParserError;
+use crate::unified_log::LogData;
+use crate::util::unixepoch_to_iso;
+use log::error;
+use serde::Serialize;
+use std::io::Write;
+
+/// Version of the JSON Lines field schema written by `JsonlWriter`
+/// Version 2 added the typed `message_value` of each `message_entries` item
+pub const JSONL_SCHEMA_VERSION: u32 = 2;
+
+/// Optional fields to include in each JSON Lines record
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonlOptions {
+    /// Include the parsed message items (`message_entries`) used to build the message
+    pub include_message_entries: bool,
+    /// Include the base format string (`raw_message`) from the `UUIDText` or dsc file
+    pub include_raw_message: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonlRecord<'a> {
+    schema_version: u32,
+    timestamp: String,
+    timestamp_ns: i64,
+    boot_uuid: &'a str,
+    timezone_name: &'a str,
+    event_type: &'a str,
+    log_type: &'a str,
+    pid: u64,
+    euid: u32,
+    thread_id: u64,
+    activity_id: u64,
+    process: &'a str,
+    process_uuid: &'a str,
+    library: &'a str,
+    library_uuid: &'a str,
+    subsystem: &'a str,
+    category: &'a str,
+    message: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    raw_message: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message_entries: Option<&'a [FirehoseItemInfo]>,
+}
+
+/// Stream `LogData` entries to any writer as JSON Lines
+pub struct JsonlWriter<W: Write> {
+    writer: W,
+    options: JsonlOptions,
+    entries_written: u64,
+}
+
+impl<W: Write> JsonlWriter<W> {
+    /// Create a JSON Lines writer using the default options (no raw message or message entries)
+    pub fn new(writer: W) -> JsonlWriter<W> {
+        JsonlWriter::with_options(writer, JsonlOptions::default())
+    }
+
+    /// Create a JSON Lines writer with the provided options
+    pub fn with_options(writer: W, options: JsonlOptions) -> JsonlWriter<W> {
+        JsonlWriter {
+            writer,
+            options,
+            entries_written: 0,
+        }
+    }
+
+    /// Write a single log entry as one JSON line
+    pub fn write_entry(&mut self, log_data: &LogData) -> Result<(), ParserError> {
+        let record = JsonlRecord {
+            schema_version: JSONL_SCHEMA_VERSION,
+            timestamp: unixepoch_to_iso(&log_data.time),
+            timestamp_ns: log_data.time as i64,
+            boot_uuid: &log_data.boot_uuid,
+            timezone_name: &log_data.timezone_name,
+            event_type: &log_data.event_type,
+            log_type: &log_data.log_type,
+            pid: log_data.pid,
+            euid: log_data.euid,
+            thread_id: log_data.thread_id,
+            activity_id: log_data.activity_id,
+            process: &log_data.process,
+            process_uuid: &log_data.process_uuid,
+            library: &log_data.library,
+            library_uuid: &log_data.library_uuid,
+            subsystem: &log_data.subsystem,
+            category: &log_data.category,
+            message: &log_data.message,
+            raw_message: if self.options.include_raw_message {
+                Some(&log_data.raw_message)
+            } else {
+                None
+            },
+            message_entries: if self.options.include_message_entries {
+                Some(&log_data.message_entries)
+            } else {
+                None
+            },
+        };
+
+        if let Err(err) = serde_json::to_writer(&mut self.writer, &record) {
+            error!(
+                "[macos-unifiedlogs] Failed to serialize log entry to JSON: {:?}",
+                err
+            );
+            return Err(ParserError::Write);
+        }
+        if let Err(err) = self.writer.write_all(b"\n") {
+            error!(
+                "[macos-unifiedlogs] Failed to write JSON line separator: {:?}",
+                err
+            );
+            return Err(ParserError::Write);
+        }
+        self.entries_written += 1;
+        Ok(())
+    }
+
+    /// Write all log entries, one JSON line per entry
+    pub fn write_entries(&mut self, log_data: &[LogData]) -> Result<(), ParserError> {
+        for entry in log_data {
+            self.write_entry(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> Result<(), ParserError> {
+        if let Err(err) = self.writer.flush() {
+            error!("[macos-unifiedlogs] Failed to flush JSON lines: {:?}", err);
+            return Err(ParserError::Write);
+        }
+        Ok(())
+    }
+
+    /// Number of log entries written so far
+    pub fn entries_written(&self) -> u64 {
+        self.entries_written
+    }
+
+    /// Return the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JsonlOptions, JsonlWriter, JSONL_SCHEMA_VERSION};
+    use crate::unified_log::test_log_data;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_write_entry() {
+        let mut writer = JsonlWriter::new(Vec::new());
+        writer.write_entry(&test_log_data()).unwrap();
+        assert_eq!(writer.entries_written(), 1);
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert!(output.ends_with('\n'));
+
+        let result: Value = serde_json::from_str(output.trim_end()).unwrap();
+        assert_eq!(result["schema_version"], JSONL_SCHEMA_VERSION);
+        assert_eq!(result["timestamp"], "2022-01-16T03:05:26.434850816Z");
+        assert_eq!(result["timestamp_ns"], 1642302326434850816_i64);
+        assert_eq!(result["pid"], 45);
+        assert_eq!(result["subsystem"], "com.apple.lom");
+        assert_eq!(result["message"], "LOMD Start 1");
+        assert!(result.get("raw_message").is_none());
+        assert!(result.get("message_entries").is_none());
+    }
+
+    #[test]
+    fn test_write_entry_with_options() {
+        let options = JsonlOptions {
+            include_message_entries: true,
+            include_raw_message: true,
+        };
+        let mut writer = JsonlWriter::with_options(Vec::new(), options);
+        writer.write_entry(&test_log_data()).unwrap();
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        let result: Value = serde_json::from_str(output.trim_end()).unwrap();
+        assert_eq!(result["raw_message"], "LOMD Start %d");
+        assert_eq!(result["message_entries"][0]["message_strings"], "1");
+        assert_eq!(result["message_entries"][0]["item_size"], 4);
+        assert_eq!(
+            result["message_entries"][0]["message_value"],
+            json!({ "type": "signed", "value": 1 })
+        );
+    }
+
+    #[test]
+    fn test_write_entries() {
+        let mut writer = JsonlWriter::new(Vec::new());
+        writer
+            .write_entries(&[test_log_data(), test_log_data()])
+            .unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.entries_written(), 2);
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(output.lines().count(), 2);
+    }
+}
@@ -0,0 +1,19 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+pub mod apple_json;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod jsonl;
+pub mod otlp;
+pub mod siem;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod syslog;
+pub mod text;
+pub mod timeline;
+pub mod trace_event;
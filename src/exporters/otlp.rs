@@ -0,0 +1,379 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Forward reconstructed Unified Log entries as OpenTelemetry log records using OTLP/HTTP with JSON encoding
+//!
+//! Entries are grouped into one resource per process (`service.name` is the process image name).
+//! Only plain `http://` endpoints are supported, use a local collector to forward to TLS endpoints
+
+use crate::error::ParserError;
+use crate::exporters::siem::HostContext;
+use crate::unified_log::LogData;
+use crate::util::{hyphenate_uuid, image_name};
+use log::error;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Instrumentation scope name set on every batch
+const SCOPE_NAME: &str = "macos-unifiedlogs";
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Send `LogData` entries to an OTLP/HTTP logs endpoint (ex: `http://127.0.0.1:4318/v1/logs`)
+pub struct OtlpSink {
+    host: String,
+    path: String,
+    context: HostContext,
+    records_sent: u64,
+}
+
+impl OtlpSink {
+    /// Create a sink for an `http://host:port/path` endpoint. No connection is made until entries are sent
+    pub fn new(endpoint: &str) -> Result<OtlpSink, ParserError> {
+        let address = match endpoint.strip_prefix("http://") {
+            Some(result) => result,
+            None => {
+                error!(
+                    "[macos-unifiedlogs] Unsupported OTLP endpoint, only http:// is supported: {}",
+                    endpoint
+                );
+                return Err(ParserError::Network);
+            }
+        };
+        let (host, path) = match address.find('/') {
+            Some(index) => (&address[..index], &address[index..]),
+            None => (address, "/v1/logs"),
+        };
+        let host = if host.contains(':') {
+            host.to_string()
+        } else {
+            format!("{}:80", host)
+        };
+
+        Ok(OtlpSink {
+            host,
+            path: path.to_string(),
+            context: HostContext::default(),
+            records_sent: 0,
+        })
+    }
+
+    /// Host information added to the resource attributes
+    pub fn set_host(&mut self, context: HostContext) {
+        self.context = context;
+    }
+
+    /// Send the log entries as one OTLP export request
+    pub fn send_entries(&mut self, log_data: &[LogData]) -> Result<(), ParserError> {
+        if log_data.is_empty() {
+            return Ok(());
+        }
+        let body = to_otlp_json(log_data, &self.context).to_string();
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            body.len(),
+            body
+        );
+
+        let response = match post(&self.host, &request) {
+            Ok(result) => result,
+            Err(err) => {
+                error!(
+                    "[macos-unifiedlogs] Failed to send OTLP request to {}: {:?}",
+                    self.host, err
+                );
+                return Err(ParserError::Network);
+            }
+        };
+
+        // Status line: HTTP/1.1 200 OK
+        let status = response.split(' ').nth(1).unwrap_or_default();
+        if !status.starts_with('2') {
+            error!(
+                "[macos-unifiedlogs] OTLP endpoint rejected log records: {}",
+                response.lines().next().unwrap_or_default()
+            );
+            return Err(ParserError::Network);
+        }
+        self.records_sent += log_data.len() as u64;
+        Ok(())
+    }
+
+    /// Number of log records accepted by the endpoint so far
+    pub fn records_sent(&self) -> u64 {
+        self.records_sent
+    }
+}
+
+/// Write the request and read the full response
+fn post(host: &str, request: &str) -> std::io::Result<String> {
+    let mut stream = TcpStream::connect(host)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    stream.write_all(request.as_bytes())?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    Ok(String::from_utf8_lossy(&response).to_string())
+}
+
+/// Build an OTLP `ExportLogsServiceRequest` in the JSON encoding
+pub fn to_otlp_json(log_data: &[LogData], host: &HostContext) -> Value {
+    let mut processes: BTreeMap<(&str, u64), Vec<Value>> = BTreeMap::new();
+    for entry in log_data {
+        processes
+            .entry((&entry.process, entry.pid))
+            .or_default()
+            .push(to_log_record(entry));
+    }
+
+    let resource_logs: Vec<Value> = processes
+        .into_iter()
+        .map(|((process, pid), records)| {
+            let mut attributes = vec![
+                string_attribute("service.name", image_name(process)),
+                string_attribute("process.executable.path", process),
+                int_attribute("process.pid", pid),
+                string_attribute("os.type", "darwin"),
+            ];
+            if !host.os_version.is_empty() {
+                attributes.push(string_attribute("os.build_id", &host.os_version));
+            }
+            if !host.hostname.is_empty() {
+                attributes.push(string_attribute("host.name", &host.hostname));
+            }
+            if !host.hardware_model.is_empty() {
+                attributes.push(string_attribute("host.type", &host.hardware_model));
+            }
+            json!({
+                "resource": { "attributes": attributes },
+                "scopeLogs": [{
+                    "scope": { "name": SCOPE_NAME, "version": env!("CARGO_PKG_VERSION") },
+                    "logRecords": records,
+                }],
+            })
+        })
+        .collect();
+
+    json!({ "resourceLogs": resource_logs })
+}
+
+/// Map a log entry to an OTLP `LogRecord`. 64 bit integers are encoded as strings (see `int_attribute`)
+fn to_log_record(log_data: &LogData) -> Value {
+    let time = (log_data.time as u64).to_string();
+    let mut attributes = vec![
+        int_attribute("thread.id", log_data.thread_id),
+        string_attribute("unified_log.event_type", &log_data.event_type),
+        string_attribute("unified_log.format_string", &log_data.raw_message),
+        string_attribute(
+            "unified_log.boot_uuid",
+            &hyphenate_uuid(&log_data.boot_uuid),
+        ),
+        int_attribute("unified_log.trace_id", log_data.trace_id),
+    ];
+    if !log_data.subsystem.is_empty() {
+        attributes.push(string_attribute(
+            "unified_log.subsystem",
+            &log_data.subsystem,
+        ));
+        attributes.push(string_attribute("unified_log.category", &log_data.category));
+    }
+    if log_data.activity_id != 0 {
+        attributes.push(int_attribute(
+            "unified_log.activity_id",
+            log_data.activity_id,
+        ));
+    }
+
+    json!({
+        "timeUnixNano": time,
+        "observedTimeUnixNano": time,
+        "severityNumber": severity_number(&log_data.log_type),
+        "severityText": log_data.log_type,
+        "body": { "stringValue": log_data.message },
+        "attributes": attributes,
+    })
+}
+
+fn string_attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// OTLP `intValue` is a signed 64 bit integer. Larger values (ex: trace IDs with the high bit set) are sent as a `stringValue`
+fn int_attribute(key: &str, value: u64) -> Value {
+    if i64::try_from(value).is_ok() {
+        json!({ "key": key, "value": { "intValue": value.to_string() } })
+    } else {
+        string_attribute(key, &value.to_string())
+    }
+}
+
+/// OpenTelemetry severity number from the Unified Log type
+fn severity_number(log_type: &str) -> u8 {
+    match log_type {
+        "Debug" => 5,
+        "Info" => 9,
+        "Error" => 17,
+        "Fault" => 21,
+        // Default
+        _ => 10,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{int_attribute, to_otlp_json, OtlpSink};
+    use crate::exporters::siem::HostContext;
+    use crate::unified_log::{self, LogData};
+    use serde_json::Value;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn test_log_data() -> LogData {
+        LogData {
+            log_type: String::from("Error"),
+            ..unified_log::test_log_data()
+        }
+    }
+
+    #[test]
+    fn test_to_otlp_json() {
+        let mut other = test_log_data();
+        other.process = String::from("/usr/sbin/bluetoothd");
+        other.pid = 90;
+        other.log_type = String::from("Debug");
+
+        let host = HostContext {
+            hostname: String::from("macbook"),
+            os_version: String::from("21A559"),
+            hardware_model: String::from("MacBookPro16,1"),
+        };
+        let result = to_otlp_json(&[test_log_data(), other, test_log_data()], &host);
+        let resources = result["resourceLogs"].as_array().unwrap();
+        assert_eq!(resources.len(), 2);
+
+        let attributes = &resources[1]["resource"]["attributes"];
+        assert_eq!(attributes[0]["key"], "service.name");
+        assert_eq!(attributes[0]["value"]["stringValue"], "bluetoothd");
+        assert_eq!(
+            resources[1]["scopeLogs"][0]["logRecords"][0]["severityNumber"],
+            5
+        );
+
+        let records = resources[0]["scopeLogs"][0]["logRecords"]
+            .as_array()
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["timeUnixNano"], "1642302326434850816");
+        assert_eq!(records[0]["severityNumber"], 17);
+        assert_eq!(records[0]["severityText"], "Error");
+        assert_eq!(records[0]["body"]["stringValue"], "LOMD Start 1");
+        assert_eq!(
+            resources[0]["scopeLogs"][0]["scope"]["name"],
+            "macos-unifiedlogs"
+        );
+    }
+
+    #[test]
+    fn test_int_attribute() {
+        let result = int_attribute("thread.id", 588);
+        assert_eq!(result["key"], "thread.id");
+        assert_eq!(result["value"]["intValue"], "588");
+
+        let result = int_attribute("unified_log.trace_id", i64::MAX as u64 + 1);
+        assert!(result["value"]["intValue"].is_null());
+        assert_eq!(result["value"]["stringValue"], "9223372036854775808");
+
+        let mut log_data = test_log_data();
+        log_data.trace_id = u64::MAX;
+        let result = to_otlp_json(&[log_data], &HostContext::default());
+        let attributes = &result["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0]["attributes"];
+        assert_eq!(attributes[0]["value"]["intValue"], "588");
+        assert_eq!(attributes[4]["key"], "unified_log.trace_id");
+        assert_eq!(
+            attributes[4]["value"]["stringValue"],
+            "18446744073709551615"
+        );
+    }
+
+    #[test]
+    fn test_otlp_sink() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            // Read until the full body has arrived
+            loop {
+                let size = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..size]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                    let length: usize = headers
+                        .lines()
+                        .find_map(|line| line.strip_prefix("Content-Length: "))
+                        .unwrap()
+                        .parse()
+                        .unwrap();
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}")
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let mut sink = OtlpSink::new(&format!("http://{}/v1/logs", address)).unwrap();
+        sink.send_entries(&[test_log_data()]).unwrap();
+        assert_eq!(sink.records_sent(), 1);
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /v1/logs HTTP/1.1\r\n"));
+        assert!(request.contains("Content-Type: application/json\r\n"));
+        let body: Value = serde_json::from_str(request.split_once("\r\n\r\n").unwrap().1).unwrap();
+        assert_eq!(
+            body["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0]["body"]["stringValue"],
+            "LOMD Start 1"
+        );
+    }
+
+    #[test]
+    fn test_otlp_sink_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 4096];
+            let _ = stream.read(&mut buffer).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+        });
+
+        let mut sink = OtlpSink::new(&format!("http://{}/v1/logs", address)).unwrap();
+        assert!(sink.send_entries(&[test_log_data()]).is_err());
+        assert_eq!(sink.records_sent(), 0);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_otlp_sink_endpoint() {
+        assert!(OtlpSink::new("https://collector:4318/v1/logs").is_err());
+        let sink = OtlpSink::new("http://collector").unwrap();
+        assert_eq!(sink.host, "collector:80");
+        assert_eq!(sink.path, "/v1/logs");
+    }
+}
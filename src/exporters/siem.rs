@@ -0,0 +1,375 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Map reconstructed Unified Log entries to Elastic Common Schema (ECS) and OCSF events
+//!
+//! `BulkWriter` serializes the mapped events as NDJSON bodies for the Elasticsearch/OpenSearch `_bulk` API.
+//! Unified Log specific values without a schema field are kept under `unified_log` (ECS) or `unmapped` (OCSF)
+
+use crate::error::ParserError;
+use crate::header::HeaderChunk;
+use crate::unified_log::LogData;
+use crate::util::{hyphenate_uuid, image_name, unixepoch_to_iso};
+use log::error;
+use serde_json::{json, Value};
+use std::io::Write;
+
+/// ECS version the documents are mapped to
+pub const ECS_VERSION: &str = "8.11.0";
+/// OCSF schema version the events are mapped to
+pub const OCSF_VERSION: &str = "1.1.0";
+
+/// Host information shared by every entry of a tracev3 file
+#[derive(Debug, Clone, Default)]
+pub struct HostContext {
+    pub hostname: String,
+    /// macOS build version (ex: `21A559`)
+    pub os_version: String,
+    pub hardware_model: String,
+}
+
+impl HostContext {
+    /// Get the host information from the tracev3 header
+    pub fn from_header(header: &HeaderChunk) -> HostContext {
+        HostContext {
+            hostname: String::new(),
+            os_version: header.build_version_string.to_owned(),
+            hardware_model: header.hardware_model_string.to_owned(),
+        }
+    }
+}
+
+/// Event schema produced by `BulkWriter`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SiemSchema {
+    Ecs,
+    Ocsf,
+}
+
+/// Map a log entry to an ECS document
+pub fn to_ecs(log_data: &LogData, host: &HostContext) -> Value {
+    let mut host_value = json!({
+        "os": {
+            "family": "macos",
+            "type": "macos",
+            "platform": "darwin",
+            "version": host.os_version,
+        },
+        "boot": { "id": hyphenate_uuid(&log_data.boot_uuid) },
+    });
+    if !host.hostname.is_empty() {
+        host_value["hostname"] = json!(host.hostname);
+    }
+    if !host.hardware_model.is_empty() {
+        host_value["type"] = json!(host.hardware_model);
+    }
+
+    let dataset = if log_data.subsystem.is_empty() {
+        "macos.unifiedlog"
+    } else {
+        &log_data.subsystem
+    };
+
+    json!({
+        "@timestamp": unixepoch_to_iso(&log_data.time),
+        "message": log_data.message,
+        "ecs": { "version": ECS_VERSION },
+        "event": {
+            "kind": "event",
+            "module": "macos_unifiedlogs",
+            "dataset": dataset,
+            "severity": severity_id(&log_data.log_type),
+        },
+        "log": {
+            "level": log_level(&log_data.log_type),
+            "logger": log_data.category,
+        },
+        "process": {
+            "pid": log_data.pid,
+            "executable": log_data.process,
+            "name": image_name(&log_data.process),
+            "thread": { "id": log_data.thread_id },
+            "user": { "id": log_data.euid.to_string() },
+        },
+        "user": { "id": log_data.euid.to_string() },
+        "host": host_value,
+        "unified_log": {
+            "event_type": log_data.event_type,
+            "log_type": log_data.log_type,
+            "subsystem": log_data.subsystem,
+            "category": log_data.category,
+            "activity_id": log_data.activity_id,
+            "trace_id": log_data.trace_id,
+            "format_string": log_data.raw_message,
+            "library": log_data.library,
+            "library_uuid": hyphenate_uuid(&log_data.library_uuid),
+            "process_uuid": hyphenate_uuid(&log_data.process_uuid),
+            "timezone_name": log_data.timezone_name,
+        },
+    })
+}
+
+/// Map a log entry to an OCSF Base Event (`class_uid` 0)
+pub fn to_ocsf(log_data: &LogData, host: &HostContext) -> Value {
+    let nanoseconds_per_millisecond = 1000000;
+    let other_activity = 99;
+    let severity = severity_id(&log_data.log_type);
+
+    let mut device = json!({
+        "type_id": 0,
+        "os": {
+            "name": "macOS",
+            "type": "macOS",
+            "type_id": 300,
+            "build": host.os_version,
+        },
+    });
+    if !host.hostname.is_empty() {
+        device["hostname"] = json!(host.hostname);
+    }
+    if !host.hardware_model.is_empty() {
+        device["model"] = json!(host.hardware_model);
+    }
+
+    json!({
+        "class_uid": 0,
+        "class_name": "Base Event",
+        "category_uid": 0,
+        "category_name": "Uncategorized",
+        "activity_id": other_activity,
+        "activity_name": log_data.event_type,
+        "type_uid": other_activity,
+        "time": (log_data.time as i64).div_euclid(nanoseconds_per_millisecond),
+        "severity_id": severity,
+        "severity": ocsf_severity(severity),
+        "message": log_data.message,
+        "metadata": {
+            "version": OCSF_VERSION,
+            "log_name": "Unified Log",
+            "log_provider": log_data.subsystem,
+            "product": { "name": "macos-unifiedlogs", "vendor_name": "Mandiant" },
+        },
+        "device": device,
+        "actor": {
+            "process": {
+                "pid": log_data.pid,
+                "name": image_name(&log_data.process),
+                "file": {
+                    "path": log_data.process,
+                    "name": image_name(&log_data.process),
+                    "type_id": 1,
+                },
+                "tid": log_data.thread_id,
+                "user": { "uid": log_data.euid.to_string() },
+            },
+        },
+        "unmapped": {
+            "boot_uuid": hyphenate_uuid(&log_data.boot_uuid),
+            "event_type": log_data.event_type,
+            "log_type": log_data.log_type,
+            "subsystem": log_data.subsystem,
+            "category": log_data.category,
+            "activity_id": log_data.activity_id,
+            "trace_id": log_data.trace_id,
+            "format_string": log_data.raw_message,
+            "library": log_data.library,
+            "library_uuid": hyphenate_uuid(&log_data.library_uuid),
+            "process_uuid": hyphenate_uuid(&log_data.process_uuid),
+        },
+    })
+}
+
+/// Stream mapped entries to any writer as `_bulk` API request bodies (an action line followed by the document)
+pub struct BulkWriter<W: Write> {
+    writer: W,
+    schema: SiemSchema,
+    index: String,
+    host: HostContext,
+    entries_written: u64,
+}
+
+impl<W: Write> BulkWriter<W> {
+    /// Create a bulk writer that indexes documents of `schema` into `index`
+    pub fn new(writer: W, schema: SiemSchema, index: &str) -> BulkWriter<W> {
+        BulkWriter {
+            writer,
+            schema,
+            index: index.to_string(),
+            host: HostContext::default(),
+            entries_written: 0,
+        }
+    }
+
+    /// Host information added to the next entries. Use `HostContext::from_header()` for each tracev3 file
+    pub fn set_host(&mut self, host: HostContext) {
+        self.host = host;
+    }
+
+    /// Write the action and document lines for a log entry
+    pub fn write_entry(&mut self, log_data: &LogData) -> Result<(), ParserError> {
+        let action = json!({ "create": { "_index": self.index } });
+        let document = match self.schema {
+            SiemSchema::Ecs => to_ecs(log_data, &self.host),
+            SiemSchema::Ocsf => to_ocsf(log_data, &self.host),
+        };
+
+        for value in [action, document] {
+            if let Err(err) = serde_json::to_writer(&mut self.writer, &value) {
+                error!(
+                    "[macos-unifiedlogs] Failed to serialize bulk request line: {:?}",
+                    err
+                );
+                return Err(ParserError::Write);
+            }
+            if let Err(err) = self.writer.write_all(b"\n") {
+                error!(
+                    "[macos-unifiedlogs] Failed to write bulk request line separator: {:?}",
+                    err
+                );
+                return Err(ParserError::Write);
+            }
+        }
+        self.entries_written += 1;
+        Ok(())
+    }
+
+    /// Write all log entries
+    pub fn write_entries(&mut self, log_data: &[LogData]) -> Result<(), ParserError> {
+        for entry in log_data {
+            self.write_entry(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> Result<(), ParserError> {
+        if let Err(err) = self.writer.flush() {
+            error!(
+                "[macos-unifiedlogs] Failed to flush bulk request: {:?}",
+                err
+            );
+            return Err(ParserError::Write);
+        }
+        Ok(())
+    }
+
+    /// Number of log entries written so far
+    pub fn entries_written(&self) -> u64 {
+        self.entries_written
+    }
+
+    /// Return the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// ECS `log.level` from the Unified Log type
+fn log_level(log_type: &str) -> String {
+    match log_type {
+        "" => String::from("default"),
+        _ => log_type.to_lowercase(),
+    }
+}
+
+/// OCSF `severity_id` (also used for ECS `event.severity`)
+fn severity_id(log_type: &str) -> u8 {
+    match log_type {
+        "Error" => 3,
+        "Fault" => 4,
+        _ => 1,
+    }
+}
+
+fn ocsf_severity(severity_id: u8) -> &'static str {
+    match severity_id {
+        1 => "Informational",
+        2 => "Low",
+        3 => "Medium",
+        4 => "High",
+        5 => "Critical",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_ecs, to_ocsf, BulkWriter, HostContext, SiemSchema, ECS_VERSION};
+    use crate::header::HeaderChunk;
+    use crate::unified_log::{self, LogData};
+    use serde_json::Value;
+
+    fn test_log_data() -> LogData {
+        LogData {
+            log_type: String::from("Error"),
+            ..unified_log::test_log_data()
+        }
+    }
+
+    fn test_host() -> HostContext {
+        let header = HeaderChunk {
+            build_version_string: String::from("21A559"),
+            hardware_model_string: String::from("MacBookPro16,1"),
+            ..Default::default()
+        };
+        HostContext::from_header(&header)
+    }
+
+    #[test]
+    fn test_to_ecs() {
+        let result = to_ecs(&test_log_data(), &test_host());
+        assert_eq!(result["@timestamp"], "2022-01-16T03:05:26.434850816Z");
+        assert_eq!(result["ecs"]["version"], ECS_VERSION);
+        assert_eq!(result["process"]["pid"], 45);
+        assert_eq!(
+            result["process"]["executable"],
+            "/usr/libexec/lightsoutmanagementd"
+        );
+        assert_eq!(result["process"]["name"], "lightsoutmanagementd");
+        assert_eq!(result["log"]["level"], "error");
+        assert_eq!(result["event"]["dataset"], "com.apple.lom");
+        assert_eq!(result["host"]["os"]["version"], "21A559");
+        assert_eq!(result["unified_log"]["format_string"], "LOMD Start %d");
+        assert!(result["host"].get("hostname").is_none());
+    }
+
+    #[test]
+    fn test_to_ocsf() {
+        let result = to_ocsf(&test_log_data(), &test_host());
+        assert_eq!(result["class_uid"], 0);
+        assert_eq!(result["time"], 1642302326434_i64);
+        assert_eq!(result["severity_id"], 3);
+        assert_eq!(result["severity"], "Medium");
+        assert_eq!(result["actor"]["process"]["pid"], 45);
+        assert_eq!(result["device"]["os"]["build"], "21A559");
+        assert_eq!(result["device"]["model"], "MacBookPro16,1");
+        assert_eq!(result["unmapped"]["subsystem"], "com.apple.lom");
+    }
+
+    #[test]
+    fn test_bulk_writer() {
+        let mut writer = BulkWriter::new(Vec::new(), SiemSchema::Ecs, "logs-macos");
+        let mut host = test_host();
+        host.hostname = String::from("macbook");
+        writer.set_host(host);
+        writer
+            .write_entries(&[test_log_data(), test_log_data()])
+            .unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.entries_written(), 2);
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4);
+
+        let action: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(action["create"]["_index"], "logs-macos");
+        let document: Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(document["host"]["hostname"], "macbook");
+        assert!(output.ends_with('\n'));
+    }
+}
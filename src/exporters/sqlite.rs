@@ -0,0 +1,763 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Write reconstructed Unified Log entries to a normalized `SQLite` database (requires the `sqlite` feature)
+//!
+//! Opening an existing database appends to it, so several logarchives can be combined into one file.
+//! Unsigned 64-bit values (thread IDs, activity IDs, trace IDs) are stored with their bit pattern as `SQLite` signed integers.
+//! Message arguments are stored with their typed value and the `MessageValue` type name (ex: `unsigned`, `float`, `bytes`) in `value_type`.
+//! The `log_entries` view joins the normalized tables back into one row per entry
+
+use crate::catalog::CatalogChunk;
+use crate::error::ParserError;
+use crate::message_value::MessageValue;
+use crate::timesync::TimesyncBoot;
+use crate::unified_log::LogData;
+use log::error;
+use rusqlite::{params, types::Value, Connection};
+use std::collections::HashMap;
+
+/// Version of the database schema, stored in `PRAGMA user_version`
+pub const SQLITE_SCHEMA_VERSION: u32 = 2;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS boots (
+    id INTEGER PRIMARY KEY,
+    boot_uuid TEXT NOT NULL UNIQUE,
+    timezone_name TEXT,
+    boot_time INTEGER,
+    timebase_numerator INTEGER,
+    timebase_denominator INTEGER,
+    timezone_offset_mins INTEGER,
+    daylight_savings INTEGER
+);
+CREATE TABLE IF NOT EXISTS images (
+    id INTEGER PRIMARY KEY,
+    uuid TEXT NOT NULL,
+    path TEXT NOT NULL,
+    UNIQUE (uuid, path)
+);
+CREATE TABLE IF NOT EXISTS processes (
+    id INTEGER PRIMARY KEY,
+    boot_id INTEGER NOT NULL REFERENCES boots (id),
+    pid INTEGER NOT NULL,
+    euid INTEGER NOT NULL,
+    uuid TEXT NOT NULL,
+    image_id INTEGER REFERENCES images (id),
+    UNIQUE (boot_id, pid, uuid)
+);
+CREATE TABLE IF NOT EXISTS subsystems (
+    id INTEGER PRIMARY KEY,
+    subsystem TEXT NOT NULL,
+    category TEXT NOT NULL,
+    UNIQUE (subsystem, category)
+);
+CREATE TABLE IF NOT EXISTS format_strings (
+    id INTEGER PRIMARY KEY,
+    format_string TEXT NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS entries (
+    id INTEGER PRIMARY KEY,
+    boot_id INTEGER NOT NULL REFERENCES boots (id),
+    time INTEGER NOT NULL,
+    continuous_time INTEGER NOT NULL,
+    trace_id INTEGER NOT NULL,
+    event_type TEXT NOT NULL,
+    log_type TEXT NOT NULL,
+    pid INTEGER NOT NULL,
+    thread_id INTEGER NOT NULL,
+    activity_id INTEGER NOT NULL,
+    ttl INTEGER NOT NULL,
+    process_id INTEGER NOT NULL REFERENCES processes (id),
+    sender_image_id INTEGER NOT NULL REFERENCES images (id),
+    subsystem_id INTEGER REFERENCES subsystems (id),
+    format_string_id INTEGER REFERENCES format_strings (id),
+    message TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS message_arguments (
+    entry_id INTEGER NOT NULL REFERENCES entries (id),
+    position INTEGER NOT NULL,
+    item_type INTEGER NOT NULL,
+    item_size INTEGER NOT NULL,
+    value_type TEXT NOT NULL,
+    value,
+    PRIMARY KEY (entry_id, position)
+) WITHOUT ROWID;
+CREATE INDEX IF NOT EXISTS entries_time ON entries (time);
+CREATE INDEX IF NOT EXISTS entries_pid ON entries (pid);
+CREATE INDEX IF NOT EXISTS entries_subsystem ON entries (subsystem_id);
+CREATE INDEX IF NOT EXISTS entries_format_string ON entries (format_string_id);
+CREATE VIEW IF NOT EXISTS log_entries AS
+SELECT
+    entries.id,
+    entries.time,
+    boots.boot_uuid,
+    boots.timezone_name,
+    entries.event_type,
+    entries.log_type,
+    entries.pid,
+    processes.euid,
+    entries.thread_id,
+    entries.activity_id,
+    process_images.path AS process,
+    process_images.uuid AS process_uuid,
+    sender_images.path AS library,
+    sender_images.uuid AS library_uuid,
+    subsystems.subsystem,
+    subsystems.category,
+    format_strings.format_string,
+    entries.message
+FROM entries
+JOIN boots ON boots.id = entries.boot_id
+JOIN processes ON processes.id = entries.process_id
+LEFT JOIN images AS process_images ON process_images.id = processes.image_id
+JOIN images AS sender_images ON sender_images.id = entries.sender_image_id
+LEFT JOIN subsystems ON subsystems.id = entries.subsystem_id
+LEFT JOIN format_strings ON format_strings.id = entries.format_string_id;
+";
+
+/// Ids of rows already written, to avoid a lookup per log entry
+#[derive(Debug, Default)]
+struct IdCache {
+    boots: HashMap<String, i64>,
+    images: HashMap<(String, String), i64>,
+    processes: HashMap<(i64, u64, String), i64>,
+    subsystems: HashMap<(String, String), i64>,
+    format_strings: HashMap<String, i64>,
+}
+
+/// Write `LogData` entries, `CatalogChunk` process information and `TimesyncBoot` records to a `SQLite` database
+pub struct SqliteWriter {
+    connection: Connection,
+    cache: IdCache,
+    entries_written: u64,
+}
+
+impl SqliteWriter {
+    /// Open (or create) a database file. New entries are appended to any existing data
+    pub fn open(path: &str) -> Result<SqliteWriter, ParserError> {
+        match Connection::open(path) {
+            Ok(connection) => SqliteWriter::from_connection(connection),
+            Err(err) => {
+                error!(
+                    "[macos-unifiedlogs] Failed to open SQLite database {}: {:?}",
+                    path, err
+                );
+                Err(ParserError::Sqlite)
+            }
+        }
+    }
+
+    /// Create a database that only exists in memory
+    pub fn open_in_memory() -> Result<SqliteWriter, ParserError> {
+        match Connection::open_in_memory() {
+            Ok(connection) => SqliteWriter::from_connection(connection),
+            Err(err) => {
+                error!(
+                    "[macos-unifiedlogs] Failed to open in memory SQLite database: {:?}",
+                    err
+                );
+                Err(ParserError::Sqlite)
+            }
+        }
+    }
+
+    /// Use an existing connection. The schema is created if missing
+    pub fn from_connection(connection: Connection) -> Result<SqliteWriter, ParserError> {
+        let version: u32 = match connection.query_row("PRAGMA user_version", [], |row| row.get(0)) {
+            Ok(result) => result,
+            Err(err) => {
+                error!(
+                    "[macos-unifiedlogs] Failed to get SQLite schema version: {:?}",
+                    err
+                );
+                return Err(ParserError::Sqlite);
+            }
+        };
+        if version != 0 && version != SQLITE_SCHEMA_VERSION {
+            error!(
+                "[macos-unifiedlogs] Unsupported SQLite schema version: {}",
+                version
+            );
+            return Err(ParserError::Sqlite);
+        }
+
+        let setup = format!("{}PRAGMA user_version = {};", SCHEMA, SQLITE_SCHEMA_VERSION);
+        if let Err(err) = connection.execute_batch(&setup) {
+            error!(
+                "[macos-unifiedlogs] Failed to create SQLite schema: {:?}",
+                err
+            );
+            return Err(ParserError::Sqlite);
+        }
+
+        Ok(SqliteWriter {
+            connection,
+            cache: IdCache::default(),
+            entries_written: 0,
+        })
+    }
+
+    /// Write boot information (boot time and timebase) from the timesync files
+    pub fn write_timesync(&mut self, timesync_data: &[TimesyncBoot]) -> Result<(), ParserError> {
+        let results = self.in_transaction(|connection, _| {
+            let mut statement = connection.prepare_cached(
+                "INSERT INTO boots (boot_uuid, boot_time, timebase_numerator, timebase_denominator, timezone_offset_mins, daylight_savings)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                ON CONFLICT (boot_uuid) DO UPDATE SET
+                    boot_time = excluded.boot_time,
+                    timebase_numerator = excluded.timebase_numerator,
+                    timebase_denominator = excluded.timebase_denominator,
+                    timezone_offset_mins = excluded.timezone_offset_mins,
+                    daylight_savings = excluded.daylight_savings",
+            )?;
+            for boot in timesync_data {
+                statement.execute(params![
+                    boot.boot_uuid,
+                    boot.boot_time,
+                    boot.timebase_numerator,
+                    boot.timebase_denominator,
+                    boot.timezone_offset_mins,
+                    boot.daylight_savings
+                ])?;
+            }
+            Ok(())
+        });
+
+        if let Err(err) = results {
+            error!(
+                "[macos-unifiedlogs] Failed to write timesync data to SQLite: {:?}",
+                err
+            );
+            return Err(ParserError::Sqlite);
+        }
+        Ok(())
+    }
+
+    /// Write the processes and subsystems listed in a tracev3 Catalog chunk
+    pub fn write_catalog(
+        &mut self,
+        catalog: &CatalogChunk,
+        boot_uuid: &str,
+    ) -> Result<(), ParserError> {
+        let results = self.in_transaction(|connection, cache| {
+            let boot_id = boot_id(connection, cache, boot_uuid, None)?;
+            let mut statement = connection.prepare_cached(
+                "INSERT INTO processes (boot_id, pid, euid, uuid) VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT (boot_id, pid, uuid) DO NOTHING",
+            )?;
+            for process in &catalog.catalog_process_info_entries {
+                statement.execute(params![
+                    boot_id,
+                    process.pid,
+                    process.effective_user_id,
+                    process.main_uuid
+                ])?;
+
+                for subsystem in &process.subsystem_entries {
+                    let subsystem_info = CatalogChunk::get_subsystem(
+                        &subsystem.identifer,
+                        &process.first_number_proc_id,
+                        &process.second_number_proc_id,
+                        catalog,
+                    );
+                    if let Ok((_, info)) = subsystem_info {
+                        subsystem_id(connection, cache, &info.subsystem, &info.category)?;
+                    }
+                }
+            }
+            Ok(())
+        });
+
+        if let Err(err) = results {
+            error!(
+                "[macos-unifiedlogs] Failed to write catalog data to SQLite: {:?}",
+                err
+            );
+            return Err(ParserError::Sqlite);
+        }
+        Ok(())
+    }
+
+    /// Write log entries in a single transaction
+    pub fn write_entries(&mut self, log_data: &[LogData]) -> Result<(), ParserError> {
+        let results = self.in_transaction(|connection, cache| {
+            for entry in log_data {
+                insert_entry(connection, cache, entry)?;
+            }
+            Ok(())
+        });
+
+        if let Err(err) = results {
+            error!(
+                "[macos-unifiedlogs] Failed to write log entries to SQLite: {:?}",
+                err
+            );
+            return Err(ParserError::Sqlite);
+        }
+        self.entries_written += log_data.len() as u64;
+        Ok(())
+    }
+
+    /// Number of log entries written so far by this writer
+    pub fn entries_written(&self) -> u64 {
+        self.entries_written
+    }
+
+    /// Return the underlying connection
+    pub fn into_connection(self) -> Connection {
+        self.connection
+    }
+
+    /// Run database writes in a transaction. Cached ids are discarded if the transaction fails
+    fn in_transaction<F>(&mut self, write: F) -> rusqlite::Result<()>
+    where
+        F: FnOnce(&Connection, &mut IdCache) -> rusqlite::Result<()>,
+    {
+        let transaction = self.connection.transaction()?;
+        let results = write(&transaction, &mut self.cache).and_then(|_| transaction.commit());
+        if results.is_err() {
+            self.cache = IdCache::default();
+        }
+        results
+    }
+}
+
+/// Insert a log entry, its message arguments and any new rows it references
+fn insert_entry(
+    connection: &Connection,
+    cache: &mut IdCache,
+    log_data: &LogData,
+) -> rusqlite::Result<()> {
+    let boot_id = boot_id(
+        connection,
+        cache,
+        &log_data.boot_uuid,
+        Some(&log_data.timezone_name),
+    )?;
+    let process_image_id = image_id(connection, cache, &log_data.process_uuid, &log_data.process)?;
+    let sender_image_id = image_id(connection, cache, &log_data.library_uuid, &log_data.library)?;
+    let process_id = process_id(connection, cache, boot_id, log_data, process_image_id)?;
+    let subsystem_id = if log_data.subsystem.is_empty() {
+        None
+    } else {
+        Some(subsystem_id(
+            connection,
+            cache,
+            &log_data.subsystem,
+            &log_data.category,
+        )?)
+    };
+    let format_string_id = if log_data.raw_message.is_empty() {
+        None
+    } else {
+        Some(format_string_id(connection, cache, &log_data.raw_message)?)
+    };
+
+    let mut statement = connection.prepare_cached(
+        "INSERT INTO entries (boot_id, time, continuous_time, trace_id, event_type, log_type, pid, thread_id, activity_id, ttl, process_id, sender_image_id, subsystem_id, format_string_id, message)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+    )?;
+    statement.execute(params![
+        boot_id,
+        log_data.time as i64,
+        sql_integer(log_data.continuous_time),
+        sql_integer(log_data.trace_id),
+        log_data.event_type,
+        log_data.log_type,
+        sql_integer(log_data.pid),
+        sql_integer(log_data.thread_id),
+        sql_integer(log_data.activity_id),
+        log_data.ttl,
+        process_id,
+        sender_image_id,
+        subsystem_id,
+        format_string_id,
+        log_data.message
+    ])?;
+    let entry_id = connection.last_insert_rowid();
+
+    let mut statement = connection.prepare_cached(
+        "INSERT INTO message_arguments (entry_id, position, item_type, item_size, value_type, value) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    for (position, item) in log_data.message_entries.iter().enumerate() {
+        let (value_type, value) = sql_value(&item.message_value);
+        statement.execute(params![
+            entry_id,
+            position,
+            item.item_type,
+            item.item_size,
+            value_type,
+            value
+        ])?;
+    }
+    Ok(())
+}
+
+/// Get the id of a boot, adding it if needed
+fn boot_id(
+    connection: &Connection,
+    cache: &mut IdCache,
+    boot_uuid: &str,
+    timezone_name: Option<&str>,
+) -> rusqlite::Result<i64> {
+    if let Some(id) = cache.boots.get(boot_uuid) {
+        return Ok(*id);
+    }
+    connection
+        .prepare_cached(
+            "INSERT INTO boots (boot_uuid, timezone_name) VALUES (?1, ?2)
+            ON CONFLICT (boot_uuid) DO UPDATE SET timezone_name = COALESCE(boots.timezone_name, excluded.timezone_name)",
+        )?
+        .execute(params![boot_uuid, timezone_name])?;
+    let id = connection
+        .prepare_cached("SELECT id FROM boots WHERE boot_uuid = ?1")?
+        .query_row(params![boot_uuid], |row| row.get(0))?;
+    cache.boots.insert(boot_uuid.to_string(), id);
+    Ok(id)
+}
+
+/// Get the id of an image (UUID and path), adding it if needed
+fn image_id(
+    connection: &Connection,
+    cache: &mut IdCache,
+    uuid: &str,
+    path: &str,
+) -> rusqlite::Result<i64> {
+    let key = (uuid.to_string(), path.to_string());
+    if let Some(id) = cache.images.get(&key) {
+        return Ok(*id);
+    }
+    connection
+        .prepare_cached(
+            "INSERT INTO images (uuid, path) VALUES (?1, ?2) ON CONFLICT (uuid, path) DO NOTHING",
+        )?
+        .execute(params![uuid, path])?;
+    let id = connection
+        .prepare_cached("SELECT id FROM images WHERE uuid = ?1 AND path = ?2")?
+        .query_row(params![uuid, path], |row| row.get(0))?;
+    cache.images.insert(key, id);
+    Ok(id)
+}
+
+/// Get the id of the process that wrote a log entry. Processes added from the Catalog are linked to their image
+fn process_id(
+    connection: &Connection,
+    cache: &mut IdCache,
+    boot_id: i64,
+    log_data: &LogData,
+    image_id: i64,
+) -> rusqlite::Result<i64> {
+    let key = (boot_id, log_data.pid, log_data.process_uuid.to_owned());
+    if let Some(id) = cache.processes.get(&key) {
+        return Ok(*id);
+    }
+    connection
+        .prepare_cached(
+            "INSERT INTO processes (boot_id, pid, euid, uuid, image_id) VALUES (?1, ?2, ?3, ?4, ?5)
+            ON CONFLICT (boot_id, pid, uuid) DO UPDATE SET image_id = COALESCE(processes.image_id, excluded.image_id)",
+        )?
+        .execute(params![
+            boot_id,
+            sql_integer(log_data.pid),
+            log_data.euid,
+            log_data.process_uuid,
+            image_id
+        ])?;
+    let id = connection
+        .prepare_cached("SELECT id FROM processes WHERE boot_id = ?1 AND pid = ?2 AND uuid = ?3")?
+        .query_row(
+            params![boot_id, sql_integer(log_data.pid), log_data.process_uuid],
+            |row| row.get(0),
+        )?;
+    cache.processes.insert(key, id);
+    Ok(id)
+}
+
+/// Get the id of a subsystem and category, adding it if needed
+fn subsystem_id(
+    connection: &Connection,
+    cache: &mut IdCache,
+    subsystem: &str,
+    category: &str,
+) -> rusqlite::Result<i64> {
+    let key = (subsystem.to_string(), category.to_string());
+    if let Some(id) = cache.subsystems.get(&key) {
+        return Ok(*id);
+    }
+    connection
+        .prepare_cached(
+            "INSERT INTO subsystems (subsystem, category) VALUES (?1, ?2) ON CONFLICT (subsystem, category) DO NOTHING",
+        )?
+        .execute(params![subsystem, category])?;
+    let id = connection
+        .prepare_cached("SELECT id FROM subsystems WHERE subsystem = ?1 AND category = ?2")?
+        .query_row(params![subsystem, category], |row| row.get(0))?;
+    cache.subsystems.insert(key, id);
+    Ok(id)
+}
+
+/// Get the id of a format string, adding it if needed
+fn format_string_id(
+    connection: &Connection,
+    cache: &mut IdCache,
+    format_string: &str,
+) -> rusqlite::Result<i64> {
+    if let Some(id) = cache.format_strings.get(format_string) {
+        return Ok(*id);
+    }
+    connection
+        .prepare_cached(
+            "INSERT INTO format_strings (format_string) VALUES (?1) ON CONFLICT (format_string) DO NOTHING",
+        )?
+        .execute(params![format_string])?;
+    let id = connection
+        .prepare_cached("SELECT id FROM format_strings WHERE format_string = ?1")?
+        .query_row(params![format_string], |row| row.get(0))?;
+    cache.format_strings.insert(format_string.to_string(), id);
+    Ok(id)
+}
+
+/// `SQLite` integers are signed 64-bit, keep the bit pattern of unsigned values
+fn sql_integer(value: u64) -> i64 {
+    i64::from_le_bytes(value.to_le_bytes())
+}
+
+/// Type name and `SQLite` value of a message argument. Redacted and missing values are NULL
+fn sql_value(value: &MessageValue) -> (&'static str, Value) {
+    match value {
+        MessageValue::Signed(number) => ("signed", Value::Integer(*number)),
+        MessageValue::Unsigned(number) => ("unsigned", Value::Integer(sql_integer(*number))),
+        MessageValue::Float(number) => ("float", Value::Real(*number)),
+        MessageValue::String(text) => ("string", Value::Text(text.to_owned())),
+        MessageValue::Bytes(data) => ("bytes", Value::Blob(data.to_owned())),
+        MessageValue::Private => ("private", Value::Null),
+        MessageValue::Hashed(hash) => ("hashed", Value::Text(hash.to_owned())),
+        MessageValue::Object(text) => ("object", Value::Text(text.to_owned())),
+        MessageValue::Missing => ("missing", Value::Null),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sql_integer, sql_value, SqliteWriter, SQLITE_SCHEMA_VERSION};
+    use crate::catalog::{test_catalog, test_process_info};
+    use crate::message_value::MessageValue;
+    use crate::timesync::TimesyncBoot;
+    use crate::unified_log::test_log_data;
+    use rusqlite::types::Value;
+    use std::fs;
+
+    fn count(writer: &SqliteWriter, table: &str) -> i64 {
+        writer
+            .connection
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_write_entries() {
+        let mut writer = SqliteWriter::open_in_memory().unwrap();
+        writer
+            .write_entries(&[test_log_data(), test_log_data()])
+            .unwrap();
+        assert_eq!(writer.entries_written(), 2);
+
+        assert_eq!(count(&writer, "entries"), 2);
+        assert_eq!(count(&writer, "boots"), 1);
+        assert_eq!(count(&writer, "images"), 1);
+        assert_eq!(count(&writer, "processes"), 1);
+        assert_eq!(count(&writer, "subsystems"), 1);
+        assert_eq!(count(&writer, "format_strings"), 1);
+        assert_eq!(count(&writer, "message_arguments"), 2);
+
+        let (process, format_string, message, time): (String, String, String, i64) = writer
+            .connection
+            .query_row(
+                "SELECT process, format_string, message, time FROM log_entries WHERE pid = 45",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(process, "/usr/libexec/lightsoutmanagementd");
+        assert_eq!(format_string, "LOMD Start %d");
+        assert_eq!(message, "LOMD Start 1");
+        assert_eq!(time, 1642302326434850816);
+
+        let (value_type, value): (String, i64) = writer
+            .connection
+            .query_row(
+                "SELECT value_type, value FROM message_arguments WHERE position = 0",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(value_type, "signed");
+        assert_eq!(value, 1);
+    }
+
+    #[test]
+    fn test_write_timesync() {
+        let mut writer = SqliteWriter::open_in_memory().unwrap();
+        writer.write_entries(&[test_log_data()]).unwrap();
+        writer
+            .write_timesync(&[TimesyncBoot {
+                signature: 0xbbb0,
+                header_size: 48,
+                unknown: 0,
+                boot_uuid: String::from("80D194AF56A34C54867449D2130D41BB"),
+                timebase_numerator: 125,
+                timebase_denominator: 3,
+                boot_time: 1642302206000000000,
+                timezone_offset_mins: 0,
+                daylight_savings: 0,
+                timesync: Vec::new(),
+            }])
+            .unwrap();
+
+        assert_eq!(count(&writer, "boots"), 1);
+        let (timebase_numerator, timezone_name): (u32, String) = writer
+            .connection
+            .query_row(
+                "SELECT timebase_numerator, timezone_name FROM boots",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(timebase_numerator, 125);
+        assert_eq!(timezone_name, "Pacific");
+    }
+
+    #[test]
+    fn test_write_catalog() {
+        let mut catalog = test_catalog(0, 100);
+        catalog.catalog_process_info_entries = vec![
+            test_process_info(45, "6C3ADF991F033C1C96C4ADFAA12D8CED"),
+            test_process_info(90, "80D194AF56A34C54867449D2130D41BB"),
+        ];
+        catalog.catalog_process_info_entries[1].effective_user_id = 501;
+
+        let mut writer = SqliteWriter::open_in_memory().unwrap();
+        writer
+            .write_catalog(&catalog, "80D194AF56A34C54867449D2130D41BB")
+            .unwrap();
+        // Writing the same catalog again does not add rows
+        writer
+            .write_catalog(&catalog, "80D194AF56A34C54867449D2130D41BB")
+            .unwrap();
+
+        assert_eq!(count(&writer, "boots"), 1);
+        assert_eq!(count(&writer, "processes"), 2);
+        assert_eq!(count(&writer, "subsystems"), 2);
+
+        let processes: Vec<(u32, u32, String, Option<i64>)> = {
+            let mut statement = writer
+                .connection
+                .prepare("SELECT pid, euid, uuid, image_id FROM processes ORDER BY pid")
+                .unwrap();
+            let rows = statement
+                .query_map([], |row| {
+                    Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+                })
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+            rows
+        };
+        assert_eq!(
+            processes,
+            vec![
+                (45, 0, String::from("6C3ADF991F033C1C96C4ADFAA12D8CED"), None),
+                (90, 501, String::from("80D194AF56A34C54867449D2130D41BB"), None),
+            ]
+        );
+
+        let subsystems: Vec<(String, String)> = {
+            let mut statement = writer
+                .connection
+                .prepare("SELECT subsystem, category FROM subsystems ORDER BY subsystem")
+                .unwrap();
+            let rows = statement
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+            rows
+        };
+        assert_eq!(
+            subsystems,
+            vec![
+                (String::from("com.apple.lom"), String::from("device")),
+                (String::from("com.apple.powerd"), String::from("battery")),
+            ]
+        );
+
+        // Entries of a process from the catalog link the existing row to the process image
+        writer.write_entries(&[test_log_data()]).unwrap();
+        assert_eq!(count(&writer, "processes"), 2);
+        let image_id: Option<i64> = writer
+            .connection
+            .query_row("SELECT image_id FROM processes WHERE pid = 45", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert!(image_id.is_some());
+    }
+
+    #[test]
+    fn test_append_database() {
+        let path = std::env::temp_dir().join(format!(
+            "macos_unifiedlogs_sqlite_{}.db",
+            std::process::id()
+        ));
+        let db_path = path.to_str().unwrap();
+
+        let mut writer = SqliteWriter::open(db_path).unwrap();
+        writer.write_entries(&[test_log_data()]).unwrap();
+        drop(writer);
+
+        let mut writer = SqliteWriter::open(db_path).unwrap();
+        writer.write_entries(&[test_log_data()]).unwrap();
+        assert_eq!(writer.entries_written(), 1);
+        assert_eq!(count(&writer, "entries"), 2);
+        assert_eq!(count(&writer, "format_strings"), 1);
+
+        let version: u32 = writer
+            .connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SQLITE_SCHEMA_VERSION);
+
+        drop(writer);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_sql_integer() {
+        assert_eq!(sql_integer(45), 45);
+        assert_eq!(sql_integer(u64::MAX), -1);
+    }
+
+    #[test]
+    fn test_sql_value() {
+        assert_eq!(
+            sql_value(&MessageValue::Unsigned(u64::MAX)),
+            ("unsigned", Value::Integer(-1))
+        );
+        assert_eq!(
+            sql_value(&MessageValue::Float(0.5)),
+            ("float", Value::Real(0.5))
+        );
+        assert_eq!(
+            sql_value(&MessageValue::Bytes(vec![0, 255])),
+            ("bytes", Value::Blob(vec![0, 255]))
+        );
+        assert_eq!(sql_value(&MessageValue::Private), ("private", Value::Null));
+    }
+}
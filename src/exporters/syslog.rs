@@ -0,0 +1,372 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Forward reconstructed Unified Log entries as RFC 5424 syslog messages over UDP, TCP or a Unix datagram socket
+//!
+//! TCP messages use octet counting framing (RFC 6587). Subsystem, category and activity are sent as structured data
+
+use crate::error::ParserError;
+use crate::unified_log::LogData;
+use crate::util::{image_name, unixepoch_to_iso};
+use log::error;
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+
+/// Structured data ID. 32473 is the private enterprise number reserved for documentation (RFC 5612)
+const SD_ID: &str = "unifiedlog@32473";
+
+/// Where syslog messages are sent
+#[derive(Debug, Clone)]
+pub enum SyslogTransport {
+    /// Address of a UDP syslog listener (ex: `127.0.0.1:514`)
+    Udp(String),
+    /// Address of a TCP syslog listener (ex: `127.0.0.1:601`)
+    Tcp(String),
+    /// Path of a Unix datagram socket (ex: `/dev/log`)
+    #[cfg(unix)]
+    Unix(String),
+}
+
+enum Connection {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixDatagram),
+}
+
+/// Options for the syslog header
+#[derive(Debug, Clone)]
+pub struct SyslogOptions {
+    /// Syslog facility, 0 to 23 (default 1, user-level messages)
+    pub facility: u8,
+    /// HOSTNAME field, `-` if empty
+    pub hostname: String,
+}
+
+impl Default for SyslogOptions {
+    fn default() -> Self {
+        SyslogOptions {
+            facility: 1,
+            hostname: String::new(),
+        }
+    }
+}
+
+/// Send `LogData` entries to a syslog listener
+pub struct SyslogSink {
+    connection: Connection,
+    options: SyslogOptions,
+    messages_sent: u64,
+}
+
+impl SyslogSink {
+    /// Connect to a syslog listener using the default options
+    pub fn connect(transport: &SyslogTransport) -> Result<SyslogSink, ParserError> {
+        SyslogSink::connect_with_options(transport, SyslogOptions::default())
+    }
+
+    /// Connect to a syslog listener
+    pub fn connect_with_options(
+        transport: &SyslogTransport,
+        options: SyslogOptions,
+    ) -> Result<SyslogSink, ParserError> {
+        let max_facility = 23;
+        if options.facility > max_facility {
+            error!(
+                "[macos-unifiedlogs] Invalid syslog facility {}, expected 0 to {}",
+                options.facility, max_facility
+            );
+            return Err(ParserError::Network);
+        }
+
+        let connection = match transport {
+            SyslogTransport::Udp(address) => udp_connect(address).map(Connection::Udp),
+            SyslogTransport::Tcp(address) => TcpStream::connect(address).map(Connection::Tcp),
+            #[cfg(unix)]
+            SyslogTransport::Unix(path) => UnixDatagram::unbound()
+                .and_then(|socket| socket.connect(path).map(|_| socket))
+                .map(Connection::Unix),
+        };
+
+        match connection {
+            Ok(result) => Ok(SyslogSink {
+                connection: result,
+                options,
+                messages_sent: 0,
+            }),
+            Err(err) => {
+                error!(
+                    "[macos-unifiedlogs] Failed to connect to syslog listener {:?}: {:?}",
+                    transport, err
+                );
+                Err(ParserError::Network)
+            }
+        }
+    }
+
+    /// Send a single log entry
+    pub fn send(&mut self, log_data: &LogData) -> Result<(), ParserError> {
+        let message = to_rfc5424(log_data, &self.options);
+        let results = match &mut self.connection {
+            Connection::Udp(socket) => socket.send(message.as_bytes()).map(|_| ()),
+            Connection::Tcp(stream) => {
+                let frame = format!("{} {}", message.len(), message);
+                stream.write_all(frame.as_bytes())
+            }
+            #[cfg(unix)]
+            Connection::Unix(socket) => socket.send(message.as_bytes()).map(|_| ()),
+        };
+
+        if let Err(err) = results {
+            error!(
+                "[macos-unifiedlogs] Failed to send syslog message: {:?}",
+                err
+            );
+            return Err(ParserError::Network);
+        }
+        self.messages_sent += 1;
+        Ok(())
+    }
+
+    /// Send all log entries
+    pub fn send_entries(&mut self, log_data: &[LogData]) -> Result<(), ParserError> {
+        for entry in log_data {
+            self.send(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Number of messages sent so far
+    pub fn messages_sent(&self) -> u64 {
+        self.messages_sent
+    }
+}
+
+/// Connect a UDP socket to the first resolved address that accepts it. The socket is bound to the unspecified address of the same family (IPv4 or IPv6)
+fn udp_connect(address: &str) -> std::io::Result<UdpSocket> {
+    let mut last_error = None;
+    for target in address.to_socket_addrs()? {
+        let local = match target {
+            SocketAddr::V4(_) => SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0),
+            SocketAddr::V6(_) => SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0),
+        };
+        let results =
+            UdpSocket::bind(local).and_then(|socket| socket.connect(target).map(|_| socket));
+        match results {
+            Ok(socket) => return Ok(socket),
+            Err(err) => last_error = Some(err),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "address did not resolve to any socket address",
+        )
+    }))
+}
+
+/// Format a log entry as an RFC 5424 syslog message. The facility should be 0 to 23 (checked by `SyslogSink::connect_with_options`)
+pub fn to_rfc5424(log_data: &LogData, options: &SyslogOptions) -> String {
+    let priority = u16::from(options.facility) * 8 + u16::from(severity(&log_data.log_type));
+    let nanoseconds = unixepoch_to_iso(&log_data.time);
+    // RFC 5424 allows at most 6 digits for fractional seconds
+    let timestamp = match nanoseconds.split_once('.') {
+        Some((seconds, fraction)) => format!("{}.{:.6}Z", seconds, fraction),
+        None => nanoseconds,
+    };
+
+    format!(
+        "<{}>1 {} {} {} {} {} [{} subsystem=\"{}\" category=\"{}\" activity=\"{}\"] \u{feff}{}",
+        priority,
+        timestamp,
+        header_field(&options.hostname, 255),
+        header_field(image_name(&log_data.process), 48),
+        log_data.pid,
+        header_field(&log_data.event_type, 32),
+        SD_ID,
+        param_value(&log_data.subsystem),
+        param_value(&log_data.category),
+        log_data.activity_id,
+        log_data.message
+    )
+}
+
+/// Syslog severity from the Unified Log type
+fn severity(log_type: &str) -> u8 {
+    match log_type {
+        "Fault" => 2,
+        "Error" => 3,
+        "Info" => 6,
+        "Debug" => 7,
+        _ => 5,
+    }
+}
+
+/// Header fields only allow printable ASCII without spaces. Empty values are written as `-`
+fn header_field(value: &str, max_length: usize) -> String {
+    let field: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max_length)
+        .collect();
+    if field.is_empty() {
+        String::from("-")
+    } else {
+        field
+    }
+}
+
+/// Escape `"`, `\` and `]` in structured data values
+fn param_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{param_value, to_rfc5424, SyslogOptions, SyslogSink, SyslogTransport};
+    use crate::unified_log::{self, LogData};
+    use std::io::Read;
+    use std::net::{TcpListener, UdpSocket};
+
+    fn test_log_data() -> LogData {
+        LogData {
+            log_type: String::from("Error"),
+            ..unified_log::test_log_data()
+        }
+    }
+
+    const EXPECTED: &str = "<11>1 2022-01-16T03:05:26.434850Z macbook lightsoutmanagementd 45 Log [unifiedlog@32473 subsystem=\"com.apple.lom\" category=\"device\" activity=\"0\"] \u{feff}LOMD Start 1";
+
+    fn test_options() -> SyslogOptions {
+        SyslogOptions {
+            hostname: String::from("macbook"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_to_rfc5424() {
+        assert_eq!(to_rfc5424(&test_log_data(), &test_options()), EXPECTED);
+
+        let result = to_rfc5424(&test_log_data(), &SyslogOptions::default());
+        assert!(result.starts_with("<11>1 2022-01-16T03:05:26.434850Z - lightsoutmanagementd"));
+    }
+
+    #[test]
+    fn test_param_value() {
+        assert_eq!(param_value("a\"b]c\\"), "a\\\"b\\]c\\\\");
+    }
+
+    #[test]
+    fn test_send_udp() {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let mut sink =
+            SyslogSink::connect_with_options(&SyslogTransport::Udp(address), test_options())
+                .unwrap();
+        sink.send(&test_log_data()).unwrap();
+        assert_eq!(sink.messages_sent(), 1);
+
+        let mut buffer = [0; 1024];
+        let size = listener.recv(&mut buffer).unwrap();
+        assert_eq!(std::str::from_utf8(&buffer[..size]).unwrap(), EXPECTED);
+    }
+
+    #[test]
+    fn test_send_udp_ipv6() {
+        // IPv6 may be disabled in the test environment
+        let Ok(listener) = UdpSocket::bind("[::1]:0") else {
+            return;
+        };
+        let address = listener.local_addr().unwrap().to_string();
+
+        let mut sink =
+            SyslogSink::connect_with_options(&SyslogTransport::Udp(address), test_options())
+                .unwrap();
+        sink.send(&test_log_data()).unwrap();
+
+        let mut buffer = [0; 1024];
+        let size = listener.recv(&mut buffer).unwrap();
+        assert_eq!(std::str::from_utf8(&buffer[..size]).unwrap(), EXPECTED);
+    }
+
+    #[test]
+    fn test_invalid_facility() {
+        let listener = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let options = SyslogOptions {
+            facility: 24,
+            ..test_options()
+        };
+        assert!(
+            SyslogSink::connect_with_options(&SyslogTransport::Udp(address.clone()), options)
+                .is_err()
+        );
+
+        let options = SyslogOptions {
+            facility: 23,
+            ..test_options()
+        };
+        let sink = SyslogSink::connect_with_options(&SyslogTransport::Udp(address), options);
+        assert!(sink.is_ok());
+        assert!(to_rfc5424(&test_log_data(), &sink.unwrap().options).starts_with("<187>1 "));
+    }
+
+    #[test]
+    fn test_send_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let mut sink =
+            SyslogSink::connect_with_options(&SyslogTransport::Tcp(address), test_options())
+                .unwrap();
+        let (mut stream, _) = listener.accept().unwrap();
+        sink.send_entries(&[test_log_data(), test_log_data()])
+            .unwrap();
+        drop(sink);
+
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+        let frame = format!("{} {}", EXPECTED.len(), EXPECTED);
+        assert_eq!(received, format!("{}{}", frame, frame));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_send_unix() {
+        use std::os::unix::net::UnixDatagram;
+
+        let path = std::env::temp_dir().join(format!(
+            "macos_unifiedlogs_syslog_{}.sock",
+            std::process::id()
+        ));
+        let listener = UnixDatagram::bind(&path).unwrap();
+
+        let mut sink = SyslogSink::connect_with_options(
+            &SyslogTransport::Unix(path.to_str().unwrap().to_string()),
+            test_options(),
+        )
+        .unwrap();
+        sink.send(&test_log_data()).unwrap();
+
+        let mut buffer = [0; 1024];
+        let size = listener.recv(&mut buffer).unwrap();
+        assert_eq!(std::str::from_utf8(&buffer[..size]).unwrap(), EXPECTED);
+        std::fs::remove_file(path).unwrap();
+    }
+}
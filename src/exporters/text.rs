@@ -0,0 +1,299 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Render reconstructed Unified Log entries as plain text, mirroring `log show --style default|compact|syslog`
+//!
+//! Timestamps are rendered in the provided timezone. Use `HeaderChunk::timezone_offset()` to match the system that wrote the logs
+
+use crate::error::ParserError;
+use crate::unified_log::LogData;
+use crate::util::{image_name, unixepoch_to_local};
+use chrono::{FixedOffset, Offset, Utc};
+use log::error;
+use std::io::Write;
+
+/// Text layout matching the `--style` argument of `log show`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextStyle {
+    /// Timestamp, thread, type, activity, PID and TTL columns followed by the message
+    #[default]
+    Default,
+    /// Millisecond timestamp, short type and `process[PID:TID]`
+    Compact,
+    /// Syslog like `process[PID]:` lines
+    Syslog,
+}
+
+/// Options for `TextWriter`
+#[derive(Debug, Clone, Copy)]
+pub struct TextOptions {
+    pub style: TextStyle,
+    /// Offset used to render timestamps
+    pub timezone: FixedOffset,
+    /// Write the column header line before the first entry
+    pub include_header: bool,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        TextOptions {
+            style: TextStyle::default(),
+            timezone: Utc.fix(),
+            include_header: true,
+        }
+    }
+}
+
+/// Hostname written by `log show --style syslog`
+const SYSLOG_HOSTNAME: &str = "localhost";
+
+/// Stream `LogData` entries to any writer as `log show` style text
+pub struct TextWriter<W: Write> {
+    writer: W,
+    options: TextOptions,
+    entries_written: u64,
+}
+
+impl<W: Write> TextWriter<W> {
+    /// Create a text writer using the `default` style, UTC timestamps and a column header
+    pub fn new(writer: W) -> TextWriter<W> {
+        TextWriter::with_options(writer, TextOptions::default())
+    }
+
+    /// Create a text writer with the provided options
+    pub fn with_options(writer: W, options: TextOptions) -> TextWriter<W> {
+        TextWriter {
+            writer,
+            options,
+            entries_written: 0,
+        }
+    }
+
+    /// Write a single log entry as one line of text
+    pub fn write_entry(&mut self, log_data: &LogData) -> Result<(), ParserError> {
+        let mut line = String::new();
+        if self.entries_written == 0 && self.options.include_header {
+            line.push_str(&render_header(&self.options.style));
+            line.push('\n');
+        }
+        line.push_str(&render_entry(log_data, &self.options));
+        line.push('\n');
+
+        if let Err(err) = self.writer.write_all(line.as_bytes()) {
+            error!(
+                "[macos-unifiedlogs] Failed to write text log entry: {:?}",
+                err
+            );
+            return Err(ParserError::Write);
+        }
+        self.entries_written += 1;
+        Ok(())
+    }
+
+    /// Write all log entries, one line per entry
+    pub fn write_entries(&mut self, log_data: &[LogData]) -> Result<(), ParserError> {
+        for entry in log_data {
+            self.write_entry(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> Result<(), ParserError> {
+        if let Err(err) = self.writer.flush() {
+            error!("[macos-unifiedlogs] Failed to flush text output: {:?}", err);
+            return Err(ParserError::Write);
+        }
+        Ok(())
+    }
+
+    /// Number of log entries written so far
+    pub fn entries_written(&self) -> u64 {
+        self.entries_written
+    }
+
+    /// Return the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Get the column header line for a text style
+pub fn render_header(style: &TextStyle) -> String {
+    match style {
+        TextStyle::Default => format!(
+            "{:<31} {:<10} {:<11} {:<20} {:<6} {:<4}",
+            "Timestamp", "Thread", "Type", "Activity", "PID", "TTL"
+        ),
+        TextStyle::Compact => format!("{:<23} {:<2} {}", "Timestamp", "Ty", "Process[PID:TID]"),
+        TextStyle::Syslog => format!("{:<31} {}", "Timestamp", "(process)[PID]"),
+    }
+}
+
+/// Render a single log entry without a trailing newline
+pub fn render_entry(log_data: &LogData, options: &TextOptions) -> String {
+    let process = image_name(&log_data.process);
+    let context = message_context(log_data);
+
+    match options.style {
+        TextStyle::Default => format!(
+            "{:<31} {:<#10x} {:<11} {:<#20x} {:<6} {:<4} {}: {}{}",
+            unixepoch_to_local(&log_data.time, &options.timezone, "%Y-%m-%d %H:%M:%S%.6f%z"),
+            log_data.thread_id,
+            type_name(log_data),
+            log_data.activity_id,
+            log_data.pid,
+            log_data.ttl,
+            process,
+            context,
+            log_data.message
+        ),
+        TextStyle::Compact => format!(
+            "{:<23} {:<2} {}[{}:{:x}] {}{}",
+            unixepoch_to_local(&log_data.time, &options.timezone, "%Y-%m-%d %H:%M:%S%.3f"),
+            compact_type_name(log_data),
+            process,
+            log_data.pid,
+            log_data.thread_id,
+            context,
+            log_data.message
+        ),
+        TextStyle::Syslog => format!(
+            "{:<31}  {} {}[{}]: {}{}",
+            unixepoch_to_local(&log_data.time, &options.timezone, "%Y-%m-%d %H:%M:%S%.6f%z"),
+            SYSLOG_HOSTNAME,
+            process,
+            log_data.pid,
+            context,
+            log_data.message
+        ),
+    }
+}
+
+/// Build the `(library) [subsystem:category] ` prefix of the message. The library is only shown if it differs from the process
+fn message_context(log_data: &LogData) -> String {
+    let mut context = String::new();
+    if !log_data.library.is_empty() && log_data.library != log_data.process {
+        context.push_str(&format!("({}) ", image_name(&log_data.library)));
+    }
+    if !log_data.subsystem.is_empty() {
+        context.push_str(&format!("[{}:{}] ", log_data.subsystem, log_data.category));
+    }
+    context
+}
+
+/// Type column used by the `default` style
+fn type_name(log_data: &LogData) -> &str {
+    match log_data.event_type.as_str() {
+        "Activity" => "Activity",
+        "Signpost" => "Signpost",
+        "Loss" => "Loss",
+        "Statedump" => "State",
+        _ if log_data.log_type.is_empty() => "Default",
+        _ => &log_data.log_type,
+    }
+}
+
+/// Two character type column used by the `compact` style
+fn compact_type_name(log_data: &LogData) -> &'static str {
+    match log_data.event_type.as_str() {
+        "Activity" => "A",
+        "Signpost" => "S",
+        "Loss" => "L",
+        "Statedump" => "St",
+        _ => match log_data.log_type.as_str() {
+            "Info" => "I",
+            "Debug" => "Db",
+            "Error" => "E",
+            "Fault" => "F",
+            "Useraction" => "U",
+            _ => "Df",
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_entry, render_header, TextOptions, TextStyle, TextWriter};
+    use crate::unified_log::{self, LogData};
+    use chrono::FixedOffset;
+
+    fn test_log_data() -> LogData {
+        LogData {
+            library: String::from("/usr/lib/libsystem_info.dylib"),
+            ttl: 14,
+            ..unified_log::test_log_data()
+        }
+    }
+
+    fn pacific_options(style: TextStyle) -> TextOptions {
+        TextOptions {
+            style,
+            timezone: FixedOffset::west_opt(8 * 3600).unwrap(),
+            include_header: true,
+        }
+    }
+
+    #[test]
+    fn test_render_default() {
+        let result = render_entry(&test_log_data(), &pacific_options(TextStyle::Default));
+        assert_eq!(
+            result,
+            "2022-01-15 19:05:26.434850-0800 0x24c      Default     0x0                  45     14   lightsoutmanagementd: (libsystem_info.dylib) [com.apple.lom:device] LOMD Start 1"
+        );
+    }
+
+    #[test]
+    fn test_render_compact() {
+        let result = render_entry(&test_log_data(), &pacific_options(TextStyle::Compact));
+        assert_eq!(
+            result,
+            "2022-01-15 19:05:26.434 Df lightsoutmanagementd[45:24c] (libsystem_info.dylib) [com.apple.lom:device] LOMD Start 1"
+        );
+    }
+
+    #[test]
+    fn test_render_syslog() {
+        let mut test_data = test_log_data();
+        test_data.library = test_data.process.clone();
+        test_data.subsystem = String::new();
+
+        let result = render_entry(&test_data, &pacific_options(TextStyle::Syslog));
+        assert_eq!(
+            result,
+            "2022-01-15 19:05:26.434850-0800  localhost lightsoutmanagementd[45]: LOMD Start 1"
+        );
+    }
+
+    #[test]
+    fn test_render_header() {
+        assert_eq!(
+            render_header(&TextStyle::Default),
+            "Timestamp                       Thread     Type        Activity             PID    TTL "
+        );
+        assert_eq!(
+            render_header(&TextStyle::Compact),
+            "Timestamp               Ty Process[PID:TID]"
+        );
+    }
+
+    #[test]
+    fn test_write_entries() {
+        let mut writer = TextWriter::new(Vec::new());
+        writer
+            .write_entries(&[test_log_data(), test_log_data()])
+            .unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.entries_written(), 2);
+
+        let output = String::from_utf8(writer.into_inner()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("Timestamp"));
+        assert!(lines[1].starts_with("2022-01-16 03:05:26.434850+0000 0x24c"));
+    }
+}
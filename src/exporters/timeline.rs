@@ -0,0 +1,281 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Write reconstructed Unified Log entries as forensic timelines: log2timeline CSV (l2t CSV), TLN and mactime bodyfile
+//!
+//! These formats are line based, so new lines in messages are replaced with spaces. Timestamps are written in UTC.
+//! The path of the source tracev3 file (see `TimelineWriter::set_source()`) is recorded in each line for provenance
+
+use crate::error::ParserError;
+use crate::unified_log::LogData;
+use crate::util::{image_name, unixepoch_to_local};
+use chrono::{Offset, Utc};
+use log::error;
+use std::io::Write;
+
+/// Supported timeline formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelineFormat {
+    /// log2timeline/Plaso `l2tcsv` (17 columns, with header)
+    L2tCsv,
+    /// Five field TLN: `Time|Source|System|User|Description`
+    Tln,
+    /// The Sleuth Kit bodyfile (3.x) read by `mactime`. The entry time is written as the modified time
+    Bodyfile,
+}
+
+/// Source name written in the l2t CSV `source` and TLN `Source` columns
+const TIMELINE_SOURCE: &str = "ULOG";
+const TIMELINE_SOURCE_TYPE: &str = "Apple Unified Log";
+
+/// Stream `LogData` entries to any writer as a timeline
+pub struct TimelineWriter<W: Write> {
+    writer: W,
+    format: TimelineFormat,
+    hostname: String,
+    source: String,
+    entries_written: u64,
+}
+
+impl<W: Write> TimelineWriter<W> {
+    /// Create a timeline writer. The hostname defaults to `-`
+    pub fn new(writer: W, format: TimelineFormat) -> TimelineWriter<W> {
+        TimelineWriter {
+            writer,
+            format,
+            hostname: String::from("-"),
+            source: String::new(),
+            entries_written: 0,
+        }
+    }
+
+    /// Hostname written in the l2t CSV `host` and TLN `System` columns
+    pub fn set_hostname(&mut self, hostname: &str) {
+        self.hostname = hostname.to_string();
+    }
+
+    /// Path of the tracev3 file the next entries were parsed from
+    pub fn set_source(&mut self, source: &str) {
+        self.source = source.to_string();
+    }
+
+    /// Write a single log entry as one timeline line. The l2t CSV header is written before the first entry
+    pub fn write_entry(&mut self, log_data: &LogData) -> Result<(), ParserError> {
+        let mut line = String::new();
+        if self.entries_written == 0 && self.format == TimelineFormat::L2tCsv {
+            line.push_str(L2T_CSV_HEADER);
+            line.push('\n');
+        }
+        let entry = match self.format {
+            TimelineFormat::L2tCsv => l2t_csv_line(log_data, &self.hostname, &self.source),
+            TimelineFormat::Tln => tln_line(log_data, &self.hostname, &self.source),
+            TimelineFormat::Bodyfile => bodyfile_line(log_data, &self.source),
+        };
+        line.push_str(&entry);
+        line.push('\n');
+
+        if let Err(err) = self.writer.write_all(line.as_bytes()) {
+            error!(
+                "[macos-unifiedlogs] Failed to write timeline entry: {:?}",
+                err
+            );
+            return Err(ParserError::Write);
+        }
+        self.entries_written += 1;
+        Ok(())
+    }
+
+    /// Write all log entries, one line per entry
+    pub fn write_entries(&mut self, log_data: &[LogData]) -> Result<(), ParserError> {
+        for entry in log_data {
+            self.write_entry(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> Result<(), ParserError> {
+        if let Err(err) = self.writer.flush() {
+            error!("[macos-unifiedlogs] Failed to flush timeline: {:?}", err);
+            return Err(ParserError::Write);
+        }
+        Ok(())
+    }
+
+    /// Number of log entries written so far
+    pub fn entries_written(&self) -> u64 {
+        self.entries_written
+    }
+
+    /// Return the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+const L2T_CSV_HEADER: &str =
+    "date,time,timezone,MACB,source,sourcetype,type,user,host,short,desc,version,filename,inode,notes,format,extra";
+
+/// Build the log2timeline CSV columns for an entry
+fn l2t_csv_line(log_data: &LogData, hostname: &str, source: &str) -> String {
+    let utc = Utc.fix();
+    let columns = [
+        unixepoch_to_local(&log_data.time, &utc, "%m/%d/%Y"),
+        unixepoch_to_local(&log_data.time, &utc, "%H:%M:%S"),
+        String::from("UTC"),
+        String::from("...."),
+        String::from(TIMELINE_SOURCE),
+        String::from(TIMELINE_SOURCE_TYPE),
+        String::from("Recorded Time"),
+        log_data.euid.to_string(),
+        hostname.to_string(),
+        single_line(&log_data.message),
+        description(log_data),
+        String::from("2"),
+        source.to_string(),
+        String::from("-"),
+        String::from("-"),
+        String::from("macos-unifiedlogs"),
+        format!(
+            "event_type: {}; log_type: {}; thread_id: {:#x}; activity_id: {:#x}; boot_uuid: {}; library: {}",
+            log_data.event_type,
+            log_data.log_type,
+            log_data.thread_id,
+            log_data.activity_id,
+            log_data.boot_uuid,
+            log_data.library
+        ),
+    ];
+    columns
+        .iter()
+        .map(|column| csv_field(column))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Build a TLN line. `|` is not allowed in the fields and is replaced with `/`
+fn tln_line(log_data: &LogData, hostname: &str, source: &str) -> String {
+    let description = if source.is_empty() {
+        description(log_data)
+    } else {
+        format!("{} ({})", description(log_data), source)
+    };
+    format!(
+        "{}|{}|{}|{}|{}",
+        unix_seconds(log_data),
+        TIMELINE_SOURCE,
+        hostname.replace('|', "/"),
+        log_data.euid,
+        description.replace('|', "/")
+    )
+}
+
+/// Build a bodyfile line: `MD5|name|inode|mode_as_string|UID|GID|size|atime|mtime|ctime|crtime`
+fn bodyfile_line(log_data: &LogData, source: &str) -> String {
+    let name = if source.is_empty() {
+        description(log_data)
+    } else {
+        format!("{}: {}", source, description(log_data))
+    };
+    format!(
+        "0|{}|0|0|{}|0|0|0|{}|0|0",
+        name.replace('|', "/"),
+        log_data.euid,
+        unix_seconds(log_data)
+    )
+}
+
+/// Describe an entry as `process[pid] (subsystem:category) message`
+fn description(log_data: &LogData) -> String {
+    let process = image_name(&log_data.process);
+    let message = single_line(&log_data.message);
+    if log_data.subsystem.is_empty() {
+        format!("{}[{}] {}", process, log_data.pid, message)
+    } else {
+        format!(
+            "{}[{}] ({}:{}) {}",
+            process, log_data.pid, log_data.subsystem, log_data.category, message
+        )
+    }
+}
+
+fn single_line(message: &str) -> String {
+    message.replace(['\r', '\n'], " ")
+}
+
+fn unix_seconds(log_data: &LogData) -> i64 {
+    let nanoseconds = 1000000000;
+    (log_data.time as i64).div_euclid(nanoseconds)
+}
+
+/// Quote a CSV field if it contains a separator, quote or new line
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{csv_field, TimelineFormat, TimelineWriter};
+    use crate::unified_log::{self, LogData};
+
+    fn test_log_data() -> LogData {
+        LogData {
+            message: String::from("LOMD Start 1, ready"),
+            raw_message: String::from("LOMD Start %d, ready"),
+            ..unified_log::test_log_data()
+        }
+    }
+
+    fn write(format: TimelineFormat) -> String {
+        let mut writer = TimelineWriter::new(Vec::new(), format);
+        writer.set_hostname("macbook");
+        writer.set_source("/var/db/diagnostics/Persist/0000000000000001.tracev3");
+        writer.write_entry(&test_log_data()).unwrap();
+        writer.flush().unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn test_l2t_csv() {
+        let output = write(TimelineFormat::L2tCsv);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("date,time,timezone,MACB,source"));
+        assert!(lines[1].starts_with(
+            "01/16/2022,03:05:26,UTC,....,ULOG,Apple Unified Log,Recorded Time,0,macbook,\"LOMD Start 1, ready\",\"lightsoutmanagementd[45] (com.apple.lom:device) LOMD Start 1, ready\",2,/var/db/diagnostics/Persist/0000000000000001.tracev3,-,-,macos-unifiedlogs,"
+        ));
+    }
+
+    #[test]
+    fn test_tln() {
+        let output = write(TimelineFormat::Tln);
+        assert_eq!(
+            output,
+            "1642302326|ULOG|macbook|0|lightsoutmanagementd[45] (com.apple.lom:device) LOMD Start 1, ready (/var/db/diagnostics/Persist/0000000000000001.tracev3)\n"
+        );
+    }
+
+    #[test]
+    fn test_bodyfile() {
+        let output = write(TimelineFormat::Bodyfile);
+        assert_eq!(
+            output,
+            "0|/var/db/diagnostics/Persist/0000000000000001.tracev3: lightsoutmanagementd[45] (com.apple.lom:device) LOMD Start 1, ready|0|0|0|0|0|0|1642302326|0|0\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("test"), "test");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
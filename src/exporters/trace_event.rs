@@ -0,0 +1,570 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Export signpost intervals and activities as Chrome Trace Event JSON, which can be loaded in Perfetto or `chrome://tracing`
+//!
+//! Signpost entries are only built if `LogOptions.signposts` is set.
+//! Signpost begin and end entries are paired per process, signpost ID and signpost name.
+//! Thread signposts are drawn on the thread track, process and system signposts as async tracks.
+//! Activities are drawn from their first to their last log entry and named after their create entry (see `LogOptions.activities`)
+
+use crate::error::ParserError;
+use crate::unified_log::LogData;
+use crate::util::image_name;
+use log::error;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+/// Scope of a signpost from the log type (ex: `Thread Signpost Start`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignpostScope {
+    Process,
+    Thread,
+    System,
+}
+
+/// Kind of a signpost from the log type (ex: `Thread Signpost Start`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignpostKind {
+    Begin,
+    End,
+    Event,
+}
+
+/// Signpost details of a log entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignpostMarker<'a> {
+    pub scope: SignpostScope,
+    pub kind: SignpostKind,
+    pub signpost_id: u64,
+    pub signpost_name: u32,
+    /// Log message without the `Signpost ID` prefix
+    pub message: &'a str,
+}
+
+impl SignpostMarker<'_> {
+    /// Get the signpost details from a signpost log entry. Returns `None` for other entries
+    pub fn from_log_data(log_data: &LogData) -> Option<SignpostMarker<'_>> {
+        let mut parts = log_data.log_type.split(" Signpost ");
+        let scope = match parts.next()? {
+            "Process" => SignpostScope::Process,
+            "Thread" => SignpostScope::Thread,
+            "System" => SignpostScope::System,
+            _ => return None,
+        };
+        let kind = match parts.next()? {
+            "Start" => SignpostKind::Begin,
+            "End" => SignpostKind::End,
+            "Event" => SignpostKind::Event,
+            _ => return None,
+        };
+
+        // build_log() prefixes signpost messages with: Signpost ID: {:X} - Signpost Name: {:X}\n
+        let prefix = format!(
+            "Signpost ID: {:X} - Signpost Name: {:X}\n ",
+            log_data.signpost_id, log_data.signpost_name
+        );
+        let message = match log_data.message.find(&prefix) {
+            Some(start) => &log_data.message[start + prefix.len()..],
+            None => &log_data.message,
+        };
+
+        Some(SignpostMarker {
+            scope,
+            kind,
+            signpost_id: log_data.signpost_id,
+            signpost_name: log_data.signpost_name,
+            message,
+        })
+    }
+}
+
+/// A signpost interval built from matching begin and end entries
+#[derive(Debug, Clone)]
+pub struct SignpostInterval {
+    pub scope: SignpostScope,
+    pub signpost_id: u64,
+    pub signpost_name: u32,
+    pub pid: u64,
+    pub thread_id: u64,
+    pub process: String,
+    pub subsystem: String,
+    pub category: String,
+    /// Format string of the begin entry
+    pub name: String,
+    pub begin_message: String,
+    pub end_message: String,
+    /// Nanoseconds since UNIXEPOCH
+    pub begin: f64,
+    pub end: f64,
+}
+
+impl SignpostInterval {
+    fn new(begin: &LogData, end: &LogData) -> SignpostInterval {
+        let begin_marker = SignpostMarker::from_log_data(begin);
+        let end_marker = SignpostMarker::from_log_data(end);
+        SignpostInterval {
+            scope: begin_marker
+                .as_ref()
+                .map_or(SignpostScope::Process, |marker| marker.scope),
+            signpost_id: begin_marker.as_ref().map_or(0, |marker| marker.signpost_id),
+            signpost_name: begin_marker
+                .as_ref()
+                .map_or(0, |marker| marker.signpost_name),
+            pid: begin.pid,
+            thread_id: begin.thread_id,
+            process: begin.process.to_owned(),
+            subsystem: begin.subsystem.to_owned(),
+            category: begin.category.to_owned(),
+            name: signpost_event_name(begin),
+            begin_message: begin_marker
+                .map(|marker| marker.message.to_string())
+                .unwrap_or_default(),
+            end_message: end_marker
+                .map(|marker| marker.message.to_string())
+                .unwrap_or_default(),
+            begin: begin.time,
+            end: end.time,
+        }
+    }
+
+    /// Length of the interval in nanoseconds
+    pub fn duration(&self) -> f64 {
+        self.end - self.begin
+    }
+}
+
+/// Key used to pair signpost begin and end entries: pid, signpost ID and signpost name
+type SignpostKey = (u64, u64, u32);
+
+/// Pair signpost begin and end entries. Entries should be in time order.
+/// Nested intervals with the same key are matched innermost first, unmatched entries are ignored
+pub fn pair_signposts(log_data: &[LogData]) -> Vec<SignpostInterval> {
+    let mut open: HashMap<SignpostKey, Vec<&LogData>> = HashMap::new();
+    let mut intervals = Vec::new();
+    for entry in log_data {
+        let Some(marker) = SignpostMarker::from_log_data(entry) else {
+            continue;
+        };
+        let key = (entry.pid, marker.signpost_id, marker.signpost_name);
+        match marker.kind {
+            SignpostKind::Begin => open.entry(key).or_default().push(entry),
+            SignpostKind::End => {
+                if let Some(begin) = open.get_mut(&key).and_then(|entries| entries.pop()) {
+                    intervals.push(SignpostInterval::new(begin, entry));
+                }
+            }
+            SignpostKind::Event => {}
+        }
+    }
+    intervals
+}
+
+/// First and last log entry of an activity
+struct ActivitySpan {
+    name: String,
+    begin: f64,
+    end: f64,
+    thread_id: u64,
+    entries: u64,
+}
+
+/// Stream `LogData` entries to any writer as a Chrome Trace Event JSON object.
+/// Intervals are written when their end entry is seen, unmatched begins and activities are written by `finish()`
+pub struct TraceEventWriter<W: Write> {
+    writer: W,
+    open_signposts: HashMap<SignpostKey, Vec<LogData>>,
+    activities: BTreeMap<(u64, u64), ActivitySpan>,
+    processes: BTreeMap<u64, String>,
+    events_written: u64,
+}
+
+impl<W: Write> TraceEventWriter<W> {
+    /// Create a Trace Event writer
+    pub fn new(writer: W) -> TraceEventWriter<W> {
+        TraceEventWriter {
+            writer,
+            open_signposts: HashMap::new(),
+            activities: BTreeMap::new(),
+            processes: BTreeMap::new(),
+            events_written: 0,
+        }
+    }
+
+    /// Add a log entry. Entries should be in time order
+    pub fn write_entry(&mut self, log_data: &LogData) -> Result<(), ParserError> {
+        self.processes
+            .entry(log_data.pid)
+            .or_insert_with(|| log_data.process.to_owned());
+
+        if log_data.activity_id != 0 {
+            let span = self
+                .activities
+                .entry((log_data.pid, log_data.activity_id))
+                .or_insert_with(|| ActivitySpan {
+                    name: format!("Activity {:#x}", log_data.activity_id),
+                    begin: log_data.time,
+                    end: log_data.time,
+                    thread_id: log_data.thread_id,
+                    entries: 0,
+                });
+            if log_data.event_type == "Activity" && !log_data.message.is_empty() {
+                span.name = log_data.message.to_owned();
+            }
+            span.begin = span.begin.min(log_data.time);
+            span.end = span.end.max(log_data.time);
+            span.entries += 1;
+        }
+
+        let Some(marker) = SignpostMarker::from_log_data(log_data) else {
+            return Ok(());
+        };
+        let key = (log_data.pid, marker.signpost_id, marker.signpost_name);
+        match marker.kind {
+            SignpostKind::Begin => {
+                self.open_signposts
+                    .entry(key)
+                    .or_default()
+                    .push(log_data.to_owned());
+                Ok(())
+            }
+            SignpostKind::End => {
+                let begin = self
+                    .open_signposts
+                    .get_mut(&key)
+                    .and_then(|entries| entries.pop());
+                match begin {
+                    Some(begin) => {
+                        let interval = SignpostInterval::new(&begin, log_data);
+                        for event in interval_events(&interval) {
+                            self.write_event(&event)?;
+                        }
+                        Ok(())
+                    }
+                    None => self.write_event(&instant_event(log_data, &marker, true)),
+                }
+            }
+            SignpostKind::Event => self.write_event(&instant_event(log_data, &marker, false)),
+        }
+    }
+
+    /// Add all log entries
+    pub fn write_entries(&mut self, log_data: &[LogData]) -> Result<(), ParserError> {
+        for entry in log_data {
+            self.write_entry(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Write unmatched signpost begins, activity spans and process names, close the JSON object and return the underlying writer
+    pub fn finish(mut self) -> Result<W, ParserError> {
+        let mut unmatched: Vec<LogData> = self
+            .open_signposts
+            .drain()
+            .flat_map(|(_, entries)| entries)
+            .collect();
+        unmatched.sort_by(|first, second| first.time.total_cmp(&second.time));
+        for entry in &unmatched {
+            if let Some(marker) = SignpostMarker::from_log_data(entry) {
+                self.write_event(&instant_event(entry, &marker, true))?;
+            }
+        }
+
+        let activities = std::mem::take(&mut self.activities);
+        for ((pid, activity_id), span) in &activities {
+            let id = json!({ "local": format!("{:#x}", activity_id) });
+            let args = json!({ "activity_id": activity_id, "entries": span.entries });
+            self.write_event(&json!({
+                "name": span.name,
+                "cat": "activity",
+                "ph": "b",
+                "ts": microseconds(span.begin),
+                "pid": pid,
+                "tid": span.thread_id,
+                "id2": id,
+                "args": args,
+            }))?;
+            self.write_event(&json!({
+                "name": span.name,
+                "cat": "activity",
+                "ph": "e",
+                "ts": microseconds(span.end),
+                "pid": pid,
+                "tid": span.thread_id,
+                "id2": id,
+            }))?;
+        }
+
+        let processes = std::mem::take(&mut self.processes);
+        for (pid, process) in &processes {
+            self.write_event(&json!({
+                "name": "process_name",
+                "ph": "M",
+                "pid": pid,
+                "args": { "name": format!("{} ({})", image_name(process), pid) },
+            }))?;
+        }
+
+        let end = if self.events_written == 0 {
+            "{\"traceEvents\":[],\"displayTimeUnit\":\"ns\"}\n"
+        } else {
+            "\n],\"displayTimeUnit\":\"ns\"}\n"
+        };
+        if let Err(err) = self.writer.write_all(end.as_bytes()) {
+            error!(
+                "[macos-unifiedlogs] Failed to finish Trace Event JSON: {:?}",
+                err
+            );
+            return Err(ParserError::Write);
+        }
+        if let Err(err) = self.writer.flush() {
+            error!(
+                "[macos-unifiedlogs] Failed to flush Trace Event JSON: {:?}",
+                err
+            );
+            return Err(ParserError::Write);
+        }
+        Ok(self.writer)
+    }
+
+    /// Number of trace events written so far
+    pub fn events_written(&self) -> u64 {
+        self.events_written
+    }
+
+    fn write_event(&mut self, event: &Value) -> Result<(), ParserError> {
+        let separator = if self.events_written == 0 {
+            "{\"traceEvents\":[\n"
+        } else {
+            ",\n"
+        };
+        let line = format!("{}{}", separator, event);
+        if let Err(err) = self.writer.write_all(line.as_bytes()) {
+            error!("[macos-unifiedlogs] Failed to write trace event: {:?}", err);
+            return Err(ParserError::Write);
+        }
+        self.events_written += 1;
+        Ok(())
+    }
+}
+
+/// Thread intervals are complete events on the thread track. Process and system intervals are async events
+fn interval_events(interval: &SignpostInterval) -> Vec<Value> {
+    let args = json!({
+        "signpost_id": format!("{:#X}", interval.signpost_id),
+        "signpost_name": format!("{:#X}", interval.signpost_name),
+        "begin_message": interval.begin_message,
+        "end_message": interval.end_message,
+    });
+    let category = event_category(&interval.subsystem, &interval.category);
+    if interval.scope == SignpostScope::Thread {
+        return vec![json!({
+            "name": interval.name,
+            "cat": category,
+            "ph": "X",
+            "ts": microseconds(interval.begin),
+            "dur": microseconds(interval.duration()),
+            "pid": interval.pid,
+            "tid": interval.thread_id,
+            "args": args,
+        })];
+    }
+
+    let id = format!("{:#x}", interval.signpost_id);
+    let id = if interval.scope == SignpostScope::System {
+        json!({ "global": id })
+    } else {
+        json!({ "local": id })
+    };
+    vec![
+        json!({
+            "name": interval.name,
+            "cat": category,
+            "ph": "b",
+            "ts": microseconds(interval.begin),
+            "pid": interval.pid,
+            "tid": interval.thread_id,
+            "id2": id,
+            "args": args,
+        }),
+        json!({
+            "name": interval.name,
+            "cat": category,
+            "ph": "e",
+            "ts": microseconds(interval.end),
+            "pid": interval.pid,
+            "tid": interval.thread_id,
+            "id2": id,
+        }),
+    ]
+}
+
+/// Signpost events and unmatched begin or end entries are instant events
+fn instant_event(log_data: &LogData, marker: &SignpostMarker<'_>, unmatched: bool) -> Value {
+    let scope = match marker.scope {
+        SignpostScope::Thread => "t",
+        SignpostScope::Process => "p",
+        SignpostScope::System => "g",
+    };
+    let mut args = json!({
+        "signpost_id": format!("{:#X}", marker.signpost_id),
+        "signpost_name": format!("{:#X}", marker.signpost_name),
+        "message": marker.message,
+    });
+    if unmatched {
+        args["unmatched"] = json!(true);
+    }
+    json!({
+        "name": signpost_event_name(log_data),
+        "cat": event_category(&log_data.subsystem, &log_data.category),
+        "ph": "i",
+        "s": scope,
+        "ts": microseconds(log_data.time),
+        "pid": log_data.pid,
+        "tid": log_data.thread_id,
+        "args": args,
+    })
+}
+
+/// Signpost names are not resolved, so the format string is used when available
+fn signpost_event_name(log_data: &LogData) -> String {
+    if !log_data.raw_message.is_empty() {
+        return log_data.raw_message.to_owned();
+    }
+    match SignpostMarker::from_log_data(log_data) {
+        Some(marker) => format!("Signpost {:#X}", marker.signpost_name),
+        None => log_data.log_type.to_owned(),
+    }
+}
+
+fn event_category(subsystem: &str, category: &str) -> String {
+    if subsystem.is_empty() {
+        String::from("signpost")
+    } else {
+        format!("{}:{}", subsystem, category)
+    }
+}
+
+/// Trace Event timestamps are in microseconds
+fn microseconds(nanoseconds: f64) -> f64 {
+    nanoseconds / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pair_signposts, SignpostKind, SignpostMarker, SignpostScope, TraceEventWriter};
+    use crate::unified_log::{test_log_data, LogData};
+    use serde_json::Value;
+
+    fn signpost(log_type: &str, signpost_id: u64, time: f64, message: &str) -> LogData {
+        let mut log_data = test_log_data();
+        log_data.event_type = String::from("Signpost");
+        log_data.log_type = log_type.to_string();
+        log_data.time = time;
+        log_data.raw_message = String::from("Load");
+        log_data.signpost_id = signpost_id;
+        log_data.signpost_name = 0x1e3a5;
+        log_data.message = format!(
+            "Signpost ID: {:X} - Signpost Name: {:X}\n {}",
+            signpost_id, 0x1e3a5, message
+        );
+        log_data
+    }
+
+    #[test]
+    fn test_signpost_marker() {
+        let log_data = signpost("Thread Signpost Start", 0xEEEEB0B5B2B2EEEE, 1.0, "begin");
+        let marker = SignpostMarker::from_log_data(&log_data).unwrap();
+        assert_eq!(marker.scope, SignpostScope::Thread);
+        assert_eq!(marker.kind, SignpostKind::Begin);
+        assert_eq!(marker.signpost_id, 0xEEEEB0B5B2B2EEEE);
+        assert_eq!(marker.signpost_name, 0x1e3a5);
+        assert_eq!(marker.message, "begin");
+
+        assert!(SignpostMarker::from_log_data(&test_log_data()).is_none());
+    }
+
+    #[test]
+    fn test_pair_signposts() {
+        let entries = vec![
+            signpost("Process Signpost Start", 1, 1000.0, "outer"),
+            signpost("Process Signpost Start", 1, 2000.0, "inner"),
+            signpost("Process Signpost Start", 2, 2500.0, "other"),
+            signpost("Process Signpost End", 1, 3000.0, "inner done"),
+            signpost("Process Signpost End", 1, 5000.0, "outer done"),
+            signpost("Process Signpost End", 3, 6000.0, "unmatched"),
+        ];
+        let intervals = pair_signposts(&entries);
+        assert_eq!(intervals.len(), 2);
+        assert_eq!(intervals[0].begin_message, "inner");
+        assert_eq!(intervals[0].end_message, "inner done");
+        assert_eq!(intervals[0].duration(), 1000.0);
+        assert_eq!(intervals[1].begin_message, "outer");
+        assert_eq!(intervals[1].duration(), 4000.0);
+        assert_eq!(intervals[1].name, "Load");
+    }
+
+    #[test]
+    fn test_trace_event_writer() {
+        let mut activity = test_log_data();
+        activity.event_type = String::from("Activity");
+        activity.activity_id = 0x51;
+        activity.time = 500000.0;
+        activity.message = String::from("Loading images");
+        let mut log_entry = test_log_data();
+        log_entry.activity_id = 0x51;
+        log_entry.time = 1500000.0;
+
+        let entries = vec![
+            activity,
+            signpost("Thread Signpost Start", 1, 1000000.0, "begin"),
+            log_entry,
+            signpost("Thread Signpost End", 1, 3000000.0, "end"),
+            signpost("System Signpost Start", 2, 3000000.0, "open"),
+            signpost("Process Signpost Event", 4, 4000000.0, "mark"),
+        ];
+        let mut writer = TraceEventWriter::new(Vec::new());
+        writer.write_entries(&entries).unwrap();
+        let output = writer.finish().unwrap();
+
+        let trace: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(trace["displayTimeUnit"], "ns");
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 6);
+
+        assert_eq!(events[0]["ph"], "X");
+        assert_eq!(events[0]["ts"], 1000.0);
+        assert_eq!(events[0]["dur"], 2000.0);
+        assert_eq!(events[0]["tid"], 588);
+        assert_eq!(events[0]["cat"], "com.apple.lom:device");
+        assert_eq!(events[0]["args"]["end_message"], "end");
+
+        assert_eq!(events[1]["ph"], "i");
+        assert_eq!(events[1]["s"], "p");
+        assert_eq!(events[2]["ph"], "i");
+        assert_eq!(events[2]["s"], "g");
+        assert_eq!(events[2]["args"]["unmatched"], true);
+
+        assert_eq!(events[3]["ph"], "b");
+        assert_eq!(events[3]["name"], "Loading images");
+        assert_eq!(events[3]["ts"], 500.0);
+        assert_eq!(events[4]["ph"], "e");
+        assert_eq!(events[4]["ts"], 1500.0);
+
+        assert_eq!(events[5]["ph"], "M");
+        assert_eq!(events[5]["args"]["name"], "lightsoutmanagementd (45)");
+    }
+
+    #[test]
+    fn test_trace_event_writer_empty() {
+        let writer = TraceEventWriter::new(Vec::new());
+        let output = writer.finish().unwrap();
+        let trace: Value = serde_json::from_slice(&output).unwrap();
+        assert!(trace["traceEvents"].as_array().unwrap().is_empty());
+    }
+}
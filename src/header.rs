@@ -7,13 +7,14 @@
 
 use std::{mem::size_of, str::from_utf8};
 
+use chrono::{FixedOffset, Offset, Utc};
 use log::warn;
 use nom::{
     bytes::complete::take,
     number::complete::{be_u128, le_u32, le_u64},
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct HeaderChunk {
     pub chunk_tag: u32,
     pub chunk_sub_tag: u32,
@@ -197,6 +198,24 @@ impl HeaderChunk {
 
         Ok((input, header_chunk))
     }
+
+    /// Get the UTC offset of the system that wrote the tracev3 file. Bias is minutes west of UTC, daylight savings adds one hour
+    pub fn timezone_offset(&self) -> FixedOffset {
+        let minute = 60;
+        let hour = 3600;
+        let bias = i32::from_le_bytes(self.bias_min.to_le_bytes());
+        let mut offset = -bias * minute;
+        if self.daylight_savings != 0 {
+            offset += hour;
+        }
+        FixedOffset::east_opt(offset).unwrap_or_else(|| {
+            warn!(
+                "[macos-unifiedlogs] Invalid timezone bias in header: {}",
+                self.bias_min
+            );
+            Utc.fix()
+        })
+    }
 }
 
 #[cfg(test)]
@@ -252,4 +271,20 @@ mod tests {
             "/var/db/timezone/zoneinfo/America/New_York"
         );
     }
+
+    #[test]
+    fn test_timezone_offset() {
+        let mut header_data = HeaderChunk {
+            bias_min: 300,
+            ..Default::default()
+        };
+        assert_eq!(header_data.timezone_offset().local_minus_utc(), -5 * 3600);
+
+        header_data.daylight_savings = 1;
+        assert_eq!(header_data.timezone_offset().local_minus_utc(), -4 * 3600);
+
+        header_data.bias_min = u32::from_le_bytes((-60_i32).to_le_bytes());
+        header_data.daylight_savings = 0;
+        assert_eq!(header_data.timezone_offset().local_minus_utc(), 3600);
+    }
 }
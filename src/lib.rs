@@ -31,16 +31,24 @@
     clippy::checked_conversions,
     clippy::unnecessary_cast
 )]
-mod catalog;
+pub mod activity;
+pub mod boot;
+pub mod catalog;
 mod chunks;
 mod chunkset;
-mod decoders;
+pub mod decoders;
 pub mod dsc;
-mod error;
-mod header;
+pub mod error;
+pub mod exporters;
+pub mod header;
 mod message;
+pub mod message_value;
 pub mod parser;
+pub mod predicate;
+pub mod query;
+pub mod stats;
 mod preamble;
+mod printf;
 pub mod timesync;
 pub mod unified_log;
 mod util;
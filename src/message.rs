@@ -5,14 +5,15 @@
 // is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and limitations under the License.
 
-use std::mem::size_of;
-
 use crate::chunks::firehose::firehose_log::FirehoseItemInfo;
-use crate::decoders::decoder;
+use crate::decoders::darwin::strerror;
+use crate::decoders::decoder::{self, DecoderRegistry, FormatOptions};
+use crate::decoders::keyed_archive;
+use crate::message_value::MessageValue;
+use crate::printf::{self, FormatSpec, PrintfArg};
+use crate::util::{decode_standard, encode_standard};
 use log::{error, info, warn};
-use nom::branch::alt;
-use nom::bytes::complete::{is_a, is_not, take, take_until};
-use nom::character::complete::digit0;
+use nom::bytes::complete::{is_a, take_until};
 use regex::Regex;
 
 struct FormatAndMessage {
@@ -20,18 +21,30 @@ struct FormatAndMessage {
     message: String,
 }
 
-const FLOAT_TYPES: [&str; 6] = ["f", "F", "e", "E", "g", "G"];
-const INT_TYPES: [&str; 4] = ["d", "D", "i", "u"];
-const HEX_TYPES: [&str; 5] = ["x", "X", "a", "A", "p"];
-const OCTAL_TYPES: [&str; 2] = ["o", "O"];
-const ERROR_TYPES: [&str; 1] = ["m"];
-const STRING_TYPES: [&str; 6] = ["c", "s", "@", "S", "C", "P"];
+const FLOAT_TYPES: [char; 8] = ['f', 'F', 'e', 'E', 'g', 'G', 'a', 'A'];
+const INT_TYPES: [char; 10] = ['d', 'D', 'i', 'u', 'U', 'x', 'X', 'o', 'O', 'p'];
+const UNSIGNED_TYPES: [char; 7] = ['u', 'U', 'x', 'X', 'o', 'O', 'p'];
+const CHAR_TYPES: [char; 2] = ['c', 'C'];
+const ERROR_TYPES: [char; 1] = ['m'];
+const OBJECT_TYPES: [char; 2] = ['@', 'P'];
+// Item types for arbitrary data, stored as base64
+const ARBITRARY_ITEM_TYPES: [u8; 4] = [0x30, 0x31, 0x32, 0xf2];
+// Dynamic precision items, the value is the item size
+const PRECISION_ITEM_TYPES: [u8; 2] = [0x10, 0x12];
+const NUMBER_ITEM_TYPES: [u8; 3] = [0x0, 0x1, 0x2];
+// Private strings, arbitrary data, objects, sensitive values and numbers
+const PRIVATE_ITEM_TYPES: [u8; 7] = [0x1, 0x21, 0x25, 0x31, 0x35, 0x41, 0x45];
 
 /// Format the Unified Log message entry based on the parsed log items. Formatting follows the C lang prinf formatting process
+///
+/// The `message_value` of each formatted item is updated to its typed value. Number items are converted based on the formatter type and length
+/// Ex: `%u` -> `MessageValue::Unsigned`, `%f` -> `MessageValue::Float`, `%{private, mask.hash}s` -> `MessageValue::Hashed`
 pub fn format_firehose_log_message(
     format_string: String,
-    item_message: &Vec<FirehoseItemInfo>,
+    item_message: &mut [FirehoseItemInfo],
     message_re: &Regex,
+    decoders: &DecoderRegistry,
+    format_options: &FormatOptions,
 ) -> String {
     let mut log_message = format_string;
     let mut format_and_message_vec: Vec<FormatAndMessage> = Vec::new();
@@ -100,11 +113,20 @@ pub fn format_firehose_log_message(
             continue;
         }
 
-        let private_strings = [0x1, 0x21, 0x31, 0x41];
-        let private_number = 0x1;
-        let private_message = 0x8000;
+        // Width and precision items are followed by the value item. Ex: %{private}.*P
+        let value_index = match printf::parse_formatter_spec(formatter_string) {
+            Some(spec) => dynamic_items(&spec, item_message, item_index).1,
+            None => item_index,
+        };
+        if value_index >= item_message.len() {
+            format_and_message.formatter = formatter_string.to_string();
+            format_and_message.message = String::from("<Missing message data>");
+            format_and_message_vec.push(format_and_message);
+            item_index = value_index;
+            continue;
+        }
         if formatter_string.starts_with("%{") {
-            // If item type is [0x1, 0x21, 0x31, 0x41] and the value is zero. Its appears to be a private string
+            // If item type is [0x1, 0x21, 0x25, 0x31, 0x35, 0x41, 0x45] and the value is zero. Its appears to be a private string
             /*
                0x31 (object type) example below
                 tp 16 + 413:        log default (shared_cache, has_subsystem)
@@ -153,22 +175,23 @@ pub fn format_firehose_log_message(
                     Duration: 1.835s, DNS @0.000s took 0.018s, TCP @0.018s took 0.015s, TLS took 0.040s
                     bytes in/out: 9003/941, packets in/out: 8/8, rtt: 0.010s, retransmitted packets: 0, out-of-order packets: 0
             */
-            if private_strings.contains(&item_message[item_index].item_type)
-                && item_message[item_index].message_strings.is_empty()
-                && item_message[item_index].item_size == 0
-                || (item_message[item_index].item_type == private_number
-                    && item_message[item_index].item_size == private_message)
-            {
+            if redacted(&item_message[value_index], format_options.reveal_private) {
                 formatted_log_message = String::from("<private>");
+                item_message[value_index].message_value = MessageValue::Private;
             } else {
                 let results = parse_type_formatter(
                     formatter_string,
                     item_message,
                     &item_message[item_index].item_type,
                     item_index,
+                    decoders,
+                    format_options,
                 );
                 match results {
-                    Ok((_, formatted_message)) => formatted_log_message = formatted_message,
+                    Ok((_, (formatted_message, value))) => {
+                        formatted_log_message = formatted_message;
+                        item_message[value_index].message_value = value;
+                    }
                     Err(err) => warn!(
                         "Failed to format message type ex: public/private: {:?}",
                         err
@@ -176,7 +199,7 @@ pub fn format_firehose_log_message(
                 }
             }
         } else {
-            // If item type is [0x1, 0x21, 0x31, 0x41] and the size is zero (or 0x8000 for 0x1). Its appears to be a literal <private> string
+            // If item type is [0x1, 0x21, 0x25, 0x31, 0x35, 0x41, 0x45] and the size is zero (or 0x8000 for 0x1). Its appears to be a literal <private> string
             /*
                 0x1 (number type) example below
                 tp 456 + 54:        log default (main_exe)
@@ -193,43 +216,23 @@ pub fn format_firehose_log_message(
                     format:         kext submap [0x%lx - 0x%lx], kernel text [0x%lx - 0x%lx]
                 kext submap [0x<private> - 0x<private>], kernel text [0x<private> - 0x<private>]
             */
-            if private_strings.contains(&item_message[item_index].item_type)
-                && item_message[item_index].message_strings.is_empty()
-                && item_message[item_index].item_size == 0
-                || (item_message[item_index].item_type == private_number
-                    && item_message[item_index].item_size == private_message)
-            {
+            if redacted(&item_message[value_index], format_options.reveal_private) {
                 formatted_log_message = String::from("<private>");
+                item_message[value_index].message_value = MessageValue::Private;
             } else {
-                let results = parse_formatter(
-                    formatter_string,
-                    item_message,
-                    &item_message[item_index].item_type,
-                    item_index,
-                );
+                let results =
+                    parse_formatter(formatter_string, item_message, item_index, format_options);
                 match results {
-                    Ok((_, formatted_message)) => formatted_log_message = formatted_message,
+                    Ok((_, (formatted_message, value))) => {
+                        formatted_log_message = formatted_message;
+                        item_message[value_index].message_value = value;
+                    }
                     Err(err) => warn!("[macos-unifiedlogs] Failed to format message: {:?}", err),
                 }
             }
         }
 
-        let precision_items = [0x10, 0x12]; // dynamic precision item types?
-                                            // If the item message was a precision type increment to actual value
-        if precision_items.contains(&item_message[item_index].item_type) {
-            item_index += 1;
-        }
-
-        // Also seen number type value 0 also used for dynamic width/precision value
-        let dynamic_precision_value = 0x0;
-        if (item_message[item_index].item_type == dynamic_precision_value
-            && item_message[item_index].item_size == 0)
-            && formatter_string.contains("%*")
-        {
-            item_index += 1;
-        }
-
-        item_index += 1;
+        item_index = value_index + 1;
         format_and_message.formatter = formatter.as_str().to_string();
         format_and_message.message = formatted_log_message;
         format_and_message_vec.push(format_and_message);
@@ -256,218 +259,207 @@ pub fn format_firehose_log_message(
     log_message_vec.join("")
 }
 
+// Check if a private item should be shown as <private>
+// Private items only have a value if the logs were collected with private data enabled
+fn redacted(item: &FirehoseItemInfo, reveal_private: bool) -> bool {
+    if !PRIVATE_ITEM_TYPES.contains(&item.item_type) {
+        return false;
+    }
+    let private_number = 0x1;
+    let private_message = 0x8000;
+    !reveal_private
+        || item.message_value == MessageValue::Private
+        || (item.message_strings.is_empty() && item.item_size == 0)
+        || (item.item_type == private_number && item.item_size == private_message)
+}
+
 // Format strings are based on C printf formats. Parse format specification
+// Returns the formatted message and the typed value of the message item
 fn parse_formatter<'a>(
     formatter: &'a str,
-    message_value: &'a Vec<FirehoseItemInfo>,
-    item_type: &'a u8,
+    message_value: &[FirehoseItemInfo],
     item_index: usize,
-) -> nom::IResult<&'a str, String> {
-    let mut index = item_index;
-
-    let precision_items = [0x10, 0x12];
-    let mut precision_value = 0;
-    if precision_items.contains(item_type) {
-        precision_value = message_value[index].item_size as usize;
-        index += 1;
-    }
-
-    let mut message = message_value[index].message_strings.to_owned();
-
-    let number_item_type: Vec<u8> = vec![0x0, 0x1, 0x2];
-
-    // If the message formatter is expects a string/character and the message string is a number type
-    // Try to convert to a character/string
-    if formatter.to_lowercase().ends_with('c')
-        && number_item_type.contains(&message_value[index].item_type)
-    {
-        let char_results = message_value[index].message_strings.parse::<u32>();
-        match char_results {
-            Ok(char_message) => message = (char_message as u8 as char).to_string(),
-            Err(err) => {
-                error!(
-                    "[macos-unifiedlogs] Failed to parse number item to char string: {:?}",
-                    err
-                );
-                return Ok((
-                    "",
-                    String::from("Failed to parse number item to char string"),
-                ));
-            }
-        }
-    }
-
-    let mut left_justify = false;
-    //let mut space_value = false;
-    let mut hashtag = false;
-    let mut pad_zero = false;
-    let mut plus_minus = false;
-    let mut width_index = 1;
-    //let mut has_apostrophe = false;
-    for (index, format_values) in formatter.chars().enumerate() {
-        if index == 0 {
-            continue;
+    format_options: &FormatOptions,
+) -> nom::IResult<&'a str, (String, MessageValue)> {
+    // The first character is either % or the closing } of a type. Ex: %{public}s
+    let spec_results = formatter
+        .get(1..)
+        .and_then(printf::parse_spec);
+    let mut spec = match spec_results {
+        Some(result) => result,
+        None => {
+            error!(
+                "[macos-unifiedlogs] Failed to parse format specification: {}",
+                formatter
+            );
+            return Ok(("", item_message(message_value, item_index)));
         }
+    };
 
-        match format_values {
-            '-' => left_justify = true,
-            '+' => plus_minus = true,
-            //' ' => space_value = true,
-            '#' => hashtag = true,
-            '0' => pad_zero = true,
-            // '\'' => has_apostrophe = true,
-            _ => {
-                width_index = index;
-                break;
-            }
+    let (dynamic_values, index) = dynamic_items(&spec, message_value, item_index);
+    // The dynamic values are used for the "*" width then the precision. Precision items are also logged for formatters without a "*". Ex: %.16P
+    let mut dynamic_values = dynamic_values.into_iter();
+    // The "*" items are a C int like in printf
+    if spec.dynamic_width {
+        if let Some(width) = dynamic_values.next() {
+            // A negative width is left justified
+            let width = width as i32;
+            spec.left_justify |= width < 0;
+            spec.width = usize::try_from(width.unsigned_abs())
+                .ok()
+                .map(|width| width.min(printf::MAX_FIELD_SIZE));
         }
     }
+    if let Some(precision) = dynamic_values.next() {
+        // A negative precision is ignored
+        spec.precision = usize::try_from(precision as i32)
+            .ok()
+            .map(|precision| precision.min(printf::MAX_FIELD_SIZE));
+    }
 
-    let mut formatter_message = &formatter[width_index..];
-    let (input, mut width) = digit0(formatter_message)?;
-    formatter_message = input;
-    let width_value;
-
-    if formatter_message.starts_with('*') {
-        // Also seen number type value 0 used for dynamic width/precision value
-        let dynamic_precision_value = 0x0;
-        if item_type == &dynamic_precision_value && message_value[index].item_size == 0 {
-            precision_value = message_value[index].item_size as usize;
-            index += 1;
-            message = message_value[index].message_strings.to_owned();
+    if index >= message_value.len() {
+        return Ok(("", item_message(message_value, index)));
+    }
+    let message = message_value[index].message_strings.to_owned();
+    // Number items are sign extended when parsed, use the formatter type and length to get the typed value
+    let value = match message_value[index].message_value {
+        MessageValue::Signed(number) if FLOAT_TYPES.contains(&spec.conversion) => {
+            MessageValue::Float(f64::from_bits(number as u64))
         }
+        MessageValue::Signed(number) if UNSIGNED_TYPES.contains(&spec.conversion) => {
+            MessageValue::Unsigned(spec.unsigned_value(number))
+        }
+        ref value => value.to_owned(),
+    };
 
-        width_value = format!("{}", precision_value);
-        width = width_value.as_str();
-        let (input, _) = take(size_of::<u8>())(formatter_message)?;
-        formatter_message = input;
+    // Error types map the errno value logged for the formatter to the strerror() message
+    // Ex: open on %s: %m
+    //    "open on /var/folders: No such file or directory"
+    // "No such file or directory" is error code 2
+    if ERROR_TYPES.contains(&spec.conversion) {
+        return Ok(("", (strerror(&message), value)));
     }
 
-    if formatter_message.starts_with('.') {
-        let (input, _) = is_a(".")(formatter_message)?;
-        let (input, precision_data) = is_not("hljzZtqLdDiuUoOcCxXfFeEgGaASspPn%@")(input)?;
-        if precision_data != "*" {
-            let precision_results = precision_data.parse::<usize>();
-            match precision_results {
-                Ok(value) => precision_value = value,
-                Err(err) => error!(
-                    "[macos-unifiedlogs] Failed to parse format precision value: {:?}",
-                    err
-                ),
-            }
-        } else if precision_value != 0 {
-            // For dynamic length use the length of the message string
-            precision_value = message_value.len();
+    // Objects may be logged as a binary plist, usually an NSKeyedArchiver archive
+    if OBJECT_TYPES.contains(&spec.conversion)
+        && ARBITRARY_ITEM_TYPES.contains(&message_value[index].item_type)
+    {
+        if let Some(plist_message) = keyed_archive::parse_plist_item(&message) {
+            return Ok(("", (plist_message, value)));
         }
-        formatter_message = input;
     }
 
-    // Get Length data if it exists or get the type format
-    let (input, length_data) =
-        alt((is_a("hlwIztq"), is_a("cmCdiouxXeEfgGaAnpsSZP@")))(formatter_message)?;
-    formatter_message = input;
-
-    let mut type_data = length_data;
-    let length_values = vec!["h", "hh", "l", "ll", "w", "I", "z", "t", "q"];
-    if length_values.contains(&length_data) {
-        let (_, type_format) = is_a("cmCdiouxXeEfgGaAnpsSZP@")(formatter_message)?;
-        type_data = type_format;
-    }
-
-    // Error types map error code to error message string. Currently not mapping to error message string
-    // Ex: open on %s: %m
-    //    "open on /var/folders: No such file or directory"
-    // "No such file or directory" is error code 2
-    if ERROR_TYPES.contains(&type_data) {
-        message = format!("Error code: {}", message);
-        return Ok(("", message));
+    // Binary data without an annotation. Ex: %.*P
+    // The precision is the size of the data, the binary decoder or the rendering policy is used instead of printf formatting
+    if spec.conversion == 'P' && ARBITRARY_ITEM_TYPES.contains(&message_value[index].item_type) {
+        if let Ok(data) = decode_standard(&message) {
+            let binary_message = format_options
+                .decode_binary(formatter, &data)
+                .unwrap_or_else(|| format_options.binary_rendering.render(&data));
+            return Ok(("", (binary_message, value)));
+        }
     }
 
-    if !width.is_empty() {
-        let mut width_value = 0;
-        let width_results = width.parse::<usize>();
-        match width_results {
-            Ok(value) => width_value = value,
-            Err(err) => error!(
-                "[macos-unifiedlogs] Failed to parse format width value: {:?}",
-                err
-            ),
+    // Numbers use the typed value of the item. If the message formatter expects a character and the item is a number
+    // Format the number as a character
+    let number_arg = match value {
+        MessageValue::Signed(number) => Some(PrintfArg::Int(number)),
+        MessageValue::Unsigned(number) => {
+            Some(PrintfArg::Int(i64::from_le_bytes(number.to_le_bytes())))
         }
-        if pad_zero {
-            // Pad using zeros instead of spaces
-            if left_justify {
-                message = format_alignment_left(
-                    message,
-                    width_value,
-                    precision_value,
-                    type_data,
-                    plus_minus,
-                    hashtag,
-                )
-            } else {
-                message = format_alignment_right(
-                    message,
-                    width_value,
-                    precision_value,
-                    type_data,
-                    plus_minus,
-                    hashtag,
-                )
-            }
+        MessageValue::Float(number) => Some(PrintfArg::Float(number)),
+        _ => None,
+    };
+    let arg = match number_arg {
+        Some(arg)
+            if FLOAT_TYPES.contains(&spec.conversion)
+                || INT_TYPES.contains(&spec.conversion)
+                || CHAR_TYPES.contains(&spec.conversion) =>
+        {
+            arg
+        }
+        _ => PrintfArg::Str(&message),
+    };
+    Ok(("", (spec.format(arg), value)))
+}
+
+// Width and precision values for "*" are logged as items before the value item. Ex: %*.*s
+// Precision items (0x10, 0x12) store the value as the item size, number items store it as the item value
+// Returns the dynamic values and the index of the value item
+fn dynamic_items(
+    spec: &FormatSpec,
+    message_value: &[FirehoseItemInfo],
+    item_index: usize,
+) -> (Vec<i64>, usize) {
+    let dynamic_count = usize::from(spec.dynamic_width) + usize::from(spec.dynamic_precision);
+    let mut values = Vec::new();
+    let mut index = item_index;
+    while let Some(item) = message_value.get(index) {
+        if PRECISION_ITEM_TYPES.contains(&item.item_type) {
+            values.push(i64::from(item.item_size));
+        } else if values.len() < dynamic_count && NUMBER_ITEM_TYPES.contains(&item.item_type) {
+            values.push(item.message_value.as_i64().unwrap_or_default());
         } else {
-            // Pad spaces instead of zeros
-            if left_justify {
-                message = format_alignment_left_space(
-                    message,
-                    width_value,
-                    precision_value,
-                    type_data,
-                    plus_minus,
-                    hashtag,
-                )
-            } else {
-                message = format_alignment_right_space(
-                    message,
-                    width_value,
-                    precision_value,
-                    type_data,
-                    plus_minus,
-                    hashtag,
-                )
-            }
+            break;
         }
-    } else if left_justify {
-        message = format_left(message, precision_value, type_data, plus_minus, hashtag)
-    } else {
-        message = format_right(message, precision_value, type_data, plus_minus, hashtag);
+        index += 1;
     }
+    (values, index)
+}
 
-    Ok(("", message))
+// Message string and value of an item, items missing from the log entry are labeled like log show
+fn item_message(message_value: &[FirehoseItemInfo], index: usize) -> (String, MessageValue) {
+    match message_value.get(index) {
+        Some(item) => (item.message_strings.to_owned(), item.message_value.to_owned()),
+        None => (String::from("<Missing message data>"), MessageValue::Missing),
+    }
 }
 
 // Function to parse formatters containing types. Ex: %{errno}d, %{public}s, %{private}s, %{sensitive}
 fn parse_type_formatter<'a>(
     formatter: &'a str,
-    message_value: &'a Vec<FirehoseItemInfo>,
-    item_type: &'a u8,
+    message_value: &[FirehoseItemInfo],
+    item_type: &u8,
     item_index: usize,
-) -> nom::IResult<&'a str, String> {
+    decoders: &DecoderRegistry,
+    format_options: &FormatOptions,
+) -> nom::IResult<&'a str, (String, MessageValue)> {
     let (format, format_type) = take_until("}")(formatter)?;
 
-    let apple_object = decoder::check_objects(format_type, message_value, item_type, item_index);
+    let apple_object = decoder::check_objects(
+        formatter,
+        message_value,
+        item_type,
+        item_index,
+        decoders,
+        format_options,
+    );
 
     // If we successfully decoded an apple object, then there is nothing to format.
     // Signpost entries have not been seen with custom objects
     if !apple_object.is_empty() {
-        return Ok(("", apple_object));
+        // Precision items are followed by the decoded item
+        let mut index = item_index;
+        if PRECISION_ITEM_TYPES.contains(item_type) {
+            index += 1;
+        }
+        let value = match message_value.get(index).map(|item| &item.message_value) {
+            Some(MessageValue::Bytes(data)) if format_type.contains("mask.hash") => {
+                MessageValue::Hashed(encode_standard(data))
+            }
+            Some(value) => value.to_owned(),
+            None => MessageValue::Missing,
+        };
+        return Ok(("", (apple_object, value)));
     }
 
-    let (_, mut message) = parse_formatter(format, message_value, item_type, item_index)?;
+    let (_, (mut message, value)) =
+        parse_formatter(format, message_value, item_index, format_options)?;
     if format_type.contains("signpost") {
         let (_, signpost_message) = parse_signpost_format(format_type)?;
         message = format!("{} ({})", message, signpost_message);
     }
-    Ok(("", message))
+    Ok(("", (message, value)))
 }
 
 // Try to parse additional signpost metadata.
@@ -488,610 +480,330 @@ fn parse_signpost_format(signpost_format: &str) -> nom::IResult<&str, String> {
     Ok(("", signpost_message))
 }
 
-// Align the message to the left and pad using zeros instead of spaces
-fn format_alignment_left(
-    format_message: String,
-    format_width: usize,
-    format_precision: usize,
-    type_data: &str,
-    plus_minus: bool,
-    hashtag: bool,
-) -> String {
-    let mut message = format_message;
-    let mut precision_value = format_precision;
-    let mut plus_option = String::new();
-
-    let mut adjust_width = 0;
-    if plus_minus {
-        plus_option = String::from("+");
-        adjust_width = 1;
-    }
+#[cfg(test)]
+mod tests {
+    use crate::decoders::decoder::{BinaryRendering, DecoderRegistry, FormatOptions};
+    use crate::chunks::firehose::firehose_log::FirehoseItemInfo;
+    use crate::message_value::MessageValue;
+    use crate::message::{
+        format_firehose_log_message, parse_formatter, parse_signpost_format, parse_type_formatter,
+    };
+    use regex::Regex;
 
-    if FLOAT_TYPES.contains(&type_data) {
-        let float_message = parse_float(message);
-        if precision_value == 0 {
-            let message_float = float_message.to_string();
-            let float_precision: Vec<&str> = message_float.split('.').collect();
-            if float_precision.len() == 2 {
-                precision_value = float_precision[1].len();
-            }
-        }
-        message = format!(
-            "{plus_symbol}{:0<width$.precision$}",
-            float_message,
-            width = format_width - adjust_width,
-            precision = precision_value,
-            plus_symbol = plus_option
-        );
-    } else if INT_TYPES.contains(&type_data) {
-        let int_message = parse_int(message);
-        message = format!(
-            "{plus_symbol}{:0<width$.precision$}",
-            int_message,
-            width = format_width - adjust_width,
-            precision = precision_value,
-            plus_symbol = plus_option
-        );
-    } else if STRING_TYPES.contains(&type_data) {
-        if precision_value == 0 {
-            precision_value = message.len()
-        }
-        message = format!(
-            "{plus_symbol}{:0<width$.precision$}",
-            message,
-            width = format_width - adjust_width,
-            precision = precision_value,
-            plus_symbol = plus_option
-        );
-    } else if HEX_TYPES.contains(&type_data) {
-        let hex_message = parse_int(message);
-        if hashtag {
-            message = format!(
-                "{plus_symbol}{:0<#width$.precision$X}",
-                hex_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        } else {
-            message = format!(
-                "{plus_symbol}{:0<width$.precision$X}",
-                hex_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        }
-    } else if OCTAL_TYPES.contains(&type_data) {
-        let octal_message = parse_int(message);
-        if hashtag {
-            message = format!(
-                "{plus_symbol}{:0<#width$.precision$o}",
-                octal_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        } else {
-            message = format!(
-                "{plus_symbol}{:0<width$.precision$o}",
-                octal_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        }
+    // Finds the printf formatters in a message, like the regex in build_log
+    fn formatter_re() -> Regex {
+        Regex::new(r"(%(?:(?:\{[^}]+}?)(?:[-+0#]{0,5})(?:\d+|\*)?(?:\.(?:\d+|\*))?(?:h|hh|l|ll|w|I|z|t|q|I32|I64)?[cmCdiouxXeEfgGaAnpsSZP@%}]|(?:[-+0 #]{0,5})(?:\d+|\*)?(?:\.(?:\d+|\*))?(?:h|hh|l||q|t|ll|w|I|z|I32|I64)?[cmCdiouxXeEfgGaAnpsSZP@%]))").unwrap()
     }
-    message
-}
 
-// Align the message to the right and pad using zeros instead of spaces
-fn format_alignment_right(
-    format_message: String,
-    format_width: usize,
-    format_precision: usize,
-    type_data: &str,
-    plus_minus: bool,
-    hashtag: bool,
-) -> String {
-    let mut message = format_message;
-    let mut precision_value = format_precision;
-    let mut plus_option = String::new();
-
-    let mut adjust_width = 0;
-    if plus_minus {
-        plus_option = String::from("+");
-        adjust_width = 1;
+    #[test]
+    fn test_format_firehose_log_message() {
+        let test_data = String::from("opendirectoryd (build %{public}s) launched...");
+        let mut item_message: Vec<FirehoseItemInfo> = Vec::new();
+        item_message.push(FirehoseItemInfo {
+            message_strings: String::from("796.100"),
+            item_type: 34,
+            item_size: 0,
+            message_value: MessageValue::String(String::from("796.100")),
+        });
+        let message_re = formatter_re();
+
+        let log_string = format_firehose_log_message(
+            test_data,
+            &mut item_message,
+            &message_re,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
+        assert_eq!(log_string, "opendirectoryd (build 796.100) launched...")
     }
 
-    if FLOAT_TYPES.contains(&type_data) {
-        let float_message = parse_float(message);
-        if precision_value == 0 {
-            let message_float = float_message.to_string();
-            let float_precision: Vec<&str> = message_float.split('.').collect();
-            if float_precision.len() == 2 {
-                precision_value = float_precision[1].len();
-            }
-        }
-        message = format!(
-            "{plus_symbol}{:0>width$.precision$}",
-            float_message,
-            width = format_width - adjust_width,
-            precision = precision_value,
-            plus_symbol = plus_option
-        );
-    } else if INT_TYPES.contains(&type_data) {
-        let int_message = parse_int(message);
-        message = format!(
-            "{plus_symbol}{:0>width$.precision$}",
-            int_message,
-            width = format_width - adjust_width,
-            precision = precision_value,
-            plus_symbol = plus_option
+    #[test]
+    fn test_format_firehose_log_message_errno() {
+        let message_re = formatter_re();
+        let mut item_message = vec![FirehoseItemInfo {
+            message_strings: String::from("2"),
+            item_type: 0,
+            item_size: 4,
+            message_value: MessageValue::Signed(2),
+        }];
+
+        let log_string = format_firehose_log_message(
+            String::from("Failed to open file, error: %m"),
+            &mut item_message,
+            &message_re,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
         );
-    } else if STRING_TYPES.contains(&type_data) {
-        if precision_value == 0 {
-            precision_value = message.len()
-        }
-        message = format!(
-            "{plus_symbol}{:0>width$.precision$}",
-            message,
-            width = format_width - adjust_width,
-            precision = precision_value,
-            plus_symbol = plus_option
+        assert_eq!(
+            log_string,
+            "Failed to open file, error: No such file or directory"
         );
-    } else if HEX_TYPES.contains(&type_data) {
-        let hex_message = parse_int(message);
-        if hashtag {
-            message = format!(
-                "{plus_symbol}{:0>#width$.precision$X}",
-                hex_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        } else {
-            message = format!(
-                "{plus_symbol}{:0>width$.precision$X}",
-                hex_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        }
-    } else if OCTAL_TYPES.contains(&type_data) {
-        let octal_message = parse_int(message);
-        if hashtag {
-            message = format!(
-                "{plus_symbol}{:0>#width$.precision$o}",
-                octal_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        } else {
-            message = format!(
-                "{plus_symbol}{:0>width$.precision$o}",
-                octal_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        }
-    }
-    message
-}
-
-// Align the message to the left and pad using spaces
-fn format_alignment_left_space(
-    format_message: String,
-    format_width: usize,
-    format_precision: usize,
-    type_data: &str,
-    plus_minus: bool,
-    hashtag: bool,
-) -> String {
-    let mut message = format_message;
-    let mut precision_value = format_precision;
-    let mut plus_option = String::new();
-
-    let mut adjust_width = 0;
-    if plus_minus {
-        plus_option = String::from("+");
-        adjust_width = 1;
-    }
 
-    if FLOAT_TYPES.contains(&type_data) {
-        let float_message = parse_float(message);
-        if precision_value == 0 {
-            let message_float = float_message.to_string();
-            let float_precision: Vec<&str> = message_float.split('.').collect();
-            if float_precision.len() == 2 {
-                precision_value = float_precision[1].len();
-            }
-        }
-        message = format!(
-            "{plus_symbol}{:<width$.precision$}",
-            float_message,
-            width = format_width - adjust_width,
-            precision = precision_value,
-            plus_symbol = plus_option
+        // The errno item is consumed by %m, the next formatter gets the next item
+        let mut item_message = vec![
+            FirehoseItemInfo {
+                message_strings: String::from("/var/folders"),
+                item_type: 34,
+                item_size: 13,
+                message_value: MessageValue::String(String::from("/var/folders")),
+            },
+            FirehoseItemInfo {
+                message_strings: String::from("1"),
+                item_type: 0,
+                item_size: 4,
+                message_value: MessageValue::Signed(1),
+            },
+            FirehoseItemInfo {
+                message_strings: String::from("3"),
+                item_type: 0,
+                item_size: 4,
+                message_value: MessageValue::Signed(3),
+            },
+        ];
+        let log_string = format_firehose_log_message(
+            String::from("open on %{public}s: %m, retry %d"),
+            &mut item_message,
+            &message_re,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
         );
-    } else if INT_TYPES.contains(&type_data) {
-        let int_message = parse_int(message);
-        message = format!(
-            "{plus_symbol}{:<width$.precision$}",
-            int_message,
-            width = format_width - adjust_width,
-            precision = precision_value,
-            plus_symbol = plus_option
+        assert_eq!(
+            log_string,
+            "open on /var/folders: Operation not permitted, retry 3"
         );
-    } else if STRING_TYPES.contains(&type_data) {
-        if precision_value == 0 {
-            precision_value = message.len()
-        }
-        message = format!(
-            "{plus_symbol}{:<width$.precision$}",
-            message,
-            width = format_width - adjust_width,
-            precision = precision_value,
-            plus_symbol = plus_option
-        );
-    } else if HEX_TYPES.contains(&type_data) {
-        let hex_message = parse_int(message);
-        if hashtag {
-            message = format!(
-                "{plus_symbol}{:<#width$.precision$X}",
-                hex_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        } else {
-            message = format!(
-                "{plus_symbol}{:<width$.precision$X}",
-                hex_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        }
-    } else if OCTAL_TYPES.contains(&type_data) {
-        let octal_message = parse_int(message);
-        if hashtag {
-            message = format!(
-                "{plus_symbol}{:<#width$.precision$o}",
-                octal_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        } else {
-            message = format!(
-                "{plus_symbol}{:<width$.precision$o}",
-                octal_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        }
     }
-    message
-}
 
-// Align the message to the right and pad using spaces
-fn format_alignment_right_space(
-    format_message: String,
-    format_width: usize,
-    format_precision: usize,
-    type_data: &str,
-    plus_minus: bool,
-    hashtag: bool,
-) -> String {
-    let mut message = format_message;
-    let mut precision_value = format_precision;
-    let mut plus_option = String::new();
-
-    let mut adjust_width = 0;
-    if plus_minus {
-        plus_option = String::from("+");
-        adjust_width = 1;
+    #[test]
+    fn test_format_firehose_log_message_plist() {
+        let message_re = formatter_re();
+        let mut plist_data = plist::Dictionary::new();
+        plist_data.insert(String::from("level"), plist::Value::from(-1));
+        let mut data = Vec::new();
+        plist::Value::Dictionary(plist_data)
+            .to_writer_binary(&mut data)
+            .unwrap();
+
+        let mut item_message = vec![FirehoseItemInfo {
+            message_strings: crate::util::encode_standard(&data),
+            item_type: 0x32,
+            item_size: data.len() as u16,
+            message_value: MessageValue::Bytes(data.clone()),
+        }];
+        let log_string = format_firehose_log_message(
+            String::from("Battery state: %@"),
+            &mut item_message,
+            &message_re,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
+        assert_eq!(log_string, "Battery state: {\"level\":-1}");
     }
 
-    if FLOAT_TYPES.contains(&type_data) {
-        let float_message = parse_float(message);
-        if precision_value == 0 {
-            let message_float = float_message.to_string();
-            let float_precision: Vec<&str> = message_float.split('.').collect();
-            if float_precision.len() == 2 {
-                precision_value = float_precision[1].len();
-            }
-        }
-        message = format!(
-            "{plus_symbol}{:>width$.precision$}",
-            float_message,
-            width = format_width - adjust_width,
-            precision = precision_value,
-            plus_symbol = plus_option
+    #[test]
+    fn test_format_firehose_log_message_private() {
+        let message_re = formatter_re();
+        let item_message = vec![
+            FirehoseItemInfo {
+                message_strings: String::from("admin"),
+                item_type: 0x21,
+                item_size: 6,
+                message_value: MessageValue::String(String::from("admin")),
+            },
+            FirehoseItemInfo {
+                message_strings: String::from("<private>"),
+                item_type: 0x21,
+                item_size: 0,
+                message_value: MessageValue::Private,
+            },
+            FirehoseItemInfo {
+                message_strings: String::from("uwXwI1tMkCg3RDH4FxW3vA=="),
+                item_type: 0xf2,
+                item_size: 16,
+                message_value: MessageValue::Bytes(
+                    crate::util::decode_standard("uwXwI1tMkCg3RDH4FxW3vA==").unwrap(),
+                ),
+            },
+        ];
+        let format_string = "user: %{private}s home: %s email: %{private, mask.hash}s";
+
+        let mut format_options = FormatOptions::default();
+        let mut items = item_message.clone();
+        let log_string = format_firehose_log_message(
+            String::from(format_string),
+            &mut items,
+            &message_re,
+            &DecoderRegistry::new(),
+            &format_options,
         );
-    } else if INT_TYPES.contains(&type_data) {
-        let int_message = parse_int(message);
-        message = format!(
-            "{plus_symbol}{:>width$.precision$}",
-            int_message,
-            width = format_width - adjust_width,
-            precision = precision_value,
-            plus_symbol = plus_option
+        assert_eq!(
+            log_string,
+            "user: <private> home: <private> email: <mask.hash: 'uwXwI1tMkCg3RDH4FxW3vA=='>"
         );
-    } else if STRING_TYPES.contains(&type_data) {
-        if precision_value == 0 {
-            precision_value = message.len()
-        }
-        message = format!(
-            "{plus_symbol}{:>width$.precision$}",
-            message,
-            width = format_width - adjust_width,
-            precision = precision_value,
-            plus_symbol = plus_option
+        assert_eq!(items[0].message_value, MessageValue::Private);
+        assert_eq!(
+            items[2].message_value,
+            MessageValue::Hashed(String::from("uwXwI1tMkCg3RDH4FxW3vA=="))
         );
-    } else if HEX_TYPES.contains(&type_data) {
-        let hex_message = parse_int(message);
-        if hashtag {
-            message = format!(
-                "{plus_symbol}{:>#width$.precision$X}",
-                hex_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        } else {
-            message = format!(
-                "{plus_symbol}{:>width$.precision$X}",
-                hex_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        }
-    } else if OCTAL_TYPES.contains(&type_data) {
-        let octal_message = parse_int(message);
-        if hashtag {
-            message = format!(
-                "{plus_symbol}{:>#width$.precision$o}",
-                octal_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        } else {
-            message = format!(
-                "{plus_symbol}{:>width$.precision$o}",
-                octal_message,
-                width = format_width - adjust_width,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        }
-    }
-    message
-}
-
-// Align the message to the left
-fn format_left(
-    format_message: String,
-    format_precision: usize,
-    type_data: &str,
-    plus_minus: bool,
-    hashtag: bool,
-) -> String {
-    let mut message = format_message;
-    let mut precision_value = format_precision;
-    let mut plus_option = String::new();
-
-    if plus_minus {
-        plus_option = String::from("+");
-    }
-
-    if FLOAT_TYPES.contains(&type_data) {
-        let float_message = parse_float(message);
-        if precision_value == 0 {
-            let message_float = float_message.to_string();
-            let float_precision: Vec<&str> = message_float.split('.').collect();
-            if float_precision.len() == 2 {
-                precision_value = float_precision[1].len();
-            }
-        }
 
-        message = format!(
-            "{plus_symbol}{:<.precision$}",
-            float_message,
-            precision = precision_value,
-            plus_symbol = plus_option
+        // Values missing from the private data are still redacted
+        format_options.reveal_private = true;
+        let mut items = item_message.clone();
+        let log_string = format_firehose_log_message(
+            String::from(format_string),
+            &mut items,
+            &message_re,
+            &DecoderRegistry::new(),
+            &format_options,
         );
-    } else if INT_TYPES.contains(&type_data) {
-        let int_message = parse_int(message);
-        message = format!(
-            "{plus_symbol}{:<.precision$}",
-            int_message,
-            precision = precision_value,
-            plus_symbol = plus_option
+        assert_eq!(
+            log_string,
+            "user: admin home: <private> email: <mask.hash: 'uwXwI1tMkCg3RDH4FxW3vA=='>"
         );
-    } else if STRING_TYPES.contains(&type_data) {
-        if precision_value == 0 {
-            precision_value = message.len()
-        }
-        message = format!(
-            "{plus_symbol}{:<.precision$}",
-            message,
-            precision = precision_value,
-            plus_symbol = plus_option
+        assert_eq!(
+            items[0].message_value,
+            MessageValue::String(String::from("admin"))
         );
-    } else if HEX_TYPES.contains(&type_data) {
-        let hex_message = parse_int(message);
-        if hashtag {
-            message = format!(
-                "{plus_symbol}{:<#.precision$X}",
-                hex_message,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        } else {
-            message = format!(
-                "{plus_symbol}{:<.precision$X}",
-                hex_message,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        }
-    } else if OCTAL_TYPES.contains(&type_data) {
-        let octal_message = parse_int(message);
-        if hashtag {
-            message = format!(
-                "{plus_symbol}{:<#.precision$o}",
-                octal_message,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        } else {
-            message = format!(
-                "{plus_symbol}{:<.precision$o}",
-                octal_message,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        }
-    }
-    message
-}
-
-// Align the message to the right (default)
-fn format_right(
-    format_message: String,
-    format_precision: usize,
-    type_data: &str,
-    plus_minus: bool,
-    hashtag: bool,
-) -> String {
-    let mut message = format_message;
-    let mut precision_value = format_precision;
-    let mut plus_option = String::new();
-
-    if plus_minus {
-        plus_option = String::from("+");
+        assert_eq!(items[1].message_value, MessageValue::Private);
     }
 
-    if FLOAT_TYPES.contains(&type_data) {
-        let float_message = parse_float(message);
-        if precision_value == 0 {
-            let message_float = float_message.to_string();
-            let float_precision: Vec<&str> = message_float.split('.').collect();
-            if float_precision.len() == 2 {
-                precision_value = float_precision[1].len();
+    #[test]
+    fn test_format_firehose_log_message_binary() {
+        let message_re = formatter_re();
+        let mut item_message = vec![
+            FirehoseItemInfo {
+                message_strings: String::new(),
+                item_type: 0x12,
+                item_size: 4,
+                message_value: MessageValue::Unsigned(4),
+            },
+            FirehoseItemInfo {
+                message_strings: String::from("3q2+7w=="),
+                item_type: 0x32,
+                item_size: 4,
+                message_value: MessageValue::Bytes(vec![0xde, 0xad, 0xbe, 0xef]),
+            },
+        ];
+
+        let mut format_options = FormatOptions::default();
+        let test_formats = [
+            (BinaryRendering::Base64, "3q2+7w=="),
+            (BinaryRendering::Hex, "deadbeef"),
+            (
+                BinaryRendering::Hexdump,
+                "00000000: de ad be ef                                     ....",
+            ),
+            (BinaryRendering::TypedOnly, "<4 bytes>"),
+        ];
+        for (rendering, expected) in test_formats {
+            format_options.binary_rendering = rendering;
+            for format_string in ["%.*P", "%{public}.*P", "%{public, acme:packet}.*P"] {
+                let log_string = format_firehose_log_message(
+                    format!("Packet: {}", format_string),
+                    &mut item_message,
+                    &message_re,
+                    &DecoderRegistry::new(),
+                    &format_options,
+                );
+                assert_eq!(log_string, format!("Packet: {}", expected));
             }
         }
+    }
 
-        message = format!(
-            "{plus_symbol}{:>.precision$}",
-            float_message,
-            precision = precision_value,
-            plus_symbol = plus_option
-        );
-    } else if INT_TYPES.contains(&type_data) {
-        let int_message = parse_int(message);
-        message = format!(
-            "{plus_symbol}{:>.precision$}",
-            int_message,
-            precision = precision_value,
-            plus_symbol = plus_option
-        );
-    } else if STRING_TYPES.contains(&type_data) {
-        if precision_value == 0 {
-            precision_value = message.len()
-        }
-        message = format!(
-            "{plus_symbol}{:>.precision$}",
-            message,
-            precision = precision_value,
-            plus_symbol = plus_option
-        );
-    } else if HEX_TYPES.contains(&type_data) {
-        let hex_message = parse_int(message);
-        if hashtag {
-            message = format!(
-                "{plus_symbol}{:>#.precision$X}",
-                hex_message,
-                precision = precision_value,
-                plus_symbol = plus_option
-            );
-        } else {
-            message = format!(
-                "{plus_symbol}{:>.precision$X}",
-                hex_message,
-                precision = precision_value,
-                plus_symbol = plus_option
+    #[test]
+    fn test_format_firehose_log_message_binary_decoder() {
+        let message_re = formatter_re();
+        let mut item_message = vec![
+            FirehoseItemInfo {
+                message_strings: String::new(),
+                item_type: 0x12,
+                item_size: 4,
+                message_value: MessageValue::Unsigned(4),
+            },
+            FirehoseItemInfo {
+                message_strings: String::from("3q2+7w=="),
+                item_type: 0x32,
+                item_size: 4,
+                message_value: MessageValue::Bytes(vec![0xde, 0xad, 0xbe, 0xef]),
+            },
+        ];
+
+        let format_options = FormatOptions {
+            binary_decoder: Some(Box::new(|annotation: &str, data: &[u8]| -> Option<String> {
+                Some(format!("[{}] {} bytes", annotation, data.len()))
+            })),
+            ..Default::default()
+        };
+        let test_formats = [
+            ("%.*P", "[] 4 bytes"),
+            ("%{public}.*P", "[] 4 bytes"),
+            ("%{public, acme:packet}.*P", "[acme:packet] 4 bytes"),
+        ];
+        for (format_string, expected) in test_formats {
+            let log_string = format_firehose_log_message(
+                format!("Packet: {}", format_string),
+                &mut item_message,
+                &message_re,
+                &DecoderRegistry::new(),
+                &format_options,
             );
+            assert_eq!(log_string, format!("Packet: {}", expected));
         }
-    } else if OCTAL_TYPES.contains(&type_data) {
-        let octal_message = parse_int(message);
-        message = format!(
-            "{plus_symbol}{:>#.precision$o}",
-            octal_message,
-            precision = precision_value,
-            plus_symbol = plus_option
-        );
     }
-    message
-}
-
-// Parse the float string log message to float value
-fn parse_float(message: String) -> f64 {
-    let byte_results = message.parse::<i64>();
-    match byte_results {
-        Ok(bytes) => return f64::from_bits(bytes as u64),
-        Err(err) => error!(
-            "[macos-unifiedlogs] Failed to parse float log message value: {}, err: {:?}",
-            message, err
-        ),
-    }
-    f64::from_bits(0)
-}
-
-// Parse the int string log message to int value
-fn parse_int(message: String) -> i64 {
-    let int_results = message.parse::<i64>();
-    match int_results {
-        Ok(message) => return message,
-        Err(err) => error!(
-            "[macos-unifiedlogs] Failed to parse int log message value: {}, err: {:?}",
-            message, err
-        ),
-    }
-    0
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::chunks::firehose::firehose_log::FirehoseItemInfo;
-    use crate::message::{
-        format_alignment_left, format_alignment_left_space, format_alignment_right,
-        format_alignment_right_space, format_firehose_log_message, format_left, format_right,
-        parse_float, parse_formatter, parse_int, parse_signpost_format, parse_type_formatter,
-    };
-    use regex::Regex;
 
     #[test]
-    fn test_format_firehose_log_message() {
-        let test_data = String::from("opendirectoryd (build %{public}s) launched...");
-        let mut item_message: Vec<FirehoseItemInfo> = Vec::new();
-        item_message.push(FirehoseItemInfo {
-            message_strings: String::from("796.100"),
-            item_type: 34,
-            item_size: 0,
-        });
-        let message_re = Regex::new(r"(%(?:(?:\{[^}]+}?)(?:[-+0#]{0,5})(?:\d+|\*)?(?:\.(?:\d+|\*))?(?:h|hh|l|ll|w|I|z|t|q|I32|I64)?[cmCdiouxXeEfgGaAnpsSZP@%}]|(?:[-+0 #]{0,5})(?:\d+|\*)?(?:\.(?:\d+|\*))?(?:h|hh|l||q|t|ll|w|I|z|I32|I64)?[cmCdiouxXeEfgGaAnpsSZP@%]))").unwrap();
-
-        let log_string = format_firehose_log_message(test_data, &item_message, &message_re);
-        assert_eq!(log_string, "opendirectoryd (build 796.100) launched...")
+    fn test_format_firehose_log_message_values() {
+        let message_re = formatter_re();
+        let mut item_message = vec![
+            FirehoseItemInfo {
+                message_strings: String::from("-1"),
+                item_type: 0,
+                item_size: 4,
+                message_value: MessageValue::Signed(-1),
+            },
+            FirehoseItemInfo {
+                message_strings: String::from("-1"),
+                item_type: 0,
+                item_size: 8,
+                message_value: MessageValue::Signed(-1),
+            },
+            FirehoseItemInfo {
+                message_strings: String::from("4602678819172646912"),
+                item_type: 0,
+                item_size: 8,
+                message_value: MessageValue::Signed(4602678819172646912),
+            },
+            FirehoseItemInfo {
+                message_strings: String::from("<private>"),
+                item_type: 0x21,
+                item_size: 0,
+                message_value: MessageValue::Private,
+            },
+        ];
+
+        let log_string = format_firehose_log_message(
+            String::from("Charge: %u%% capacity: %{public}llu level: %f user: %{private}s %d"),
+            &mut item_message,
+            &message_re,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
+        assert_eq!(
+            log_string,
+            "Charge: 4294967295% capacity: 18446744073709551615 level: 0.500000 user: <private> <Missing message data>"
+        );
+        let values: Vec<MessageValue> = item_message
+            .into_iter()
+            .map(|item| item.message_value)
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                MessageValue::Unsigned(4294967295),
+                MessageValue::Unsigned(u64::MAX),
+                MessageValue::Float(0.5),
+                MessageValue::Private,
+            ]
+        );
     }
 
     #[test]
@@ -1103,164 +815,174 @@ mod tests {
             message_strings: String::from("2"),
             item_type: 2,
             item_size: 2,
+            message_value: MessageValue::Signed(2),
         };
         test_message.push(test_data);
 
         let item_index = 0;
-        let (_, formatted_results) = parse_formatter(
+        let (_, (formatted_results, _)) = parse_formatter(
             test_format,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "+002");
 
         let test_format = "%04d";
-        let (_, formatted_results) = parse_formatter(
+        let (_, (formatted_results, _)) = parse_formatter(
             test_format,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "0002");
 
         let test_format = "%#4x";
-        let (_, formatted_results) = parse_formatter(
+        let (_, (formatted_results, _)) = parse_formatter(
             test_format,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, " 0x2");
 
         let test_format = "%#04o";
         test_message[0].message_strings = String::from("100");
-        let (_, formatted_results) = parse_formatter(
+        test_message[0].message_value = MessageValue::Signed(100);
+        let (_, (formatted_results, _)) = parse_formatter(
             test_format,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
-        assert_eq!(formatted_results, "0o144");
+        assert_eq!(formatted_results, "0144");
 
         let test_format = "%07o";
-        let (_, formatted_results) = parse_formatter(
+        let (_, (formatted_results, _)) = parse_formatter(
             test_format,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "0000144");
 
         let test_format = "%x";
         test_message[0].message_strings = String::from("10");
-        let (_, formatted_results) = parse_formatter(
+        test_message[0].message_value = MessageValue::Signed(10);
+        let (_, (formatted_results, _)) = parse_formatter(
             test_format,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
-        assert_eq!(formatted_results, "A");
+        assert_eq!(formatted_results, "a");
 
         let test_float = "%+09.4f";
         test_message[0].message_strings = String::from("4570111009880014848");
-        let (_, formatted_results) = parse_formatter(
+        test_message[0].message_value = MessageValue::Signed(4570111009880014848);
+        let (_, (formatted_results, _)) = parse_formatter(
             test_float,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "+000.0035");
 
         let test_float = "%9.4f";
-        let (_, formatted_results) = parse_formatter(
+        let (_, (formatted_results, _)) = parse_formatter(
             test_float,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "   0.0035");
 
         let test_float = "%-8.4f";
-        let (_, formatted_results) = parse_formatter(
+        let (_, (formatted_results, _)) = parse_formatter(
             test_float,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "0.0035  ");
 
         let test_float = "%f";
         test_message[0].message_strings = String::from("4614286721111404799");
-        let (_, formatted_results) = parse_formatter(
+        test_message[0].message_value = MessageValue::Signed(4614286721111404799);
+        let (_, (formatted_results, _)) = parse_formatter(
             test_float,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "3.154944");
 
         let test_int = "%d";
         test_message[0].message_strings = String::from("-248");
-        let (_, formatted_results) = parse_formatter(
+        test_message[0].message_value = MessageValue::Signed(-248);
+        let (_, (formatted_results, _)) = parse_formatter(
             test_int,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "-248");
 
         let test_float = "%f";
         test_message[0].message_strings = String::from("-4611686018427387904");
-        let (_, formatted_results) = parse_formatter(
+        test_message[0].message_value = MessageValue::Signed(-4611686018427387904);
+        let (_, (formatted_results, _)) = parse_formatter(
             test_float,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
-        assert_eq!(formatted_results, "-2");
+        assert_eq!(formatted_results, "-2.000000");
 
         let test_float = "%f";
         test_message[0].message_strings = String::from("-4484628366119329180");
-        let (_, formatted_results) = parse_formatter(
+        test_message[0].message_value = MessageValue::Signed(-4484628366119329180);
+        let (_, (formatted_results, _)) = parse_formatter(
             test_float,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "-650937839.633862");
 
         let test_string = "%s";
         test_message[0].message_strings = String::from("The big red dog jumped over the crab");
-        let (_, formatted_results) = parse_formatter(
+        test_message[0].message_value = MessageValue::String(String::from("The big red dog jumped over the crab"));
+        let (_, (formatted_results, _)) = parse_formatter(
             test_string,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "The big red dog jumped over the crab");
 
         let test_string = "%.2@";
         test_message[0].message_strings = String::from("aaabbbb");
-        let (_, formatted_results) = parse_formatter(
+        test_message[0].message_value = MessageValue::String(String::from("aaabbbb"));
+        let (_, (formatted_results, _)) = parse_formatter(
             test_string,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "aa");
@@ -1272,18 +994,152 @@ mod tests {
             message_strings: String::from("hi"),
             item_type: 2,
             item_size: 2,
+            message_value: MessageValue::String(String::from("hi")),
         };
         test_message.push(test_data2);
-        let (_, formatted_results) = parse_formatter(
+        let (_, (formatted_results, _)) = parse_formatter(
             test_string,
             &test_message,
-            &test_message[0].item_type,
             item_index,
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "        hi");
     }
 
+    #[test]
+    fn test_parse_formatter_dynamic_items() {
+        let number = |value: i64| FirehoseItemInfo {
+            message_strings: value.to_string(),
+            item_type: 0,
+            item_size: 4,
+            message_value: MessageValue::Signed(value),
+        };
+        let test_formats = [
+            ("%*d", vec![number(5), number(42)], "   42"),
+            ("%*d", vec![number(-5), number(42)], "42   "),
+            ("%.*d", vec![number(4), number(42)], "0042"),
+            (
+                "%*.*s",
+                vec![
+                    number(6),
+                    number(2),
+                    FirehoseItemInfo {
+                        message_strings: String::from("hello"),
+                        item_type: 0x22,
+                        item_size: 6,
+                        message_value: MessageValue::String(String::from("hello")),
+                    },
+                ],
+                "    he",
+            ),
+            ("%*d", vec![number(5)], "<Missing message data>"),
+        ];
+        for (test_format, test_message, expected) in test_formats {
+            let (_, (formatted_results, _)) =
+                parse_formatter(
+                test_format,
+                &test_message,
+                0,
+                &FormatOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(formatted_results, expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_formatter_field_limits() {
+        let number = |value: i64| FirehoseItemInfo {
+            message_strings: value.to_string(),
+            item_type: 0,
+            item_size: 8,
+            message_value: MessageValue::Signed(value),
+        };
+        let float = number(i64::from_le_bytes(1.5f64.to_bits().to_le_bytes()));
+
+        // Width and precision are limited to 65535
+        let (_, (formatted_results, _)) = parse_formatter(
+            "%.*f",
+            &[number(70000), float.to_owned()],
+            0,
+            &FormatOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(formatted_results, format!("1.5{}", "0".repeat(65534)));
+
+        let (_, (formatted_results, _)) =
+            parse_formatter("%.70000f", &[float], 0, &FormatOptions::default()).unwrap();
+        assert_eq!(formatted_results, format!("1.5{}", "0".repeat(65534)));
+
+        // The width is truncated to a C int. Ex: i64::MIN is a width of 0
+        let (_, (formatted_results, _)) = parse_formatter(
+            "%*d",
+            &[number(i64::MIN), number(42)],
+            0,
+            &FormatOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(formatted_results, "42");
+    }
+
+    #[test]
+    fn test_format_firehose_log_message_dynamic_items() {
+        let message_re = formatter_re();
+        let number = |value: i64| FirehoseItemInfo {
+            message_strings: value.to_string(),
+            item_type: 0,
+            item_size: 4,
+            message_value: MessageValue::Signed(value),
+        };
+        let item_message = vec![
+            number(6),
+            number(2),
+            FirehoseItemInfo {
+                message_strings: String::from("hello"),
+                item_type: 0x22,
+                item_size: 6,
+                message_value: MessageValue::String(String::from("hello")),
+            },
+            number(7),
+        ];
+
+        // Both "*" items are consumed before the next formatter
+        let log_string = format_firehose_log_message(
+            String::from("%*.*s|%d|%d"),
+            &mut item_message.clone(),
+            &message_re,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
+        assert_eq!(log_string, "    he|7|<Missing message data>");
+
+        // The dynamic items are the last items of the entry
+        let log_string = format_firehose_log_message(
+            String::from("%*.*s"),
+            &mut item_message[..2].to_vec(),
+            &message_re,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
+        assert_eq!(log_string, "<Missing message data>");
+
+        let mut precision_only = vec![FirehoseItemInfo {
+            message_strings: String::new(),
+            item_type: 0x12,
+            item_size: 4,
+            message_value: MessageValue::Unsigned(4),
+        }];
+        let log_string = format_firehose_log_message(
+            String::from("Packet: %{private}.*P"),
+            &mut precision_only,
+            &message_re,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
+        );
+        assert_eq!(log_string, "Packet: <Missing message data>");
+    }
+
     #[test]
     fn test_parse_type_formatter() {
         let mut test_format = "%{public}s";
@@ -1293,15 +1149,18 @@ mod tests {
             message_strings: String::from("test"),
             item_type: 2,
             item_size: 4,
+            message_value: MessageValue::String(String::from("test")),
         };
         test_message.push(test_data);
 
         let item_index = 0;
-        let (_, formatted_results) = parse_type_formatter(
+        let (_, (formatted_results, _)) = parse_type_formatter(
             test_format,
             &test_message,
             &test_message[0].item_type,
             item_index,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "test");
@@ -1313,15 +1172,18 @@ mod tests {
             message_strings: String::from("1"),
             item_type: 2,
             item_size: 4,
+            message_value: MessageValue::Signed(1),
         };
         test_message.push(test_data);
 
         let item_index = 0;
-        let (_, formatted_results) = parse_type_formatter(
+        let (_, (formatted_results, _)) = parse_type_formatter(
             test_format,
             &test_message,
             &test_message[0].item_type,
             item_index,
+            &DecoderRegistry::new(),
+            &FormatOptions::default(),
         )
         .unwrap();
         assert_eq!(formatted_results, "1 (signpost.description:begin_time)");
@@ -1335,116 +1197,31 @@ mod tests {
     }
 
     #[test]
-    fn test_format_alignment_left() {
-        let test_type = "d";
-        let test_width = 4;
-        let test_precision = 0;
-        let test_format = String::from("2");
-        let plus_minus = false;
-        let hashtag = false;
-        let formatted_results = format_alignment_left(
-            test_format,
-            test_width,
-            test_precision,
-            &test_type,
-            plus_minus,
-            hashtag,
-        );
-        assert_eq!(formatted_results, "2000");
-    }
-
-    #[test]
-    fn test_format_alignment_right() {
-        let test_type = "d";
-        let test_width = 4;
-        let test_precision = 0;
-        let test_format = String::from("2");
-        let plus_minus = false;
-        let hashtag = false;
-        let formatted_results = format_alignment_right(
-            test_format,
-            test_width,
-            test_precision,
-            &test_type,
-            plus_minus,
-            hashtag,
-        );
-        assert_eq!(formatted_results, "0002");
-    }
-
-    #[test]
-    fn test_format_alignment_left_space() {
-        let test_type = "d";
-        let test_width = 4;
-        let test_precision = 0;
-        let test_format = String::from("2");
-        let plus_minus = false;
-        let hashtag = false;
-        let formatted_results = format_alignment_left_space(
-            test_format,
-            test_width,
-            test_precision,
-            &test_type,
-            plus_minus,
-            hashtag,
-        );
-        assert_eq!(formatted_results, "2   ");
-    }
-
-    #[test]
-    fn test_format_alignment_right_space() {
-        let test_type = "d";
-        let test_width = 4;
-        let test_precision = 0;
-        let test_format = String::from("2");
-        let plus_minus = false;
-        let hashtag = false;
-        let formatted_results = format_alignment_right_space(
-            test_format,
-            test_width,
-            test_precision,
-            &test_type,
-            plus_minus,
-            hashtag,
-        );
-        assert_eq!(formatted_results, "   2");
-    }
-
-    #[test]
-    fn test_format_left() {
-        let test_type = "d";
-        let test_precision = 0;
-        let test_format = String::from("2");
-        let plus_minus = false;
-        let hashtag = false;
-        let formatted_results =
-            format_left(test_format, test_precision, &test_type, plus_minus, hashtag);
-        assert_eq!(formatted_results, "2");
-    }
-
-    #[test]
-    fn test_format_right() {
-        let test_type = "d";
-        let test_precision = 0;
-        let test_format = String::from("2");
-        let plus_minus = false;
-        let hashtag = false;
-        let formatted_results =
-            format_right(test_format, test_precision, &test_type, plus_minus, hashtag);
-        assert_eq!(formatted_results, "2");
-    }
-
-    #[test]
-    fn test_parse_float() {
-        let value = String::from("4611911198408756429");
-        let results = parse_float(value);
-        assert_eq!(results, 2.1);
-    }
-
-    #[test]
-    fn test_parse_int() {
-        let value = String::from("2");
-        let results = parse_int(value);
-        assert_eq!(results, 2);
+    fn test_parse_formatter_alignment() {
+        let test_message = vec![FirehoseItemInfo {
+            message_strings: String::from("2"),
+            item_type: 2,
+            item_size: 4,
+            message_value: MessageValue::Signed(2),
+        }];
+
+        let test_formats = [
+            ("%-04d", "2   "),
+            ("%04d", "0002"),
+            ("%-4d", "2   "),
+            ("%4d", "   2"),
+            ("%-d", "2"),
+            ("%d", "2"),
+        ];
+        for (test_format, expected) in test_formats {
+            let (_, (formatted_results, _)) = parse_formatter(
+                test_format,
+                &test_message,
+                0,
+                &FormatOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(formatted_results, expected);
+        }
     }
 }
@@ -0,0 +1,118 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+use crate::util::encode_standard;
+use serde::{Serialize, Serializer};
+
+/// Typed value of a log message argument
+///
+/// Number items are stored as `Signed` when parsed. Formatting the log message uses the message format string to convert them to `Unsigned` (ex: `%u`, `%x`) or `Float` (ex: `%f`)
+#[derive(Debug, Clone, PartialEq, Serialize, Default)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum MessageValue {
+    Signed(i64),
+    Unsigned(u64),
+    Float(f64),
+    String(String),
+    /// Arbitrary data. Serialized as base64
+    #[serde(serialize_with = "serialize_bytes")]
+    Bytes(Vec<u8>),
+    /// Value redacted by the system (`<private>`)
+    Private,
+    /// Hash of a private value (`%{private, mask.hash}`), base64 encoded
+    Hashed(String),
+    /// Object description (`%@`)
+    Object(String),
+    /// Value not found in the log entry
+    #[default]
+    Missing,
+}
+
+impl MessageValue {
+    /// Get the value as a signed integer. Unsigned values larger than `i64::MAX` return None
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            MessageValue::Signed(value) => Some(*value),
+            MessageValue::Unsigned(value) => i64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    /// Get the value as an unsigned integer. Negative values return None
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            MessageValue::Signed(value) => u64::try_from(*value).ok(),
+            MessageValue::Unsigned(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get the value as a float
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            MessageValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get the string of a `String`, `Object` or `Hashed` value
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MessageValue::String(value)
+            | MessageValue::Object(value)
+            | MessageValue::Hashed(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the data of a `Bytes` value
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            MessageValue::Bytes(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+fn serialize_bytes<S: Serializer>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&encode_standard(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MessageValue;
+
+    #[test]
+    fn test_message_value_accessors() {
+        assert_eq!(MessageValue::Signed(-1).as_i64(), Some(-1));
+        assert_eq!(MessageValue::Signed(-1).as_u64(), None);
+        assert_eq!(MessageValue::Unsigned(u64::MAX).as_i64(), None);
+        assert_eq!(MessageValue::Unsigned(4500).as_u64(), Some(4500));
+        assert_eq!(MessageValue::Float(0.5).as_f64(), Some(0.5));
+        assert_eq!(
+            MessageValue::Object(String::from("(null)")).as_str(),
+            Some("(null)")
+        );
+        assert_eq!(
+            MessageValue::Bytes(vec![0, 255]).as_bytes(),
+            Some(&[0, 255][..])
+        );
+        assert_eq!(MessageValue::Private.as_str(), None);
+    }
+
+    #[test]
+    fn test_message_value_serialize() {
+        let result = serde_json::to_string(&MessageValue::Unsigned(4500)).unwrap();
+        assert_eq!(result, "{\"type\":\"unsigned\",\"value\":4500}");
+
+        let result = serde_json::to_string(&MessageValue::Bytes(vec![0, 255])).unwrap();
+        assert_eq!(result, "{\"type\":\"bytes\",\"value\":\"AP8=\"}");
+
+        let result = serde_json::to_string(&MessageValue::Private).unwrap();
+        assert_eq!(result, "{\"type\":\"private\"}");
+    }
+}
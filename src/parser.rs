@@ -10,7 +10,7 @@ use log::{error, info};
 use crate::dsc::SharedCacheStrings;
 use crate::error::ParserError;
 use crate::timesync::TimesyncBoot;
-use crate::unified_log::{LogData, UnifiedLogData};
+use crate::unified_log::{LogData, LogOptions, UnifiedLogData};
 use crate::uuidtext::UUIDText;
 use std::fs;
 use memmap::Mmap;
@@ -59,7 +59,8 @@ pub fn parse_log(full_path: &str, bh_offset: u32, fl_offset: u32) -> Result<Unif
     }
 }
 
-/// Reconstruct Unified Log entries using the strings data, cached strings data, timesync data, and unified log. Provide bool to ignore log entries that are not able to be recontructed (additional tracev3 files needed)
+/// Reconstruct Unified Log entries using the strings data, cached strings data, timesync data, and unified log
+/// `LogOptions` selects the entries to build (`query::LogQuery`), the `%{...}` decoders and whether to exclude log entries that are not able to be recontructed (additional tracev3 files needed)
 /// Return a reconstructed log entries and any leftover Unified Log entries that could not be reconstructed (data may be stored in other tracev3 files)
 // Log entries with Oversize string entries may have the data in a different tracev3 file.
 pub fn build_log(
@@ -67,21 +68,69 @@ pub fn build_log(
     strings_data: &[UUIDText],
     shared_strings: &[SharedCacheStrings],
     timesync_data: &[TimesyncBoot],
-    exclude_missing: bool,
     batteryhealth_offset: u32,
     followup_string_offset: u32,
+    options: &LogOptions,
 ) -> (Vec<LogData>, UnifiedLogData) {
     LogData::build_log(
         unified_data,
         strings_data,
         shared_strings,
         timesync_data,
-        exclude_missing,
         batteryhealth_offset,
         followup_string_offset,
+        options,
     )
 }
 
+/// Parse a tracev3 file and reconstruct its Unified Log entries one chunkset at a time. The entries of each chunkset are passed to `on_entries` as soon as they are built,
+/// so large tracev3 files can be streamed to an exporter without holding the parsed file or every entry in memory
+/// Log entries with Oversize data in a later chunkset are handled like data stored in a different tracev3 file (see `LogOptions.exclude_missing`)
+/// Return the leftover Unified Log entries that could not be reconstructed, including the Oversize entries of the file so they can be built again once all files are parsed
+#[allow(clippy::too_many_arguments)]
+pub fn stream_log<F: FnMut(Vec<LogData>)>(
+    full_path: &str,
+    strings_data: &[UUIDText],
+    shared_strings: &[SharedCacheStrings],
+    timesync_data: &[TimesyncBoot],
+    batteryhealth_offset: u32,
+    followup_string_offset: u32,
+    options: &LogOptions,
+    on_entries: F,
+) -> Result<UnifiedLogData, ParserError> {
+    let buffer = match fs::read(full_path) {
+        Ok(results) => results,
+        Err(err) => {
+            error!(
+                "[macos-unifiedlogs] Failed to read the tracev3 file {}: {:?}",
+                full_path, err
+            );
+            return Err(ParserError::Read);
+        }
+    };
+
+    let log_data_results = LogData::stream_log(
+        &buffer,
+        strings_data,
+        shared_strings,
+        timesync_data,
+        batteryhealth_offset,
+        followup_string_offset,
+        options,
+        on_entries,
+    );
+    match log_data_results {
+        Ok((_, missing)) => Ok(missing),
+        Err(err) => {
+            error!(
+                "[macos-unifiedlogs] Failed to parse the tracev3 file: {:?}",
+                err
+            );
+            Err(ParserError::Tracev3Parse)
+        }
+    }
+}
+
 /// Parse all UUID files in provided directory. The directory should follow the same layout as the live system (ex: path/to/files/<two character UUID>/<remaining UUID name>)
 pub fn collect_strings(path: &str) -> Result<Vec<UUIDText>, ParserError> {
     let paths_results = fs::read_dir(path);
@@ -355,8 +404,9 @@ pub fn collect_timesync(path: &str) -> Result<Vec<TimesyncBoot>, ParserError> {
 mod tests {
     use crate::parser::{
         build_log, collect_shared_strings, collect_shared_strings_system, collect_strings,
-        collect_strings_system, collect_timesync, collect_timesync_system, parse_log,
+        collect_strings_system, collect_timesync, collect_timesync_system, parse_log, stream_log,
     };
+    use crate::unified_log::LogOptions;
 
     use std::path::PathBuf;
 
@@ -477,15 +527,14 @@ mod tests {
         test_path.push("Persist/0000000000000002.tracev3");
         let log_data = parse_log(&test_path.display().to_string(), 0, 0).unwrap();
 
-        let exclude_missing = false;
         let (results, _) = build_log(
             &log_data,
             &string_results,
             &shared_strings_results,
             &timesync_data,
-            exclude_missing,
             0,
             0,
+            &LogOptions::default(),
         );
         assert_eq!(results.len(), 207366);
         assert_eq!(results[10].process, "/usr/libexec/lightsoutmanagementd");
@@ -509,4 +558,68 @@ mod tests {
         assert_eq!(results[10].process_uuid, "6C3ADF991F033C1C96C4ADFAA12D8CED");
         assert_eq!(results[10].raw_message, "%@ LOM isSupported : %s");
     }
+
+    #[test]
+    fn test_stream_log() {
+        let mut test_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_path.push("tests/test_data/system_logs_big_sur.logarchive");
+        let string_results = collect_strings(&test_path.display().to_string()).unwrap();
+
+        test_path.push("dsc");
+        let shared_strings_results =
+            collect_shared_strings(&test_path.display().to_string()).unwrap();
+        test_path.pop();
+
+        test_path.push("timesync");
+        let timesync_data = collect_timesync(&test_path.display().to_string()).unwrap();
+        test_path.pop();
+
+        test_path.push("Persist/0000000000000002.tracev3");
+        let log_data = parse_log(&test_path.display().to_string(), 0, 0).unwrap();
+        let options = LogOptions {
+            exclude_missing: true,
+            ..Default::default()
+        };
+        let (results, _) = build_log(
+            &log_data,
+            &string_results,
+            &shared_strings_results,
+            &timesync_data,
+            0,
+            0,
+            &options,
+        );
+
+        let mut calls = 0;
+        let mut streamed = Vec::new();
+        let missing = stream_log(
+            &test_path.display().to_string(),
+            &string_results,
+            &shared_strings_results,
+            &timesync_data,
+            0,
+            0,
+            &options,
+            |mut entries| {
+                calls += 1;
+                streamed.append(&mut entries);
+            },
+        )
+        .unwrap();
+        assert!(calls > log_data.catalog_data.len());
+        assert_eq!(missing.oversize.len(), log_data.oversize.len());
+
+        // Entries with Oversize data in a later chunkset are returned as missing data and are built once the file is parsed
+        let (mut rebuilt, _) = build_log(
+            &missing,
+            &string_results,
+            &shared_strings_results,
+            &timesync_data,
+            0,
+            0,
+            &options,
+        );
+        streamed.append(&mut rebuilt);
+        assert_eq!(streamed.len(), results.len());
+    }
 }
@@ -0,0 +1,741 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Parse and evaluate `NSPredicate` filters compatible with `log show --predicate`
+//!
+//! Supports the common subset used by `log` filters: `==`, `!=`, `<`, `<=`, `>`, `>=`, `CONTAINS`, `BEGINSWITH`, `ENDSWITH`,
+//! `LIKE`, `MATCHES` and `IN`, the `[c]`, `[d]`, `[cd]` and `[n]` modifiers, `AND`/`OR`/`NOT` (or `&&`/`||`/`!`) and parentheses.
+//! Ex: `subsystem == "com.apple.powerd" AND eventMessage CONTAINS[c] "battery"`
+
+use crate::error::ParserError;
+use crate::unified_log::LogData;
+use crate::util::image_name;
+use log::error;
+use regex::{Regex, RegexBuilder};
+use std::cmp::Ordering;
+use std::str::FromStr;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// A parsed `NSPredicate` that can be evaluated against `LogData` entries
+#[derive(Debug, Clone)]
+pub struct Predicate {
+    expression: Expression,
+}
+
+impl Predicate {
+    /// Parse a predicate string. Ex: `processID == 45 AND messageType == error`
+    pub fn parse(predicate: &str) -> Result<Predicate, ParserError> {
+        let tokens = match tokenize(predicate) {
+            Ok(result) => result,
+            Err(err) => {
+                error!(
+                    "[macos-unifiedlogs] Failed to tokenize predicate {:?}: {}",
+                    predicate, err
+                );
+                return Err(ParserError::Predicate);
+            }
+        };
+
+        let mut parser = PredicateParser {
+            tokens: &tokens,
+            position: 0,
+        };
+        let expression = parser.parse_or().and_then(|expression| {
+            if parser.position < tokens.len() {
+                Err(format!("unexpected {:?}", tokens[parser.position]))
+            } else {
+                Ok(expression)
+            }
+        });
+
+        match expression {
+            Ok(result) => Ok(Predicate { expression: result }),
+            Err(err) => {
+                error!(
+                    "[macos-unifiedlogs] Failed to parse predicate {:?}: {}",
+                    predicate, err
+                );
+                Err(ParserError::Predicate)
+            }
+        }
+    }
+
+    /// Check if a log entry matches the predicate
+    pub fn matches(&self, log_data: &LogData) -> bool {
+        self.expression.evaluate(log_data)
+    }
+}
+
+impl FromStr for Predicate {
+    type Err = ParserError;
+
+    fn from_str(predicate: &str) -> Result<Self, Self::Err> {
+        Predicate::parse(predicate)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Constant(bool),
+    Comparison(Comparison),
+}
+
+impl Expression {
+    fn evaluate(&self, log_data: &LogData) -> bool {
+        match self {
+            Expression::And(left, right) => left.evaluate(log_data) && right.evaluate(log_data),
+            Expression::Or(left, right) => left.evaluate(log_data) || right.evaluate(log_data),
+            Expression::Not(expression) => !expression.evaluate(log_data),
+            Expression::Constant(value) => *value,
+            Expression::Comparison(comparison) => comparison.evaluate(log_data),
+        }
+    }
+}
+
+/// `LogData` fields that can be used in a predicate, named like the `log` command keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    EventMessage,
+    ComposedMessage,
+    FormatString,
+    ProcessImagePath,
+    Process,
+    SenderImagePath,
+    Sender,
+    ProcessImageUuid,
+    SenderImageUuid,
+    Subsystem,
+    Category,
+    ProcessId,
+    ThreadId,
+    UserId,
+    ActivityId,
+    MessageType,
+    EventType,
+}
+
+impl Key {
+    fn from_name(name: &str) -> Option<Key> {
+        let key = match name {
+            "eventMessage" => Key::EventMessage,
+            "composedMessage" => Key::ComposedMessage,
+            "formatString" => Key::FormatString,
+            "processImagePath" => Key::ProcessImagePath,
+            "process" => Key::Process,
+            "senderImagePath" => Key::SenderImagePath,
+            "sender" => Key::Sender,
+            "processImageUUID" => Key::ProcessImageUuid,
+            "senderImageUUID" => Key::SenderImageUuid,
+            "subsystem" => Key::Subsystem,
+            "category" => Key::Category,
+            "processID" | "processIdentifier" | "pid" => Key::ProcessId,
+            "threadID" | "threadIdentifier" | "tid" => Key::ThreadId,
+            "userID" | "euid" => Key::UserId,
+            "activityIdentifier" => Key::ActivityId,
+            "messageType" | "logType" => Key::MessageType,
+            "eventType" => Key::EventType,
+            _ => return None,
+        };
+        Some(key)
+    }
+
+    /// Types and event types are compared case insensitive (ex: `messageType == error`)
+    fn is_enumeration(&self) -> bool {
+        matches!(self, Key::MessageType | Key::EventType)
+    }
+
+    fn text(&self, log_data: &LogData) -> String {
+        match self {
+            Key::EventMessage | Key::ComposedMessage => log_data.message.to_owned(),
+            Key::FormatString => log_data.raw_message.to_owned(),
+            Key::ProcessImagePath => log_data.process.to_owned(),
+            Key::Process => image_name(&log_data.process).to_string(),
+            Key::SenderImagePath => log_data.library.to_owned(),
+            Key::Sender => image_name(&log_data.library).to_string(),
+            Key::ProcessImageUuid => log_data.process_uuid.to_owned(),
+            Key::SenderImageUuid => log_data.library_uuid.to_owned(),
+            Key::Subsystem => log_data.subsystem.to_owned(),
+            Key::Category => log_data.category.to_owned(),
+            Key::ProcessId => log_data.pid.to_string(),
+            Key::ThreadId => log_data.thread_id.to_string(),
+            Key::UserId => log_data.euid.to_string(),
+            Key::ActivityId => log_data.activity_id.to_string(),
+            Key::MessageType => log_data.log_type.to_lowercase(),
+            Key::EventType => event_type_name(&log_data.event_type).to_lowercase(),
+        }
+    }
+
+    fn number(&self, log_data: &LogData) -> Option<u64> {
+        match self {
+            Key::ProcessId => Some(log_data.pid),
+            Key::ThreadId => Some(log_data.thread_id),
+            Key::UserId => Some(u64::from(log_data.euid)),
+            Key::ActivityId => Some(log_data.activity_id),
+            Key::MessageType => message_type_number(&log_data.log_type),
+            Key::EventType => event_type_number(&log_data.event_type),
+            _ => None,
+        }
+    }
+}
+
+/// `log` command name of the `LogData` event type
+fn event_type_name(event_type: &str) -> &str {
+    match event_type {
+        "Log" => "logEvent",
+        "Activity" => "activityCreateEvent",
+        "Trace" => "traceEvent",
+        "Signpost" => "signpostEvent",
+        "Loss" => "lossEvent",
+        "Statedump" => "stateEvent",
+        "Simpledump" => "simpleDumpEvent",
+        _ => event_type,
+    }
+}
+
+/// Numeric `eventType` values used by `OSLogEventProxy`
+fn event_type_number(event_type: &str) -> Option<u64> {
+    let number = match event_type {
+        "Activity" => 0x201,
+        "Trace" => 0x300,
+        "Log" => 0x400,
+        "Signpost" => 0x600,
+        "Loss" => 0x700,
+        "Statedump" => 0x1000,
+        _ => return None,
+    };
+    Some(number)
+}
+
+/// Numeric `messageType` values, same as the firehose log type
+fn message_type_number(log_type: &str) -> Option<u64> {
+    let number = match log_type {
+        "Default" => 0,
+        "Info" | "Create" => 1,
+        "Debug" => 2,
+        "Error" => 0x10,
+        "Fault" => 0x11,
+        _ => return None,
+    };
+    Some(number)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Contains,
+    BeginsWith,
+    EndsWith,
+    Like,
+    Matches,
+    In,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Text(String),
+    Number(u64),
+}
+
+#[derive(Debug, Clone)]
+struct Comparison {
+    key: Key,
+    operator: Operator,
+    case_insensitive: bool,
+    diacritic_insensitive: bool,
+    /// Values normalized with the comparison modifiers. `IN` can have multiple values
+    values: Vec<Value>,
+    /// Compiled `LIKE` and `MATCHES` pattern
+    pattern: Option<Regex>,
+}
+
+impl Comparison {
+    fn new(
+        key: Key,
+        operator: Operator,
+        modifiers: &str,
+        values: Vec<Value>,
+    ) -> Result<Comparison, String> {
+        // [n] means the predicate strings are already lowercase without diacritics. Log values are not, so they are normalized like [cd]
+        let normalized = modifiers.contains('n');
+        let mut comparison = Comparison {
+            key,
+            operator,
+            case_insensitive: modifiers.contains('c') || normalized || key.is_enumeration(),
+            diacritic_insensitive: modifiers.contains('d') || normalized,
+            values: Vec::new(),
+            pattern: None,
+        };
+        comparison.values = values
+            .into_iter()
+            .map(|value| match value {
+                Value::Text(text) => Value::Text(comparison.normalize(&text)),
+                Value::Number(_) => value,
+            })
+            .collect();
+
+        if matches!(operator, Operator::Like | Operator::Matches) {
+            let Some(Value::Text(text)) = comparison.values.first() else {
+                return Err(format!("{:?} requires a string", operator));
+            };
+            let pattern = if operator == Operator::Like {
+                like_pattern(text)
+            } else {
+                format!("^(?:{})$", text)
+            };
+            let regex = RegexBuilder::new(&pattern)
+                .case_insensitive(comparison.case_insensitive)
+                .dot_matches_new_line(true)
+                .build()
+                .map_err(|err| format!("invalid pattern {:?}: {}", text, err))?;
+            comparison.pattern = Some(regex);
+        }
+        Ok(comparison)
+    }
+
+    /// Apply the `[c]`, `[d]` and `[n]` modifiers
+    fn normalize(&self, text: &str) -> String {
+        let text = if self.diacritic_insensitive {
+            text.nfd().filter(|c| !is_combining_mark(*c)).collect()
+        } else {
+            text.to_string()
+        };
+        // Patterns are compiled case insensitive instead, lowercasing would change escapes like \D
+        let is_pattern = matches!(self.operator, Operator::Like | Operator::Matches);
+        if self.case_insensitive && !is_pattern {
+            text.to_lowercase()
+        } else {
+            text
+        }
+    }
+
+    fn evaluate(&self, log_data: &LogData) -> bool {
+        let number = self.key.number(log_data);
+        let text = self.normalize(&self.key.text(log_data));
+
+        if let Some(pattern) = &self.pattern {
+            return pattern.is_match(&text);
+        }
+
+        let compare = |value: &Value| -> Option<Ordering> {
+            match (value, number) {
+                (Value::Number(expected), Some(actual)) => Some(actual.cmp(expected)),
+                (Value::Number(expected), None) => Some(text.as_str().cmp(&expected.to_string())),
+                (Value::Text(expected), _) => Some(text.as_str().cmp(expected.as_str())),
+            }
+        };
+        let text_value = || match self.values.first() {
+            Some(Value::Text(expected)) => expected.to_owned(),
+            Some(Value::Number(expected)) => expected.to_string(),
+            None => String::new(),
+        };
+
+        match self.operator {
+            Operator::Equal => self.values.first().and_then(compare) == Some(Ordering::Equal),
+            Operator::NotEqual => self.values.first().and_then(compare) != Some(Ordering::Equal),
+            Operator::Less => self.values.first().and_then(compare) == Some(Ordering::Less),
+            Operator::LessEqual => matches!(
+                self.values.first().and_then(compare),
+                Some(Ordering::Less | Ordering::Equal)
+            ),
+            Operator::Greater => self.values.first().and_then(compare) == Some(Ordering::Greater),
+            Operator::GreaterEqual => matches!(
+                self.values.first().and_then(compare),
+                Some(Ordering::Greater | Ordering::Equal)
+            ),
+            Operator::Contains => text.contains(&text_value()),
+            Operator::BeginsWith => text.starts_with(&text_value()),
+            Operator::EndsWith => text.ends_with(&text_value()),
+            Operator::In => self
+                .values
+                .iter()
+                .any(|value| compare(value) == Some(Ordering::Equal)),
+            // Handled by the compiled pattern
+            Operator::Like | Operator::Matches => false,
+        }
+    }
+}
+
+/// Convert a `LIKE` wildcard pattern (`*` and `?`) to a regex
+fn like_pattern(like: &str) -> String {
+    let mut pattern = String::from("^");
+    let mut chars = like.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    pattern.push_str(&regex::escape(&escaped.to_string()));
+                }
+            }
+            _ => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    pattern
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Text(String),
+    Number(u64),
+    /// Comparison modifiers inside brackets. Ex: `[cd]`
+    Modifier(String),
+    Symbol(&'static str),
+}
+
+/// Split the predicate into tokens
+fn tokenize(predicate: &str) -> Result<Vec<Token>, String> {
+    let symbols = [
+        "==", "!=", "<>", "<=", "=<", ">=", "=>", "&&", "||", "=", "<", ">", "!", "(", ")", "{",
+        "}", ",",
+    ];
+    let mut tokens = Vec::new();
+    let mut remaining = predicate.trim_start();
+    'tokens: while let Some(c) = remaining.chars().next() {
+        if c == '"' || c == '\'' {
+            let mut text = String::new();
+            let mut chars = remaining.char_indices().skip(1);
+            loop {
+                match chars.next() {
+                    Some((index, value)) if value == c => {
+                        remaining = &remaining[index + 1..];
+                        break;
+                    }
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => text.push('\n'),
+                        Some((_, 't')) => text.push('\t'),
+                        Some((_, value)) => text.push(value),
+                        None => return Err(String::from("unterminated string")),
+                    },
+                    Some((_, value)) => text.push(value),
+                    None => return Err(String::from("unterminated string")),
+                }
+            }
+            tokens.push(Token::Text(text));
+        } else if c == '[' {
+            let Some(end) = remaining.find(']') else {
+                return Err(String::from("unterminated modifier"));
+            };
+            let modifier = remaining[1..end].to_lowercase();
+            if modifier.is_empty()
+                || !modifier
+                    .chars()
+                    .all(|value| matches!(value, 'c' | 'd' | 'n'))
+            {
+                return Err(format!("unsupported modifier [{}]", modifier));
+            }
+            tokens.push(Token::Modifier(modifier));
+            remaining = &remaining[end + 1..];
+        } else if c.is_ascii_digit() {
+            let end = remaining
+                .find(|value: char| !value.is_ascii_alphanumeric())
+                .unwrap_or(remaining.len());
+            let number = &remaining[..end];
+            let value = match number.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16),
+                None => number.parse(),
+            };
+            match value {
+                Ok(result) => tokens.push(Token::Number(result)),
+                Err(_) => return Err(format!("invalid number {}", number)),
+            }
+            remaining = &remaining[end..];
+        } else if c.is_alphabetic() || c == '_' || c == '$' {
+            let end = remaining
+                .find(|value: char| !(value.is_alphanumeric() || value == '_' || value == '.'))
+                .unwrap_or(remaining.len());
+            tokens.push(Token::Identifier(remaining[..end].to_string()));
+            remaining = &remaining[end..];
+        } else {
+            for symbol in symbols {
+                if let Some(rest) = remaining.strip_prefix(symbol) {
+                    tokens.push(Token::Symbol(symbol));
+                    remaining = rest;
+                    remaining = remaining.trim_start();
+                    continue 'tokens;
+                }
+            }
+            return Err(format!("unexpected character {:?}", c));
+        }
+        remaining = remaining.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser. `OR` has the lowest precedence, then `AND`, then `NOT`
+struct PredicateParser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl PredicateParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    /// Check for a keyword (case insensitive) or symbol and consume it
+    fn accept(&mut self, keyword: &str, symbol: &str) -> bool {
+        let found = match self.peek() {
+            Some(Token::Identifier(name)) => name.eq_ignore_ascii_case(keyword),
+            Some(Token::Symbol(value)) => *value == symbol,
+            _ => false,
+        };
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_and()?;
+        while self.accept("OR", "||") {
+            let right = self.parse_and()?;
+            expression = Expression::Or(Box::new(expression), Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, String> {
+        let mut expression = self.parse_not()?;
+        while self.accept("AND", "&&") {
+            let right = self.parse_not()?;
+            expression = Expression::And(Box::new(expression), Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn parse_not(&mut self) -> Result<Expression, String> {
+        if self.accept("NOT", "!") {
+            return Ok(Expression::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, String> {
+        if self.accept("", "(") {
+            let expression = self.parse_or()?;
+            if !self.accept("", ")") {
+                return Err(String::from("missing closing parenthesis"));
+            }
+            return Ok(expression);
+        }
+        if self.accept("TRUEPREDICATE", "") {
+            return Ok(Expression::Constant(true));
+        }
+        if self.accept("FALSEPREDICATE", "") {
+            return Ok(Expression::Constant(false));
+        }
+
+        let key = match self.next() {
+            Some(Token::Identifier(name)) => match Key::from_name(&name) {
+                Some(key) => key,
+                None => return Err(format!("unsupported key {}", name)),
+            },
+            Some(token) => return Err(format!("expected a key, found {:?}", token)),
+            None => return Err(String::from("unexpected end of predicate")),
+        };
+        let operator = self.parse_operator()?;
+        let modifiers = match self.peek() {
+            Some(Token::Modifier(value)) => {
+                let modifiers = value.to_owned();
+                self.position += 1;
+                modifiers
+            }
+            _ => String::new(),
+        };
+
+        let values = if operator == Operator::In {
+            self.parse_list()?
+        } else {
+            vec![self.parse_value()?]
+        };
+        Comparison::new(key, operator, &modifiers, values).map(Expression::Comparison)
+    }
+
+    fn parse_operator(&mut self) -> Result<Operator, String> {
+        let operator = match self.next() {
+            Some(Token::Symbol("==" | "=")) => Operator::Equal,
+            Some(Token::Symbol("!=" | "<>")) => Operator::NotEqual,
+            Some(Token::Symbol("<")) => Operator::Less,
+            Some(Token::Symbol("<=" | "=<")) => Operator::LessEqual,
+            Some(Token::Symbol(">")) => Operator::Greater,
+            Some(Token::Symbol(">=" | "=>")) => Operator::GreaterEqual,
+            Some(Token::Identifier(name)) => match name.to_uppercase().as_str() {
+                "CONTAINS" => Operator::Contains,
+                "BEGINSWITH" => Operator::BeginsWith,
+                "ENDSWITH" => Operator::EndsWith,
+                "LIKE" => Operator::Like,
+                "MATCHES" => Operator::Matches,
+                "IN" => Operator::In,
+                _ => return Err(format!("unsupported operator {}", name)),
+            },
+            Some(token) => return Err(format!("expected an operator, found {:?}", token)),
+            None => return Err(String::from("missing operator")),
+        };
+        Ok(operator)
+    }
+
+    /// Strings, numbers and bare identifiers (ex: `messageType == error`)
+    fn parse_value(&mut self) -> Result<Value, String> {
+        match self.next() {
+            Some(Token::Text(text) | Token::Identifier(text)) => Ok(Value::Text(text)),
+            Some(Token::Number(number)) => Ok(Value::Number(number)),
+            Some(token) => Err(format!("expected a value, found {:?}", token)),
+            None => Err(String::from("missing value")),
+        }
+    }
+
+    /// `IN` values: `{"a", "b"}`
+    fn parse_list(&mut self) -> Result<Vec<Value>, String> {
+        if !self.accept("", "{") {
+            return Ok(vec![self.parse_value()?]);
+        }
+        let mut values = Vec::new();
+        if self.accept("", "}") {
+            return Ok(values);
+        }
+        loop {
+            values.push(self.parse_value()?);
+            if self.accept("", "}") {
+                return Ok(values);
+            }
+            if !self.accept("", ",") {
+                return Err(String::from("expected , or } in list"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{like_pattern, tokenize, Predicate, Token};
+    use crate::unified_log::{self, LogData};
+
+    fn test_log_data() -> LogData {
+        LogData {
+            subsystem: String::from("com.apple.powerd"),
+            library: String::from("/usr/libexec/powerd"),
+            category: String::from("battery"),
+            log_type: String::from("Error"),
+            process: String::from("/usr/libexec/powerd"),
+            message: String::from("Updated Battery Health: Fair, Café"),
+            raw_message: String::from("Updated Battery Health: %@, %@"),
+            ..unified_log::test_log_data()
+        }
+    }
+
+    fn check(predicate: &str) -> bool {
+        Predicate::parse(predicate)
+            .unwrap()
+            .matches(&test_log_data())
+    }
+
+    #[test]
+    fn test_predicate_string_operators() {
+        assert!(check(
+            "subsystem == \"com.apple.powerd\" AND eventMessage CONTAINS[c] \"battery\""
+        ));
+        assert!(!check("eventMessage CONTAINS \"battery\""));
+        assert!(check("processImagePath BEGINSWITH '/usr/libexec'"));
+        assert!(check("senderImagePath ENDSWITH \"powerd\""));
+        assert!(check("process == \"powerd\""));
+        assert!(check("subsystem != \"com.apple.lom\""));
+        assert!(check("composedMessage LIKE \"Updated*Fair?*\""));
+        assert!(check("eventMessage MATCHES \"Updated .+: (Fair|Good).*\""));
+        assert!(!check("eventMessage MATCHES \"Fair\""));
+        assert!(check("category IN {\"battery\", \"charger\"}"));
+        assert!(!check("category IN {\"charger\"}"));
+    }
+
+    #[test]
+    fn test_predicate_modifiers() {
+        assert!(!check("eventMessage ENDSWITH \"cafe\""));
+        assert!(!check("eventMessage ENDSWITH[c] \"cafe\""));
+        assert!(!check("eventMessage ENDSWITH[d] \"cafe\""));
+        assert!(check("eventMessage ENDSWITH[d] \"Cafe\""));
+        assert!(check("eventMessage ENDSWITH[cd] \"CAFÉ\""));
+        assert!(check("eventMessage LIKE[c] \"*BATTERY*\""));
+        assert!(check("eventMessage ENDSWITH[n] \"cafe\""));
+        assert!(check("subsystem BEGINSWITH[n] \"com.apple.\""));
+        assert!(!check("eventMessage CONTAINS[n] \"good\""));
+    }
+
+    #[test]
+    fn test_predicate_numbers_and_types() {
+        assert!(check("processID == 45"));
+        assert!(check("processID > 10 && processID <= 45"));
+        assert!(check("processID IN {1, 45}"));
+        assert!(check("messageType == error"));
+        assert!(check("messageType == 16"));
+        assert!(check("messageType == \"Error\""));
+        assert!(check("eventType == logEvent"));
+        assert!(!check("eventType == signpostEvent"));
+    }
+
+    #[test]
+    fn test_predicate_logic() {
+        assert!(check("NOT subsystem == \"com.apple.lom\""));
+        assert!(check(
+            "!(processID == 1) AND (category == \"x\" OR category == \"battery\")"
+        ));
+        assert!(!check("processID == 1 OR processID == 2"));
+        assert!(check("processID == 1 or processID == 2 or processID == 45"));
+        assert!(check("TRUEPREDICATE"));
+        assert!(!check("FALSEPREDICATE OR NOT TRUEPREDICATE"));
+    }
+
+    #[test]
+    fn test_predicate_errors() {
+        assert!(Predicate::parse("subsystem ==").is_err());
+        assert!(Predicate::parse("unknownKey == 1").is_err());
+        assert!(Predicate::parse("subsystem == \"unterminated").is_err());
+        assert!(Predicate::parse("(subsystem == \"a\"").is_err());
+        assert!(Predicate::parse("subsystem == \"a\" extra").is_err());
+        assert!(Predicate::parse("eventMessage MATCHES \"(\"").is_err());
+        assert!("processID == 1".parse::<Predicate>().is_ok());
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("eventMessage CONTAINS[cd] 'a\\'b' && pid >= 0x10").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier(String::from("eventMessage")),
+                Token::Identifier(String::from("CONTAINS")),
+                Token::Modifier(String::from("cd")),
+                Token::Text(String::from("a'b")),
+                Token::Symbol("&&"),
+                Token::Identifier(String::from("pid")),
+                Token::Symbol(">="),
+                Token::Number(16),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_like_pattern() {
+        assert_eq!(like_pattern("a*b?.c"), "^a.*b.\\.c$");
+    }
+}
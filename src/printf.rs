@@ -0,0 +1,668 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+// printf formatting of log message arguments. Follows the C library on macOS:
+//   `%p` of zero is "0x0"
+//   `%a` output is always normalized ("0x1.000p+1" instead of "0x2.000p+0")
+
+/// Integer size from the length modifier. Ex: `hh`, `l`, `ll`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) enum Length {
+    Char,
+    Short,
+    #[default]
+    Int,
+    Long,
+}
+
+/// printf conversion specification. Ex: `%-+08.3lld`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct FormatSpec {
+    pub(crate) left_justify: bool, // -
+    pub(crate) plus_sign: bool,    // +
+    pub(crate) space_sign: bool,   // ' '
+    pub(crate) alternate: bool,    // #
+    pub(crate) zero_pad: bool,     // 0
+    pub(crate) width: Option<usize>,
+    pub(crate) dynamic_width: bool, // Width is taken from a message item (*)
+    pub(crate) precision: Option<usize>,
+    pub(crate) dynamic_precision: bool, // Precision is taken from a message item (.*)
+    pub(crate) length: Length,
+    pub(crate) conversion: char,
+}
+
+/// Largest width or precision. Rust formatting panics on precisions above `u16::MAX`
+pub(crate) const MAX_FIELD_SIZE: usize = 65535;
+
+/// Value to format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PrintfArg<'a> {
+    Int(i64),
+    Float(f64),
+    Str(&'a str),
+}
+
+/// Parse a conversion specification without the leading `%`. Ex: `-08.3lld`
+pub(crate) fn parse_spec(spec_string: &str) -> Option<FormatSpec> {
+    let mut spec = FormatSpec::default();
+    let mut chars = spec_string.chars().peekable();
+
+    while let Some(flag) = chars.peek() {
+        match flag {
+            '-' => spec.left_justify = true,
+            '+' => spec.plus_sign = true,
+            ' ' => spec.space_sign = true,
+            '#' => spec.alternate = true,
+            '0' => spec.zero_pad = true,
+            // Thousands grouping is not used by os_log
+            '\'' => {}
+            _ => break,
+        }
+        chars.next();
+    }
+
+    if chars.next_if_eq(&'*').is_some() {
+        spec.dynamic_width = true;
+    } else {
+        spec.width = parse_number(&mut chars);
+    }
+
+    if chars.next_if_eq(&'.').is_some() {
+        if chars.next_if_eq(&'*').is_some() {
+            spec.dynamic_precision = true;
+        } else {
+            // A lone "." is a precision of zero
+            spec.precision = Some(parse_number(&mut chars).unwrap_or_default());
+        }
+    }
+
+    let remaining: String = chars.collect();
+    let lengths = [
+        ("hh", Length::Char),
+        ("h", Length::Short),
+        ("ll", Length::Long),
+        ("l", Length::Long),
+        ("q", Length::Long),
+        ("j", Length::Long),
+        ("z", Length::Long),
+        ("t", Length::Long),
+        ("L", Length::Long),
+        ("I64", Length::Long),
+        ("I32", Length::Int),
+        ("I", Length::Long),
+        ("w", Length::Int),
+    ];
+    let mut conversion = remaining.as_str();
+    for (modifier, length) in lengths {
+        if let Some(value) = remaining.strip_prefix(modifier) {
+            spec.length = length;
+            conversion = value;
+            break;
+        }
+    }
+
+    spec.conversion = conversion.chars().next()?;
+    // Deprecated conversions for long values
+    if ['D', 'U', 'O'].contains(&spec.conversion) {
+        spec.length = Length::Long;
+    }
+    Some(spec)
+}
+
+/// Parse the specification of a message formatter. Any `%{...}` annotation is skipped. Ex: `%{public}llu`
+pub(crate) fn parse_formatter_spec(formatter: &str) -> Option<FormatSpec> {
+    match formatter.strip_prefix("%{") {
+        Some(annotated) => parse_spec(annotated.split_once('}')?.1),
+        None => parse_spec(formatter.strip_prefix('%')?),
+    }
+}
+
+// Width or precision digits, limited to MAX_FIELD_SIZE
+fn parse_number(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Option<usize> {
+    let mut digits = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        digits.push(digit);
+    }
+    if digits.is_empty() {
+        return None;
+    }
+    Some(
+        digits
+            .parse::<usize>()
+            .unwrap_or(usize::MAX)
+            .min(MAX_FIELD_SIZE),
+    )
+}
+
+impl FormatSpec {
+    /// Signed value for the length modifier. Ex: `%hhd` of 300 is 44
+    pub(crate) fn signed_value(&self, value: i64) -> i64 {
+        match self.length {
+            Length::Char => i64::from(value as i8),
+            Length::Short => i64::from(value as i16),
+            Length::Int => i64::from(value as i32),
+            Length::Long => value,
+        }
+    }
+
+    /// Unsigned value for the length modifier. Ex: `%u` of -1 is 4294967295
+    pub(crate) fn unsigned_value(&self, value: i64) -> u64 {
+        // Pointers are always 64 bits
+        if self.conversion == 'p' {
+            return value as u64;
+        }
+        match self.length {
+            Length::Char => u64::from(value as u8),
+            Length::Short => u64::from(value as u16),
+            Length::Int => u64::from(value as u32),
+            Length::Long => value as u64,
+        }
+    }
+
+    /// Format a value using the specification
+    pub(crate) fn format(&self, arg: PrintfArg<'_>) -> String {
+        match (self.conversion, arg) {
+            ('d' | 'i' | 'D' | 'u' | 'U' | 'o' | 'O' | 'x' | 'X' | 'p', PrintfArg::Int(value)) => {
+                self.format_int(value)
+            }
+            (
+                'd' | 'i' | 'D' | 'u' | 'U' | 'o' | 'O' | 'x' | 'X' | 'p',
+                PrintfArg::Float(value),
+            ) => self.format_int(i64::from_le_bytes(value.to_bits().to_le_bytes())),
+            ('e' | 'E' | 'f' | 'F' | 'g' | 'G' | 'a' | 'A', PrintfArg::Float(value)) => {
+                self.format_float(value)
+            }
+            ('e' | 'E' | 'f' | 'F' | 'g' | 'G' | 'a' | 'A', PrintfArg::Int(value)) => {
+                self.format_float(f64::from_bits(value as u64))
+            }
+            ('c', PrintfArg::Int(value)) => self.pad("", &(value as u8 as char).to_string(), false),
+            ('C', PrintfArg::Int(value)) => {
+                let wide_char = char::from_u32(value as u32).unwrap_or(char::REPLACEMENT_CHARACTER);
+                self.pad("", &wide_char.to_string(), false)
+            }
+            ('c' | 'C', PrintfArg::Str(value)) => self.pad("", value, false),
+            ('s' | 'S' | '@' | 'P', PrintfArg::Str(value)) => self.format_string(value),
+            // Writes the number of characters, nothing is printed
+            ('n', _) => String::new(),
+            (_, PrintfArg::Int(value)) => value.to_string(),
+            (_, PrintfArg::Float(value)) => value.to_string(),
+            (_, PrintfArg::Str(value)) => value.to_string(),
+        }
+    }
+
+    fn format_int(&self, value: i64) -> String {
+        let mut sign = "";
+        let mut prefix = "";
+        let mut digits = match self.conversion {
+            'd' | 'i' | 'D' => {
+                let signed = self.signed_value(value);
+                sign = self.sign(signed < 0);
+                signed.unsigned_abs().to_string()
+            }
+            'o' | 'O' => format!("{:o}", self.unsigned_value(value)),
+            'x' | 'X' | 'p' => {
+                let unsigned = self.unsigned_value(value);
+                if self.conversion == 'p' {
+                    prefix = "0x";
+                } else if self.alternate && unsigned != 0 {
+                    prefix = if self.conversion == 'X' { "0X" } else { "0x" };
+                }
+                if self.conversion == 'X' {
+                    format!("{:X}", unsigned)
+                } else {
+                    format!("{:x}", unsigned)
+                }
+            }
+            _ => self.unsigned_value(value).to_string(),
+        };
+
+        if let Some(precision) = self.precision {
+            // Zero with a precision of zero has no digits
+            if precision == 0 && digits == "0" {
+                digits.clear();
+            }
+            if digits.len() < precision {
+                digits = format!("{}{}", "0".repeat(precision - digits.len()), digits);
+            }
+        }
+        // Alternate octal always starts with 0
+        if ['o', 'O'].contains(&self.conversion) && self.alternate && !digits.starts_with('0') {
+            digits.insert(0, '0');
+        }
+
+        self.pad(
+            &format!("{}{}", sign, prefix),
+            &digits,
+            self.precision.is_none(),
+        )
+    }
+
+    fn format_float(&self, value: f64) -> String {
+        let sign = self.sign(value.is_sign_negative());
+        let uppercase = self.conversion.is_ascii_uppercase();
+        if !value.is_finite() {
+            let special = if value.is_nan() { "nan" } else { "inf" };
+            let special = if uppercase {
+                special.to_uppercase()
+            } else {
+                special.to_string()
+            };
+            return self.pad(sign, &special, false);
+        }
+
+        let value = value.abs();
+        let precision = self.precision.unwrap_or(6);
+        let (prefix, body) = match self.conversion.to_ascii_lowercase() {
+            'e' => (
+                "",
+                format_exponent(value, precision, self.alternate, uppercase),
+            ),
+            'g' => (
+                "",
+                format_general(value, precision, self.alternate, uppercase),
+            ),
+            'a' => (
+                if uppercase { "0X" } else { "0x" },
+                format_hex_float(value, self.precision, self.alternate, uppercase),
+            ),
+            _ => ("", format_fixed(value, precision, self.alternate)),
+        };
+        self.pad(&format!("{}{}", sign, prefix), &body, true)
+    }
+
+    fn format_string(&self, value: &str) -> String {
+        let mut message = value;
+        // Precision is the maximum number of bytes
+        if let Some(precision) = self.precision {
+            if precision < message.len() {
+                let mut end = precision;
+                while !message.is_char_boundary(end) {
+                    end -= 1;
+                }
+                message = &message[..end];
+            }
+        }
+        self.pad("", message, false)
+    }
+
+    fn sign(&self, negative: bool) -> &'static str {
+        if negative {
+            "-"
+        } else if self.plus_sign {
+            "+"
+        } else if self.space_sign {
+            " "
+        } else {
+            ""
+        }
+    }
+
+    /// Pad to the width. Zeros are added between the sign/prefix and the value
+    fn pad(&self, prefix: &str, value: &str, zero_allowed: bool) -> String {
+        let width = self.width.unwrap_or_default();
+        let size = prefix.len() + value.len();
+        if size >= width {
+            return format!("{}{}", prefix, value);
+        }
+        let padding = width - size;
+        if self.left_justify {
+            format!("{}{}{}", prefix, value, " ".repeat(padding))
+        } else if self.zero_pad && zero_allowed {
+            format!("{}{}{}", prefix, "0".repeat(padding), value)
+        } else {
+            format!("{}{}{}", " ".repeat(padding), prefix, value)
+        }
+    }
+}
+
+// %f
+fn format_fixed(value: f64, precision: usize, alternate: bool) -> String {
+    let mut message = format!("{:.precision$}", value, precision = precision);
+    if alternate && precision == 0 {
+        message.push('.');
+    }
+    message
+}
+
+// %e
+fn format_exponent(value: f64, precision: usize, alternate: bool, uppercase: bool) -> String {
+    let message = format!("{:.precision$e}", value, precision = precision);
+    let (mantissa, exponent) = message.split_once('e').unwrap_or((&message, "0"));
+    let exponent = exponent.parse::<i32>().unwrap_or_default();
+
+    let exponent_sign = if exponent < 0 { '-' } else { '+' };
+    format!(
+        "{}{}{}{}{:02}",
+        mantissa,
+        if alternate && precision == 0 { "." } else { "" },
+        if uppercase { 'E' } else { 'e' },
+        exponent_sign,
+        exponent.unsigned_abs()
+    )
+}
+
+// %g uses %e if the exponent is less than -4 or at least the precision, otherwise %f
+fn format_general(value: f64, precision: usize, alternate: bool, uppercase: bool) -> String {
+    let precision = precision.max(1);
+    // Exponent after rounding to the precision
+    let rounded = format!("{:.precision$e}", value, precision = precision - 1);
+    let exponent = rounded
+        .split_once('e')
+        .and_then(|(_, exponent)| exponent.parse::<i64>().ok())
+        .unwrap_or_default();
+
+    let precision_exponent = i64::try_from(precision).unwrap_or(i64::MAX);
+    let message = if exponent < -4 || exponent >= precision_exponent {
+        format_exponent(value, precision - 1, alternate, uppercase)
+    } else {
+        format_fixed(
+            value,
+            (precision_exponent - 1 - exponent) as usize,
+            alternate,
+        )
+    };
+    if alternate {
+        return message;
+    }
+
+    // Remove trailing zeros from the fraction
+    let (mantissa, exponent) = match message.find(['e', 'E']) {
+        Some(index) => message.split_at(index),
+        None => (message.as_str(), ""),
+    };
+    let mantissa = if mantissa.contains('.') {
+        mantissa.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        mantissa
+    };
+    format!("{}{}", mantissa, exponent)
+}
+
+// %a without the "0x" prefix
+fn format_hex_float(
+    value: f64,
+    precision: Option<usize>,
+    alternate: bool,
+    uppercase: bool,
+) -> String {
+    let fraction_bits = 52;
+    let fraction_mask = (1u64 << fraction_bits) - 1;
+    let bits = value.to_bits();
+    let mut fraction = bits & fraction_mask;
+    // The sign bit is not set, the exponent is 11 bits
+    let biased_exponent = i64::from((bits >> fraction_bits) as u16);
+
+    let mut leading = 1u64;
+    let mut exponent = biased_exponent - 1023;
+    if value == 0.0 {
+        leading = 0;
+        exponent = 0;
+    } else if biased_exponent == 0 {
+        // Subnormal values are normalized
+        let shift = fraction.leading_zeros() - 11;
+        fraction = (fraction << shift) & fraction_mask;
+        exponent = -1022 - i64::from(shift);
+    }
+
+    // 52 fraction bits are 13 hex digits
+    let max_digits = 13;
+    let digits = match precision {
+        None => format!("{:013x}", fraction)
+            .trim_end_matches('0')
+            .to_string(),
+        Some(precision) if precision >= max_digits => {
+            format!("{:013x}{}", fraction, "0".repeat(precision - max_digits))
+        }
+        Some(precision) => {
+            // Round half to even
+            let dropped_bits = (max_digits - precision) * 4;
+            let remainder = fraction & ((1u64 << dropped_bits) - 1);
+            let half = 1u64 << (dropped_bits - 1);
+            let mut kept = fraction >> dropped_bits;
+            let kept_value = (leading << (precision * 4)) | kept;
+            if remainder > half || (remainder == half && kept_value & 1 == 1) {
+                kept += 1;
+                // Carry into the leading digit
+                if kept == 1u64 << (precision * 4) {
+                    kept = 0;
+                    exponent += 1;
+                }
+            }
+            if precision == 0 {
+                String::new()
+            } else {
+                format!("{:0precision$x}", kept, precision = precision)
+            }
+        }
+    };
+
+    let message = format!(
+        "{}{}{}p{}{}",
+        leading,
+        if !digits.is_empty() || alternate {
+            "."
+        } else {
+            ""
+        },
+        digits,
+        if exponent < 0 { '-' } else { '+' },
+        exponent.unsigned_abs()
+    );
+    if uppercase {
+        message.to_uppercase()
+    } else {
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_formatter_spec, parse_spec, FormatSpec, Length, PrintfArg};
+
+    fn printf(spec: &str, arg: PrintfArg<'_>) -> String {
+        parse_spec(spec).unwrap().format(arg)
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let spec = parse_spec("-+ #012.3lld").unwrap();
+        assert_eq!(
+            spec,
+            FormatSpec {
+                left_justify: true,
+                plus_sign: true,
+                space_sign: true,
+                alternate: true,
+                zero_pad: true,
+                width: Some(12),
+                dynamic_width: false,
+                precision: Some(3),
+                dynamic_precision: false,
+                length: Length::Long,
+                conversion: 'd',
+            }
+        );
+
+        let spec = parse_spec("*.*s").unwrap();
+        assert!(spec.dynamic_width && spec.dynamic_precision);
+        assert_eq!(parse_spec(".x").unwrap().precision, Some(0));
+        assert_eq!(parse_spec("hhu").unwrap().length, Length::Char);
+        assert!(parse_spec("08").is_none());
+        // Width and precision are limited to 65535
+        let spec = parse_spec("99999999999999999999999.70000f").unwrap();
+        assert_eq!(spec.width, Some(65535));
+        assert_eq!(spec.precision, Some(65535));
+        assert_eq!(printf(".70000f", PrintfArg::Float(1.5)).len(), 65537);
+
+        let spec = parse_formatter_spec("%{public, mask.hash}hx").unwrap();
+        assert_eq!((spec.length, spec.conversion), (Length::Short, 'x'));
+    }
+
+    #[test]
+    fn test_format_int() {
+        assert_eq!(printf("+04d", PrintfArg::Int(2)), "+002");
+        assert_eq!(printf("-5d", PrintfArg::Int(-2)), "-2   ");
+        assert_eq!(printf("u", PrintfArg::Int(-1)), "4294967295");
+        assert_eq!(printf("hhd", PrintfArg::Int(300)), "44");
+        assert_eq!(printf("#x", PrintfArg::Int(0)), "0");
+        assert_eq!(printf("#06X", PrintfArg::Int(255)), "0X00FF");
+        assert_eq!(printf("#.0o", PrintfArg::Int(0)), "0");
+        assert_eq!(printf(".0d", PrintfArg::Int(0)), "");
+        assert_eq!(printf("08.3d", PrintfArg::Int(7)), "     007");
+        assert_eq!(printf("p", PrintfArg::Int(0)), "0x0");
+        assert_eq!(printf("018p", PrintfArg::Int(4096)), "0x0000000000001000");
+    }
+
+    #[test]
+    fn test_format_float() {
+        assert_eq!(printf("f", PrintfArg::Float(-2.0)), "-2.000000");
+        assert_eq!(printf(".2f", PrintfArg::Float(0.125)), "0.12");
+        assert_eq!(printf("#.0f", PrintfArg::Float(1.0)), "1.");
+        assert_eq!(printf("08.2e", PrintfArg::Float(-3.0)), "-3.00e+00");
+        assert_eq!(printf("E", PrintfArg::Float(1e-300)), "1.000000E-300");
+        assert_eq!(printf("g", PrintfArg::Float(100000.0)), "100000");
+        assert_eq!(printf("g", PrintfArg::Float(1e-5)), "1e-05");
+        assert_eq!(printf("#g", PrintfArg::Float(1.0)), "1.00000");
+        assert_eq!(printf("a", PrintfArg::Float(1.0)), "0x1p+0");
+        assert_eq!(printf("A", PrintfArg::Float(-0.1875)), "-0X1.8P-3");
+        assert_eq!(printf(".0a", PrintfArg::Float(1.5)), "0x1p+1");
+        assert_eq!(printf("a", PrintfArg::Float(5e-324)), "0x1p-1074");
+        assert_eq!(printf("010a", PrintfArg::Float(1.0)), "0x00001p+0");
+        assert_eq!(printf("05f", PrintfArg::Float(f64::INFINITY)), "  inf");
+        assert_eq!(printf("+F", PrintfArg::Float(f64::NAN)), "+NAN");
+    }
+
+    #[test]
+    fn test_format_string() {
+        assert_eq!(printf("5.1s", PrintfArg::Str("xyz")), "    x");
+        assert_eq!(printf("-4s", PrintfArg::Str("ab")), "ab  ");
+        assert_eq!(printf("05s", PrintfArg::Str("ab")), "   ab");
+        assert_eq!(printf(".2s", PrintfArg::Str("é!")), "é");
+        assert_eq!(printf("3c", PrintfArg::Int(97)), "  a");
+        assert_eq!(printf("C", PrintfArg::Int(0x1F600)), "😀");
+    }
+
+    // Compare with snprintf from the host C library (glibc)
+    #[cfg(target_os = "linux")]
+    mod libc_differential {
+        use super::printf;
+        use crate::printf::PrintfArg;
+        use proptest::prelude::*;
+        use std::ffi::{c_char, c_void, CStr, CString};
+
+        fn snprintf<F: Fn(*mut c_char, usize) -> i32>(call: F) -> String {
+            let mut buffer = vec![0 as c_char; 1024];
+            let size = call(buffer.as_mut_ptr(), buffer.len());
+            assert!(size >= 0 && (size as usize) < buffer.len());
+            unsafe { CStr::from_ptr(buffer.as_ptr()) }
+                .to_string_lossy()
+                .into_owned()
+        }
+
+        fn spec_string(flags: &str, width: Option<usize>, precision: Option<usize>) -> String {
+            let mut spec = flags.to_string();
+            if let Some(width) = width {
+                spec.push_str(&width.to_string());
+            }
+            if let Some(precision) = precision {
+                spec.push_str(&format!(".{}", precision));
+            }
+            spec
+        }
+
+        proptest! {
+            #[test]
+            fn test_int_matches_libc(
+                flags in "[-+ #0]{0,5}",
+                width in proptest::option::of(0..30usize),
+                precision in proptest::option::of(0..25usize),
+                length in prop::sample::select(vec!["hh", "h", "", "l", "ll", "j", "z", "t", "q"]),
+                conversion in prop::sample::select(vec!['d', 'i', 'u', 'o', 'x', 'X']),
+                value in any::<i64>(),
+            ) {
+                let spec = format!("{}{}{}", spec_string(&flags, width, precision), length, conversion);
+                let c_format = CString::new(format!("%{}", spec)).unwrap();
+                let expected = snprintf(|buffer, size| unsafe {
+                    if ["hh", "h", ""].contains(&length) {
+                        libc::snprintf(buffer, size, c_format.as_ptr(), value as i32)
+                    } else {
+                        libc::snprintf(buffer, size, c_format.as_ptr(), value)
+                    }
+                });
+                prop_assert_eq!(printf(&spec, PrintfArg::Int(value)), expected, "%{}", spec);
+            }
+
+            #[test]
+            fn test_float_matches_libc(
+                flags in "[-+ #0]{0,5}",
+                width in proptest::option::of(0..40usize),
+                precision in proptest::option::of(0..20usize),
+                conversion in prop::sample::select(vec!['f', 'F', 'e', 'E', 'g', 'G', 'a', 'A']),
+                value in prop_oneof![
+                    any::<f64>(),
+                    -1e7..1e7f64,
+                    prop::sample::select(vec![0.0, -0.0, 0.5, 1.5, 2.5, 0.125, 9.9999995, 1e-5, 100000.0]),
+                ],
+            ) {
+                // glibc does not normalize %a. Ex: 0x1.fp+0 with a precision of 0 is "0x2p+0", macOS prints "0x1p+1"
+                let hex_float = ['a', 'A'].contains(&conversion);
+                prop_assume!(!(hex_float && value.is_subnormal()));
+
+                let spec = format!("{}{}", spec_string(&flags, width, precision), conversion);
+                let c_format = CString::new(format!("%{}", spec)).unwrap();
+                let expected = snprintf(|buffer, size| unsafe {
+                    libc::snprintf(buffer, size, c_format.as_ptr(), value)
+                });
+                let glibc_carry = expected
+                    .to_lowercase()
+                    .split_once("0x")
+                    .is_some_and(|(_, digits)| digits.trim_start_matches('0').starts_with('2'));
+                prop_assume!(!(hex_float && glibc_carry));
+                prop_assert_eq!(printf(&spec, PrintfArg::Float(value)), expected, "%{}", spec);
+            }
+
+            #[test]
+            fn test_string_matches_libc(
+                flags in "[-0]{0,2}",
+                width in proptest::option::of(0..30usize),
+                precision in proptest::option::of(0..30usize),
+                value in "[ -~]{0,20}",
+                character in 0x20..0x7fi32,
+            ) {
+                let spec = format!("{}s", spec_string(&flags, width, precision));
+                let c_format = CString::new(format!("%{}", spec)).unwrap();
+                let c_value = CString::new(value.as_str()).unwrap();
+                let expected = snprintf(|buffer, size| unsafe {
+                    libc::snprintf(buffer, size, c_format.as_ptr(), c_value.as_ptr())
+                });
+                prop_assert_eq!(printf(&spec, PrintfArg::Str(&value)), expected, "%{}", spec);
+
+                let spec = format!("{}c", spec_string(&flags, width, None));
+                let c_format = CString::new(format!("%{}", spec)).unwrap();
+                let expected = snprintf(|buffer, size| unsafe {
+                    libc::snprintf(buffer, size, c_format.as_ptr(), character)
+                });
+                prop_assert_eq!(printf(&spec, PrintfArg::Int(i64::from(character))), expected, "%{}", spec);
+            }
+
+            #[test]
+            fn test_pointer_matches_libc(
+                flags in "[-0]{0,2}",
+                width in proptest::option::of(0..30usize),
+                value in 1..=i64::MAX,
+            ) {
+                // glibc prints a null pointer as "(nil)"
+                let spec = format!("{}p", spec_string(&flags, width, None));
+                let c_format = CString::new(format!("%{}", spec)).unwrap();
+                let expected = snprintf(|buffer, size| unsafe {
+                    libc::snprintf(buffer, size, c_format.as_ptr(), value as usize as *const c_void)
+                });
+                prop_assert_eq!(printf(&spec, PrintfArg::Int(value)), expected, "%{}", spec);
+            }
+        }
+    }
+}
@@ -0,0 +1,637 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Query options applied while building log entries
+//!
+//! Time bounds are converted to Mach continuous time for the boot of each `tracev3` file using the timesync data.
+//! Whole files, catalogs and firehose preambles outside of the window are then skipped before any log message is formatted.
+//! Process and subsystem options are checked against the catalog process entries, so catalogs and firehose preambles
+//! of other processes are skipped the same way
+
+use crate::catalog::CatalogChunk;
+use crate::chunks::firehose::message::MessageData;
+use crate::error::ParserError;
+use crate::predicate::Predicate;
+use crate::timesync::TimesyncBoot;
+use crate::unified_log::{LogData, UnifiedLogCatalogData, UnifiedLogData};
+use crate::util::image_name;
+use crate::uuidtext::UUIDText;
+use chrono::{DateTime, NaiveDateTime};
+use log::error;
+use std::collections::{HashMap, HashSet};
+
+/// Time bound for a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryTime {
+    /// Nanoseconds since UNIXEPOCH
+    WallClock(i64),
+    /// Nanoseconds since the boot the `tracev3` file belongs to
+    BootRelative(u64),
+}
+
+impl QueryTime {
+    /// Parse an RFC 3339 timestamp (ex: `2022-01-16T03:05:26Z`) or `YYYY-MM-DD HH:MM:SS` in UTC
+    pub fn parse(time: &str) -> Result<QueryTime, ParserError> {
+        let nanoseconds = match DateTime::parse_from_rfc3339(time) {
+            Ok(result) => result.timestamp_nanos_opt(),
+            Err(_) => NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S%.f")
+                .ok()
+                .and_then(|result| result.and_utc().timestamp_nanos_opt()),
+        };
+        match nanoseconds {
+            Some(result) => Ok(QueryTime::WallClock(result)),
+            None => {
+                error!("[macos-unifiedlogs] Failed to parse query time: {}", time);
+                Err(ParserError::Query)
+            }
+        }
+    }
+}
+
+/// Options to select which log entries `build_log` reconstructs
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    /// Only include entries at or after this time
+    pub since: Option<QueryTime>,
+    /// Only include entries at or before this time
+    pub until: Option<QueryTime>,
+    /// Only include entries that match the predicate. Evaluated after the entry is built
+    pub predicate: Option<Predicate>,
+    /// Only include entries from these process IDs
+    pub pids: Vec<u64>,
+    /// Only include entries from these processes. Either the full image path or the image name (ex: `powerd`)
+    pub processes: Vec<String>,
+    /// Only include entries from these process image UUIDs
+    pub process_uuids: Vec<String>,
+    /// Only include entries from processes running as these effective user IDs
+    pub euids: Vec<u32>,
+    /// Only include entries from these subsystems
+    pub subsystems: Vec<String>,
+    /// Only include entries from these categories
+    pub categories: Vec<String>,
+}
+
+impl LogQuery {
+    /// Check if a built entry matches the query
+    pub(crate) fn matches(&self, log_data: &LogData, window: &TimeWindow) -> bool {
+        window.includes(log_data)
+            && self.matches_process(
+                log_data.pid,
+                log_data.euid,
+                &log_data.process_uuid,
+                Some(&log_data.process),
+            )
+            && self.matches_subsystem(&log_data.subsystem, &log_data.category)
+            && match &self.predicate {
+                Some(predicate) => predicate.matches(log_data),
+                None => true,
+            }
+    }
+
+    /// Find the catalog processes, and their subsystem identifiers, that match the process and subsystem options
+    pub(crate) fn process_filter(
+        &self,
+        catalog: &CatalogChunk,
+        strings_data: &[UUIDText],
+    ) -> ProcessFilter {
+        let filter_subsystems = !self.subsystems.is_empty() || !self.categories.is_empty();
+        let filter_processes = filter_subsystems
+            || !self.pids.is_empty()
+            || !self.processes.is_empty()
+            || !self.process_uuids.is_empty()
+            || !self.euids.is_empty();
+        let mut process_filter = ProcessFilter {
+            enabled: filter_processes,
+            processes: HashMap::new(),
+        };
+        if !filter_processes {
+            return process_filter;
+        }
+
+        for process_info in &catalog.catalog_process_info_entries {
+            // The image path is only needed when filtering by process. If it cannot be found the entry is checked once built
+            let has_uuidtext = strings_data
+                .iter()
+                .any(|data| process_info.main_uuid.ends_with(&data.uuid));
+            let process_path = if self.processes.is_empty() || !has_uuidtext {
+                None
+            } else {
+                MessageData::get_uuid_image_path(&process_info.main_uuid, strings_data)
+                    .ok()
+                    .map(|(_, path)| path)
+            };
+            if !self.matches_process(
+                u64::from(process_info.pid),
+                process_info.effective_user_id,
+                &process_info.main_uuid,
+                process_path.as_deref(),
+            ) {
+                continue;
+            }
+
+            let subsystems = if filter_subsystems {
+                let mut identifiers = HashSet::new();
+                for subsystem in &process_info.subsystem_entries {
+                    let Ok((_, info)) = CatalogChunk::get_subsystem(
+                        &subsystem.identifer,
+                        &process_info.first_number_proc_id,
+                        &process_info.second_number_proc_id,
+                        catalog,
+                    ) else {
+                        continue;
+                    };
+                    if self.matches_subsystem(&info.subsystem, &info.category) {
+                        identifiers.insert(subsystem.identifer);
+                    }
+                }
+                if identifiers.is_empty() {
+                    continue;
+                }
+                Some(identifiers)
+            } else {
+                None
+            };
+            process_filter.processes.insert(
+                (
+                    process_info.first_number_proc_id,
+                    process_info.second_number_proc_id,
+                ),
+                subsystems,
+            );
+        }
+        process_filter
+    }
+
+    /// Check the process options. A `None` image path always matches
+    fn matches_process(
+        &self,
+        pid: u64,
+        euid: u32,
+        process_uuid: &str,
+        process_path: Option<&str>,
+    ) -> bool {
+        let matches_pid = self.pids.is_empty() || self.pids.contains(&pid);
+        let matches_euid = self.euids.is_empty() || self.euids.contains(&euid);
+        let matches_uuid = self.process_uuids.is_empty()
+            || self
+                .process_uuids
+                .iter()
+                .any(|uuid| uuid.replace('-', "").eq_ignore_ascii_case(process_uuid));
+        let matches_path = match process_path {
+            Some(path) => {
+                self.processes.is_empty()
+                    || self
+                        .processes
+                        .iter()
+                        .any(|process| process == path || process == image_name(path))
+            }
+            None => true,
+        };
+        matches_pid && matches_euid && matches_uuid && matches_path
+    }
+
+    fn matches_subsystem(&self, subsystem: &str, category: &str) -> bool {
+        (self.subsystems.is_empty() || self.subsystems.iter().any(|value| value == subsystem))
+            && (self.categories.is_empty() || self.categories.iter().any(|value| value == category))
+    }
+
+    /// Convert the time bounds to Mach continuous time for the boot of the `tracev3` file
+    pub(crate) fn time_window(
+        &self,
+        unified_log_data: &UnifiedLogData,
+        timesync_data: &[TimesyncBoot],
+    ) -> TimeWindow {
+        let boot_uuid = unified_log_data
+            .header
+            .first()
+            .map_or("", |header| header.boot_uuid.as_str());
+        let timebase = timebase_adjustment(timesync_data, boot_uuid);
+        // Allow for rounding and timesync corrections when skipping data, entries are checked exactly once built
+        let slack = (1000000000.0 / timebase) as u64;
+
+        let start = match self.since {
+            Some(bound) => continuous_time(timesync_data, boot_uuid, bound, timebase)
+                .map(|value| value.saturating_sub(slack)),
+            None => Some(0),
+        };
+        let end = match self.until {
+            Some(bound) => continuous_time(timesync_data, boot_uuid, bound, timebase)
+                .map(|value| value.saturating_add(slack)),
+            None => Some(u64::MAX),
+        };
+
+        TimeWindow {
+            // Without timesync data for the boot nothing can be skipped
+            start: start.unwrap_or(0),
+            end: end.unwrap_or(u64::MAX),
+            since: self.since,
+            until: self.until,
+            timebase,
+        }
+    }
+}
+
+/// Catalog processes that match the query process and subsystem options
+#[derive(Debug, Clone)]
+pub(crate) struct ProcessFilter {
+    enabled: bool,
+    /// First and second proc IDs of the matching processes, with the matching subsystem identifiers if filtering by subsystem
+    processes: HashMap<(u64, u32), Option<HashSet<u16>>>,
+}
+
+impl ProcessFilter {
+    /// Check if no process in the catalog can match
+    pub(crate) fn is_empty(&self) -> bool {
+        self.enabled && self.processes.is_empty()
+    }
+
+    /// Check if a firehose preamble belongs to a matching process
+    pub(crate) fn includes_process(&self, first_proc_id: u64, second_proc_id: u32) -> bool {
+        !self.enabled
+            || self
+                .processes
+                .contains_key(&(first_proc_id, second_proc_id))
+    }
+
+    /// Check if a firehose entry subsystem identifier matches. Entries without a subsystem have an identifier of 0
+    pub(crate) fn includes_subsystem(
+        &self,
+        first_proc_id: u64,
+        second_proc_id: u32,
+        subsystem_value: u16,
+    ) -> bool {
+        if !self.enabled {
+            return true;
+        }
+        match self.processes.get(&(first_proc_id, second_proc_id)) {
+            Some(Some(identifiers)) => identifiers.contains(&subsystem_value),
+            Some(None) => true,
+            None => false,
+        }
+    }
+}
+
+/// Query time bounds for a single `tracev3` file, in Mach continuous time
+#[derive(Debug, Clone)]
+pub(crate) struct TimeWindow {
+    start: u64,
+    end: u64,
+    since: Option<QueryTime>,
+    until: Option<QueryTime>,
+    timebase: f64,
+}
+
+impl TimeWindow {
+    /// Check if a continuous time range overlaps the window
+    pub(crate) fn overlaps(&self, first: u64, last: u64) -> bool {
+        first <= self.end && last >= self.start
+    }
+
+    /// Check if the tracev3 file has data in the window. The file starts at the header continuous time and ends at the last catalog subchunk
+    pub(crate) fn includes_file(
+        &self,
+        unified_log_data: &UnifiedLogData,
+        catalogs: &[UnifiedLogCatalogData],
+    ) -> bool {
+        let Some(header) = unified_log_data.header.first() else {
+            return true;
+        };
+        let last = catalogs
+            .iter()
+            .map(|catalog_data| catalog_end(&catalog_data.catalog))
+            .max()
+            .unwrap_or(u64::MAX);
+        self.overlaps(header.continous_time, last)
+    }
+
+    /// Check if the catalog has data in the window using the earliest firehose timestamp and the subchunk ranges
+    pub(crate) fn includes_catalog(&self, catalog: &CatalogChunk) -> bool {
+        if catalog.catalog_subchunks.is_empty() {
+            return catalog.earliest_firehose_timestamp <= self.end;
+        }
+        catalog
+            .catalog_subchunks
+            .iter()
+            .any(|subchunk| self.overlaps(subchunk.start, subchunk.end))
+            && catalog.earliest_firehose_timestamp <= self.end
+    }
+
+    /// Check if a firehose preamble may have entries in the window. Entries are never earlier than the preamble base time,
+    /// and never later than the end of the chunkset (catalog subchunk) containing the base time
+    pub(crate) fn includes_preamble(
+        &self,
+        catalog: &CatalogChunk,
+        base_continous_time: u64,
+    ) -> bool {
+        if base_continous_time > self.end {
+            return false;
+        }
+        let chunkset_end = catalog
+            .catalog_subchunks
+            .iter()
+            .filter(|subchunk| {
+                subchunk.start <= base_continous_time && base_continous_time <= subchunk.end
+            })
+            .map(|subchunk| subchunk.end)
+            .max();
+        match chunkset_end {
+            Some(end) => end >= self.start,
+            None => true,
+        }
+    }
+
+    /// Check if a firehose entry continuous time is in the window
+    pub(crate) fn includes_continuous_time(&self, continuous_time: u64) -> bool {
+        self.start <= continuous_time && continuous_time <= self.end
+    }
+
+    /// Exact check against the query bounds once the entry is built
+    pub(crate) fn includes(&self, log_data: &LogData) -> bool {
+        let boot_time = log_data.continuous_time as f64 * self.timebase;
+        let after_since = match self.since {
+            Some(QueryTime::WallClock(time)) => log_data.time >= time as f64,
+            Some(QueryTime::BootRelative(time)) => boot_time >= time as f64,
+            None => true,
+        };
+        let before_until = match self.until {
+            Some(QueryTime::WallClock(time)) => log_data.time <= time as f64,
+            Some(QueryTime::BootRelative(time)) => boot_time <= time as f64,
+            None => true,
+        };
+        after_since && before_until
+    }
+}
+
+/// Last continuous time in a catalog, unknown if there are no subchunks
+fn catalog_end(catalog: &CatalogChunk) -> u64 {
+    catalog
+        .catalog_subchunks
+        .iter()
+        .map(|subchunk| subchunk.end)
+        .max()
+        .unwrap_or(u64::MAX)
+}
+
+/// Apple Silicon uses a 125/3 timebase, Intel uses 1/1
+fn timebase_adjustment(timesync_data: &[TimesyncBoot], boot_uuid: &str) -> f64 {
+    let apple_silicon = timesync_data.iter().any(|timesync| {
+        timesync.boot_uuid == boot_uuid
+            && timesync.timebase_numerator == 125
+            && timesync.timebase_denominator == 3
+    });
+    if apple_silicon {
+        125.0 / 3.0
+    } else {
+        1.0
+    }
+}
+
+/// Convert a query time to Mach continuous time. This is the inverse of `TimesyncBoot::get_timestamp`.
+/// Returns `None` if there is no timesync data for the boot
+fn continuous_time(
+    timesync_data: &[TimesyncBoot],
+    boot_uuid: &str,
+    time: QueryTime,
+    timebase: f64,
+) -> Option<u64> {
+    let wall_time = match time {
+        QueryTime::BootRelative(nanoseconds) => {
+            return Some((nanoseconds as f64 / timebase) as u64)
+        }
+        QueryTime::WallClock(nanoseconds) => nanoseconds,
+    };
+
+    let mut base: Option<(u64, i64)> = None;
+    for timesync in timesync_data {
+        if timesync.boot_uuid != boot_uuid {
+            continue;
+        }
+        if base.is_none() {
+            base = Some((0, timesync.boot_time));
+        }
+        // Use the last timesync record at or before the wall time
+        for record in &timesync.timesync {
+            if record.walltime > wall_time {
+                break;
+            }
+            base = Some((record.kernel_time, record.walltime));
+        }
+    }
+
+    let (kernel_time, walltime) = base?;
+    let continuous = kernel_time as f64 + (wall_time - walltime) as f64 / timebase;
+    Some(continuous.max(0.0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{continuous_time, LogQuery, QueryTime};
+    use crate::catalog::{test_catalog, test_process_info};
+    use crate::header::HeaderChunk;
+    use crate::timesync::{Timesync, TimesyncBoot};
+    use crate::unified_log::{LogData, UnifiedLogData};
+    use crate::uuidtext::UUIDText;
+
+    const BOOT_UUID: &str = "80D194AF56A34C54867449D2130D41BB";
+
+    fn test_timesync() -> Vec<TimesyncBoot> {
+        vec![TimesyncBoot {
+            signature: 0xbbb0,
+            header_size: 48,
+            unknown: 0,
+            boot_uuid: String::from(BOOT_UUID),
+            timebase_numerator: 125,
+            timebase_denominator: 3,
+            boot_time: 1642300000000000000,
+            timezone_offset_mins: 0,
+            daylight_savings: 0,
+            timesync: vec![Timesync {
+                signature: 0x207354,
+                unknown_flags: 0,
+                kernel_time: 24000000000,
+                walltime: 1642302000000000000,
+                timezone: 0,
+                daylight_savings: 0,
+            }],
+        }]
+    }
+
+    fn test_unified_log() -> UnifiedLogData {
+        UnifiedLogData {
+            header: vec![HeaderChunk {
+                boot_uuid: String::from(BOOT_UUID),
+                continous_time: 24000000000,
+                ..Default::default()
+            }],
+            catalog_data: Vec::new(),
+            oversize: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_query_time_parse() {
+        assert_eq!(
+            QueryTime::parse("2022-01-16T03:05:26Z").unwrap(),
+            QueryTime::WallClock(1642302326000000000)
+        );
+        assert_eq!(
+            QueryTime::parse("2022-01-16 03:05:26.5").unwrap(),
+            QueryTime::WallClock(1642302326500000000)
+        );
+        assert!(QueryTime::parse("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_continuous_time() {
+        let timesync = test_timesync();
+        let timebase = 125.0 / 3.0;
+        // 3 seconds after the timesync record
+        let result = continuous_time(
+            &timesync,
+            BOOT_UUID,
+            QueryTime::WallClock(1642302003000000000),
+            timebase,
+        )
+        .unwrap();
+        assert_eq!(result, 24072000000);
+        // Before the first record the boot time is used
+        let result = continuous_time(
+            &timesync,
+            BOOT_UUID,
+            QueryTime::WallClock(1642300000000000000 + 125),
+            timebase,
+        )
+        .unwrap();
+        assert_eq!(result, 3);
+        let result = continuous_time(
+            &timesync,
+            BOOT_UUID,
+            QueryTime::BootRelative(1250),
+            timebase,
+        );
+        assert_eq!(result, Some(30));
+        let result = continuous_time(&timesync, "unknown", QueryTime::WallClock(1), timebase);
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_time_window_pushdown() {
+        let query = LogQuery {
+            since: Some(QueryTime::WallClock(1642302100000000000)),
+            until: Some(QueryTime::WallClock(1642302200000000000)),
+            ..Default::default()
+        };
+        let window = query.time_window(&test_unified_log(), &test_timesync());
+
+        // 100 to 200 seconds after the timesync record, minus/plus one second of slack
+        let start = 24000000000 + 99 * 24000000;
+        let end = 24000000000 + 201 * 24000000;
+        assert!(window.includes_continuous_time(start));
+        assert!(!window.includes_continuous_time(start - 1));
+        assert!(window.includes_continuous_time(end));
+        assert!(!window.includes_continuous_time(end + 1));
+
+        assert!(window.includes_catalog(&test_catalog(start - 100, start + 100)));
+        assert!(!window.includes_catalog(&test_catalog(0, start - 1)));
+        assert!(!window.includes_catalog(&test_catalog(end + 1, end + 100)));
+
+        let catalog = test_catalog(start - 1000, start - 1);
+        assert!(!window.includes_preamble(&catalog, start - 500));
+        assert!(!window.includes_preamble(&catalog, end + 1));
+        assert!(window.includes_preamble(&catalog, 1));
+
+        let mut unified_log = test_unified_log();
+        assert!(window.includes_file(&unified_log, &[]));
+        unified_log.header[0].continous_time = end + 1;
+        assert!(!window.includes_file(&unified_log, &[]));
+    }
+
+    #[test]
+    fn test_time_window_includes() {
+        let query = LogQuery {
+            since: Some(QueryTime::BootRelative(1000)),
+            until: Some(QueryTime::WallClock(1642302200000000000)),
+            ..Default::default()
+        };
+        let window = query.time_window(&test_unified_log(), &test_timesync());
+
+        let mut log_data = LogData {
+            time: 1642302200000000000.0,
+            continuous_time: 25,
+            event_type: String::from("Log"),
+            log_type: String::from("Default"),
+            boot_uuid: String::from(BOOT_UUID),
+            ..Default::default()
+        };
+        assert!(window.includes(&log_data));
+        log_data.continuous_time = 23;
+        assert!(!window.includes(&log_data));
+        log_data.continuous_time = 25;
+        log_data.time += 1.0e9;
+        assert!(!window.includes(&log_data));
+    }
+
+    #[test]
+    fn test_process_filter() {
+        let mut catalog = test_catalog(0, 100);
+        catalog.catalog_process_info_entries = vec![
+            test_process_info(45, "6C3ADF991F033C1C96C4ADFAA12D8CED"),
+            test_process_info(90, "80D194AF56A34C54867449D2130D41BB"),
+        ];
+        let strings = vec![UUIDText {
+            uuid: String::from("1F033C1C96C4ADFAA12D8CED"),
+            signature: 0x66778899,
+            unknown_major_version: 2,
+            unknown_minor_version: 1,
+            number_entries: 0,
+            entry_descriptors: Vec::new(),
+            footer_data: b"/usr/libexec/powerd\0".to_vec(),
+            bh_pos: 0,
+        }];
+
+        let filter = LogQuery::default().process_filter(&catalog, &strings);
+        assert!(!filter.is_empty());
+        assert!(filter.includes_process(1, 1));
+        assert!(filter.includes_subsystem(1, 1, 0));
+
+        let query = LogQuery {
+            pids: vec![90],
+            ..Default::default()
+        };
+        let filter = query.process_filter(&catalog, &strings);
+        assert!(!filter.includes_process(450, 45));
+        assert!(filter.includes_process(900, 90));
+
+        let query = LogQuery {
+            processes: vec![String::from("powerd")],
+            subsystems: vec![String::from("com.apple.lom")],
+            ..Default::default()
+        };
+        let filter = query.process_filter(&catalog, &strings);
+        assert!(filter.includes_process(450, 45));
+        // Image path of pid 90 is unknown, checked once the entry is built
+        assert!(filter.includes_process(900, 90));
+        assert!(filter.includes_subsystem(450, 45, 2));
+        assert!(!filter.includes_subsystem(450, 45, 1));
+        assert!(!filter.includes_subsystem(450, 45, 0));
+
+        let query = LogQuery {
+            process_uuids: vec![String::from("6C3ADF99-1F03-3C1C-96C4-ADFAA12D8CED")],
+            categories: vec![String::from("battery")],
+            ..Default::default()
+        };
+        let filter = query.process_filter(&catalog, &strings);
+        assert!(filter.includes_subsystem(450, 45, 1));
+        assert!(!filter.includes_process(900, 90));
+
+        let query = LogQuery {
+            euids: vec![501],
+            ..Default::default()
+        };
+        assert!(query.process_filter(&catalog, &strings).is_empty());
+    }
+}
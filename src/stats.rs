@@ -0,0 +1,515 @@
+// Copyright 2022 Mandiant, Inc. All Rights Reserved
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+// http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and limitations under the License.
+
+//! Archive statistics similar to `log stats`
+//!
+//! Counts are computed in a single pass over the parsed chunksets. Format strings are resolved to detect unresolved strings, but messages are never formatted
+
+use crate::catalog::CatalogChunk;
+use crate::chunks::firehose::activity::FirehoseActivity;
+use crate::chunks::firehose::firehose_log::{Firehose, FirehosePreamble};
+use crate::chunks::firehose::message::MessageData;
+use crate::chunks::firehose::nonactivity::FirehoseNonActivity;
+use crate::chunks::firehose::signpost::FirehoseSignpost;
+use crate::chunks::firehose::trace::FirehoseTrace;
+use crate::chunks::oversize::Oversize;
+use crate::dsc::SharedCacheStrings;
+use crate::timesync::TimesyncBoot;
+use crate::unified_log::{LogData, UnifiedLogCatalogData, UnifiedLogData};
+use crate::util::unixepoch_to_local;
+use crate::uuidtext::UUIDText;
+use chrono::{Offset, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Entry counts of one or more `tracev3` files
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LogStats {
+    pub total_entries: u64,
+    pub files: u64,
+    pub log_types: BTreeMap<String, u64>,
+    pub event_types: BTreeMap<String, u64>,
+    /// Entries per process image path. The process UUID is used if the `UUIDText` file is missing
+    pub processes: BTreeMap<String, u64>,
+    /// Entries per subsystem and category
+    pub subsystems: BTreeMap<String, BTreeMap<String, u64>>,
+    pub boots: BTreeMap<String, u64>,
+    /// Entries per UTC hour (ex: `2022-01-16T03:00:00Z`)
+    pub hourly: BTreeMap<String, u64>,
+    /// Entries whose format string could not be found in the `UUIDText` or shared cache files
+    pub unresolved_strings: u64,
+    /// Entries whose Oversize data is not in the same `tracev3` file
+    pub missing_data: u64,
+}
+
+/// Values counted for a single entry
+struct StatsEntry<'a> {
+    log_type: String,
+    event_type: String,
+    process: &'a str,
+    subsystem: &'a str,
+    category: &'a str,
+    boot_uuid: &'a str,
+    time: f64,
+    unresolved: bool,
+    missing: bool,
+}
+
+impl LogStats {
+    pub fn new() -> LogStats {
+        LogStats::default()
+    }
+
+    /// Add the entries of a parsed `tracev3` file
+    pub fn add_unified_log(
+        &mut self,
+        unified_log_data: &UnifiedLogData,
+        strings_data: &[UUIDText],
+        shared_strings: &[SharedCacheStrings],
+        timesync_data: &[TimesyncBoot],
+    ) {
+        let Some(header) = unified_log_data.header.first() else {
+            return;
+        };
+        self.files += 1;
+
+        for catalog_data in &unified_log_data.catalog_data {
+            let processes = catalog_processes(&catalog_data.catalog, strings_data);
+            let mut subsystems: HashMap<(u64, u32, u16), (String, String)> = HashMap::new();
+
+            for preamble in &catalog_data.firehose {
+                let process_key = (
+                    preamble.first_number_proc_id,
+                    preamble.second_number_proc_id,
+                );
+                let process = processes
+                    .get(&process_key)
+                    .map_or("Unknown process", String::as_str);
+
+                for firehose in &preamble.public_data {
+                    let continuous_time = preamble.base_continous_time
+                        + (u64::from(firehose.continous_time_delta)
+                            | (u64::from(firehose.continous_time_delta_upper) << 32));
+
+                    let subsystem_value = match firehose.unknown_log_activity_type {
+                        0x4 => firehose.firehose_non_activity.subsystem_value,
+                        0x6 => firehose.firehose_signpost.subsystem,
+                        _ => 0,
+                    };
+                    let (subsystem, category) = if subsystem_value == 0 {
+                        ("", "")
+                    } else {
+                        let (subsystem, category) = subsystems
+                            .entry((process_key.0, process_key.1, subsystem_value))
+                            .or_insert_with(|| {
+                                CatalogChunk::get_subsystem(
+                                    &subsystem_value,
+                                    &preamble.first_number_proc_id,
+                                    &preamble.second_number_proc_id,
+                                    &catalog_data.catalog,
+                                )
+                                .map(|(_, info)| (info.subsystem, info.category))
+                                .unwrap_or_default()
+                            });
+                        (subsystem.as_str(), category.as_str())
+                    };
+
+                    let (unresolved, missing) = check_strings(
+                        firehose,
+                        preamble,
+                        catalog_data,
+                        &unified_log_data.oversize,
+                        strings_data,
+                        shared_strings,
+                    );
+
+                    self.count_entry(StatsEntry {
+                        log_type: LogData::get_log_type(
+                            &firehose.unknown_log_type,
+                            &firehose.unknown_log_activity_type,
+                        ),
+                        event_type: LogData::get_event_type(&firehose.unknown_log_activity_type),
+                        process,
+                        subsystem,
+                        category,
+                        boot_uuid: &header.boot_uuid,
+                        time: TimesyncBoot::get_timestamp(
+                            timesync_data,
+                            &header.boot_uuid,
+                            continuous_time,
+                            preamble.base_continous_time,
+                        ),
+                        unresolved,
+                        missing,
+                    });
+                }
+            }
+
+            let no_firehose_preamble = 1;
+            for simpledump in &catalog_data.simpledump {
+                self.count_entry(StatsEntry {
+                    log_type: String::new(),
+                    event_type: String::from("Simpledump"),
+                    process: processes
+                        .get(&(simpledump.first_proc_id, simpledump.second_proc_id as u32))
+                        .map_or("Unknown process", String::as_str),
+                    subsystem: &simpledump.subsystem,
+                    category: "",
+                    boot_uuid: &header.boot_uuid,
+                    time: TimesyncBoot::get_timestamp(
+                        timesync_data,
+                        &header.boot_uuid,
+                        simpledump.continous_time,
+                        no_firehose_preamble,
+                    ),
+                    unresolved: false,
+                    missing: false,
+                });
+            }
+            for statedump in &catalog_data.statedump {
+                self.count_entry(StatsEntry {
+                    log_type: String::new(),
+                    event_type: String::from("Statedump"),
+                    process: processes
+                        .get(&(statedump.first_proc_id, statedump.second_proc_id))
+                        .map_or("Unknown process", String::as_str),
+                    subsystem: "",
+                    category: "",
+                    boot_uuid: &header.boot_uuid,
+                    time: TimesyncBoot::get_timestamp(
+                        timesync_data,
+                        &header.boot_uuid,
+                        statedump.continuous_time,
+                        no_firehose_preamble,
+                    ),
+                    unresolved: false,
+                    missing: false,
+                });
+            }
+        }
+    }
+
+    /// Combine the counts of another `LogStats` (ex: computed in another thread)
+    pub fn merge(&mut self, other: &LogStats) {
+        self.total_entries += other.total_entries;
+        self.files += other.files;
+        self.unresolved_strings += other.unresolved_strings;
+        self.missing_data += other.missing_data;
+        merge_counts(&mut self.log_types, &other.log_types);
+        merge_counts(&mut self.event_types, &other.event_types);
+        merge_counts(&mut self.processes, &other.processes);
+        merge_counts(&mut self.boots, &other.boots);
+        merge_counts(&mut self.hourly, &other.hourly);
+        for (subsystem, categories) in &other.subsystems {
+            merge_counts(
+                self.subsystems.entry(subsystem.to_owned()).or_default(),
+                categories,
+            );
+        }
+    }
+
+    fn count_entry(&mut self, entry: StatsEntry<'_>) {
+        self.total_entries += 1;
+        increment(&mut self.log_types, &entry.log_type);
+        increment(&mut self.event_types, &entry.event_type);
+        increment(&mut self.processes, entry.process);
+        increment(&mut self.boots, entry.boot_uuid);
+        if !entry.subsystem.is_empty() {
+            increment(
+                self.subsystems
+                    .entry(entry.subsystem.to_string())
+                    .or_default(),
+                entry.category,
+            );
+        }
+        if entry.time > 0.0 {
+            let hour = unixepoch_to_local(&entry.time, &Utc.fix(), "%Y-%m-%dT%H:00:00Z");
+            increment(&mut self.hourly, &hour);
+        }
+        if entry.unresolved {
+            self.unresolved_strings += 1;
+        }
+        if entry.missing {
+            self.missing_data += 1;
+        }
+    }
+}
+
+fn increment(counts: &mut BTreeMap<String, u64>, key: &str) {
+    match counts.get_mut(key) {
+        Some(count) => *count += 1,
+        None => {
+            counts.insert(key.to_string(), 1);
+        }
+    }
+}
+
+fn merge_counts(counts: &mut BTreeMap<String, u64>, other: &BTreeMap<String, u64>) {
+    for (key, value) in other {
+        *counts.entry(key.to_owned()).or_default() += value;
+    }
+}
+
+/// Resolve the image path of each process in the Catalog once
+fn catalog_processes(
+    catalog: &CatalogChunk,
+    strings_data: &[UUIDText],
+) -> HashMap<(u64, u32), String> {
+    let mut processes = HashMap::new();
+    for process_info in &catalog.catalog_process_info_entries {
+        let has_uuidtext = strings_data
+            .iter()
+            .any(|data| process_info.main_uuid.ends_with(&data.uuid));
+        let process = if has_uuidtext {
+            MessageData::get_uuid_image_path(&process_info.main_uuid, strings_data)
+                .map(|(_, path)| path)
+                .unwrap_or_else(|_| process_info.main_uuid.to_owned())
+        } else {
+            process_info.main_uuid.to_owned()
+        };
+        processes.insert(
+            (
+                process_info.first_number_proc_id,
+                process_info.second_number_proc_id,
+            ),
+            process,
+        );
+    }
+    processes
+}
+
+/// Check if the format string of an entry can be resolved and if its Oversize data is missing
+fn check_strings(
+    firehose: &Firehose,
+    preamble: &FirehosePreamble,
+    catalog_data: &UnifiedLogCatalogData,
+    oversize: &Vec<Oversize>,
+    strings_data: &[UUIDText],
+    shared_strings: &[SharedCacheStrings],
+) -> (bool, bool) {
+    let string_offset = u64::from(firehose.format_string_location);
+    let first_proc_id = &preamble.first_number_proc_id;
+    let second_proc_id = &preamble.second_number_proc_id;
+    let catalog = &catalog_data.catalog;
+
+    let (message_data, data_ref) = match firehose.unknown_log_activity_type {
+        0x4 => (
+            FirehoseNonActivity::get_firehose_nonactivity_strings(
+                &firehose.firehose_non_activity,
+                strings_data,
+                shared_strings,
+                string_offset,
+                first_proc_id,
+                second_proc_id,
+                catalog,
+            ),
+            firehose.firehose_non_activity.data_ref_value,
+        ),
+        0x2 => (
+            FirehoseActivity::get_firehose_activity_strings(
+                &firehose.firehose_activity,
+                strings_data,
+                shared_strings,
+                string_offset,
+                first_proc_id,
+                second_proc_id,
+                catalog,
+            ),
+            0,
+        ),
+        0x6 => (
+            FirehoseSignpost::get_firehose_signpost(
+                &firehose.firehose_signpost,
+                strings_data,
+                shared_strings,
+                string_offset,
+                first_proc_id,
+                second_proc_id,
+                catalog,
+            ),
+            firehose.firehose_signpost.data_ref_value,
+        ),
+        0x3 => (
+            FirehoseTrace::get_firehose_trace_strings(
+                strings_data,
+                string_offset,
+                first_proc_id,
+                second_proc_id,
+                catalog,
+            ),
+            0,
+        ),
+        // Loss entries do not have a format string
+        _ => return (false, false),
+    };
+
+    let unresolved = match message_data {
+        Ok((_, results)) => is_unresolved_format(&results.format_string),
+        Err(_) => true,
+    };
+    let missing = data_ref != 0
+        && Oversize::get_oversize_strings(
+            u32::from(data_ref),
+            *first_proc_id,
+            *second_proc_id,
+            oversize,
+        )
+        .is_empty();
+    (unresolved, missing)
+}
+
+/// Format string placeholders returned by `MessageData` when the string was not found
+fn is_unresolved_format(format_string: &str) -> bool {
+    format_string.starts_with("Error: Invalid")
+        || format_string.starts_with("Failed to get string message")
+        || format_string == "Unknown shared string message"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_unresolved_format, LogStats, StatsEntry};
+    use crate::catalog::CatalogChunk;
+    use crate::chunks::simpledump::SimpleDump;
+    use crate::header::HeaderChunk;
+    use crate::unified_log::{UnifiedLogCatalogData, UnifiedLogData};
+
+    const BOOT_UUID: &str = "80D194AF56A34C54867449D2130D41BB";
+
+    fn test_entry(log_type: &str, subsystem: &'static str, time: f64) -> StatsEntry<'static> {
+        StatsEntry {
+            log_type: log_type.to_string(),
+            event_type: String::from("Log"),
+            process: "/usr/libexec/lightsoutmanagementd",
+            subsystem,
+            category: "device",
+            boot_uuid: BOOT_UUID,
+            time,
+            unresolved: false,
+            missing: false,
+        }
+    }
+
+    fn test_catalog() -> CatalogChunk {
+        CatalogChunk {
+            chunk_tag: 0x600b,
+            chunk_sub_tag: 0,
+            chunk_data_size: 0,
+            catalog_subsystem_strings_offset: 0,
+            catalog_process_info_entries_offset: 0,
+            number_process_information_entries: 0,
+            catalog_offset_sub_chunks: 0,
+            number_sub_chunks: 0,
+            unknown: Vec::new(),
+            earliest_firehose_timestamp: 0,
+            catalog_uuids: Vec::new(),
+            catalog_subsystem_strings: Vec::new(),
+            catalog_process_info_entries: Vec::new(),
+            catalog_subchunks: Vec::new(),
+        }
+    }
+
+    fn test_simpledump() -> SimpleDump {
+        SimpleDump {
+            chunk_tag: 0x6004,
+            chunk_subtag: 0,
+            chunk_data_size: 0,
+            first_proc_id: 1,
+            second_proc_id: 2,
+            continous_time: 1,
+            thread_id: 0,
+            unknown_offset: 0,
+            unknown_ttl: 0,
+            unknown_type: 0,
+            sender_uuid: String::new(),
+            dsc_uuid: String::new(),
+            unknown_number_message_strings: 0,
+            unknown_size_subsystem_string: 0,
+            unknown_size_message_string: 0,
+            subsystem: String::from("com.apple.SimpleDump"),
+            message_string: String::from("simpledump message"),
+        }
+    }
+
+    #[test]
+    fn test_count_entry() {
+        let mut stats = LogStats::new();
+        stats.count_entry(test_entry(
+            "Default",
+            "com.apple.lom",
+            1.6423023264348508e18,
+        ));
+        stats.count_entry(test_entry("Error", "com.apple.lom", 1.6423023264348508e18));
+        let mut entry = test_entry("Default", "", 1.6423059264348508e18);
+        entry.unresolved = true;
+        entry.missing = true;
+        stats.count_entry(entry);
+
+        assert_eq!(stats.total_entries, 3);
+        assert_eq!(stats.log_types["Default"], 2);
+        assert_eq!(stats.log_types["Error"], 1);
+        assert_eq!(stats.event_types["Log"], 3);
+        assert_eq!(stats.processes["/usr/libexec/lightsoutmanagementd"], 3);
+        assert_eq!(stats.subsystems["com.apple.lom"]["device"], 2);
+        assert_eq!(stats.boots[BOOT_UUID], 3);
+        assert_eq!(stats.hourly["2022-01-16T03:00:00Z"], 2);
+        assert_eq!(stats.hourly["2022-01-16T04:00:00Z"], 1);
+        assert_eq!(stats.unresolved_strings, 1);
+        assert_eq!(stats.missing_data, 1);
+
+        let mut combined = LogStats::new();
+        combined.merge(&stats);
+        combined.merge(&stats);
+        assert_eq!(combined.total_entries, 6);
+        assert_eq!(combined.subsystems["com.apple.lom"]["device"], 4);
+        assert_eq!(combined.hourly["2022-01-16T04:00:00Z"], 2);
+    }
+
+    #[test]
+    fn test_add_unified_log() {
+        let unified_log_data = UnifiedLogData {
+            header: vec![HeaderChunk {
+                boot_uuid: String::from(BOOT_UUID),
+                ..Default::default()
+            }],
+            catalog_data: vec![UnifiedLogCatalogData {
+                catalog: test_catalog(),
+                firehose: Vec::new(),
+                simpledump: vec![test_simpledump(), test_simpledump()],
+                statedump: Vec::new(),
+                oversize: Vec::new(),
+            }],
+            oversize: Vec::new(),
+        };
+
+        let mut stats = LogStats::new();
+        stats.add_unified_log(&unified_log_data, &[], &[], &[]);
+        assert_eq!(stats.files, 1);
+        assert_eq!(stats.total_entries, 2);
+        assert_eq!(stats.event_types["Simpledump"], 2);
+        assert_eq!(stats.processes["Unknown process"], 2);
+        assert_eq!(stats.subsystems["com.apple.SimpleDump"][""], 2);
+        assert_eq!(stats.hourly["1970-01-01T00:00:00Z"], 2);
+
+        let no_header = UnifiedLogData {
+            header: Vec::new(),
+            catalog_data: Vec::new(),
+            oversize: Vec::new(),
+        };
+        stats.add_unified_log(&no_header, &[], &[], &[]);
+        assert_eq!(stats.files, 1);
+    }
+
+    #[test]
+    fn test_is_unresolved_format() {
+        assert!(is_unresolved_format("Error: Invalid shared string offset"));
+        assert!(is_unresolved_format(
+            "Failed to get string message from UUIDText file: 6C3ADF991F033C1C96C4ADFAA12D8CED"
+        ));
+        assert!(is_unresolved_format("Unknown shared string message"));
+        assert!(!is_unresolved_format("LOMD Start %d"));
+    }
+}
@@ -10,24 +10,29 @@
 //! Provides a simple library to parse the macOS Unified Log format.
 
 use crate::catalog::CatalogChunk;
-// use crate::chunks::firehose::activity::FirehoseActivity;
+use crate::chunks::firehose::activity::FirehoseActivity;
 use crate::chunks::firehose::firehose_log::{Firehose, FirehoseItemInfo, FirehosePreamble};
 use crate::chunks::firehose::nonactivity::FirehoseNonActivity;
-// use crate::chunks::firehose::signpost::FirehoseSignpost;
+use crate::chunks::firehose::signpost::FirehoseSignpost;
 // use crate::chunks::firehose::trace::FirehoseTrace;
 use crate::chunks::oversize::Oversize;
 use crate::chunks::simpledump::SimpleDump;
 use crate::chunks::statedump::Statedump;
 use crate::chunkset::ChunksetChunk;
+use crate::decoders::decoder::{DecoderRegistry, FormatOptions};
+use crate::decoders::objects::ObjectDecoderRegistry;
+use crate::decoders::protobuf::ProtobufSchema;
 use crate::dsc::SharedCacheStrings;
 use crate::header::HeaderChunk;
 use crate::message::format_firehose_log_message;
 use crate::preamble::LogPreamble;
+use crate::query::LogQuery;
 use crate::timesync::TimesyncBoot;
 use log::{error, warn};
 use nom::bytes::complete::take;
 use regex::Regex;
 use serde::Serialize;
+use std::collections::HashMap;
 
 use crate::util::{extract_string, padding_size};
 use crate::uuidtext::UUIDText;
@@ -48,7 +53,32 @@ pub struct UnifiedLogCatalogData {
     pub oversize: Vec<Oversize>,
 }
 
-#[derive(Debug, Serialize)]
+/// Options for reconstructing log entries with `build_log`
+#[derive(Default)]
+pub struct LogOptions {
+    /// Exclude entries whose Oversize data was not found and return them as leftover `UnifiedLogData` instead. The data may be stored in another tracev3 file
+    pub exclude_missing: bool,
+    /// Only build the entries that match the query
+    pub query: LogQuery,
+    /// Decoders for `%{...}` annotated values
+    pub decoders: DecoderRegistry,
+    /// How private and binary values are shown in the message
+    pub format: FormatOptions,
+    /// Also build the Statedump entries of each Catalog
+    pub statedumps: bool,
+    /// Also build the activity create entries (0x2). The message is the activity name
+    pub activities: bool,
+    /// Also build the signpost entries (0x6). Signposts are used by `exporters::trace_event`
+    pub signposts: bool,
+    /// Decoders for Statedump custom objects
+    pub objects: ObjectDecoderRegistry,
+    /// Schema used to decode Statedump protocol buffers by field name. Only used for the Statedumps in `protobuf_types`
+    pub protobuf_schema: Option<ProtobufSchema>,
+    /// Protocol buffer message type of each Statedump name (ex: `BatteryState` to `com.apple.Example`). Other protocol buffers are decoded without a schema
+    pub protobuf_types: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Default)]
 pub struct LogData {
     pub subsystem: String,
     pub thread_id: u64,
@@ -57,7 +87,17 @@ pub struct LogData {
     pub library: String,
     pub library_uuid: String,
     pub activity_id: u64,
+    /// Signpost ID of signpost entries (0 for other entries)
+    pub signpost_id: u64,
+    /// Signpost name of signpost entries (0 for other entries)
+    pub signpost_name: u32,
+    /// Time to live of the entry in days (0 if not set)
+    pub ttl: u8,
     pub time: f64,
+    /// Mach continuous time of the entry (ticks since boot, including sleep)
+    pub continuous_time: u64,
+    /// Firehose tracepoint ID (activity type, log type, flags and format string location)
+    pub trace_id: u64,
     pub category: String,
     pub event_type: String,
     pub log_type: String,
@@ -67,12 +107,24 @@ pub struct LogData {
     pub raw_message: String,
     pub boot_uuid: String,
     pub timezone_name: String,
+    /// Message items. `message_value` is the typed value of the item (ex: `%u` -> `MessageValue::Unsigned`)
     pub message_entries: Vec<FirehoseItemInfo>,
 }
 
 impl LogData {
     /// Parse the Unified log data read from a tracev3 file
     pub fn parse_unified_log(data: &[u8], bh_offset: u32, fl_offset: u32) -> nom::IResult<&[u8], UnifiedLogData> {
+        LogData::parse_unified_log_chunks(data, bh_offset, fl_offset, |_, _| {})
+    }
+
+    /// Parse the Unified log data read from a tracev3 file and call `on_chunkset` after each chunkset is parsed
+    /// `on_chunkset` gets the data parsed so far (header and Oversize entries) and the Catalog of the chunkset. The firehose entries of the chunkset are appended to the Catalog
+    pub(crate) fn parse_unified_log_chunks<F: FnMut(&UnifiedLogData, &mut UnifiedLogCatalogData)>(
+        data: &[u8],
+        bh_offset: u32,
+        fl_offset: u32,
+        mut on_chunkset: F,
+    ) -> nom::IResult<&[u8], UnifiedLogData> {
         let mut unified_log_data_true = UnifiedLogData {
             header: Vec::new(),
             catalog_data: Vec::new(),
@@ -154,6 +206,7 @@ impl LogData {
                     bh_offset,
                     fl_offset,
                 );
+                on_chunkset(&unified_log_data_true, &mut catalog_data);
             } else {
                 error!(
                     "[macos-unifiedlogs] Unknown chunk type: {:?}",
@@ -185,17 +238,101 @@ impl LogData {
         Ok((input, unified_log_data_true))
     }
 
-    /// Reconstruct Unified Log entries using the binary strings data, cached strings data, timesync data, and unified log. Provide bool to ignore log entries that are not able to be recontructed (additional tracev3 files needed)
+    /// Reconstruct Unified Log entries using the binary strings data, cached strings data, timesync data, and unified log. See `LogOptions` for the query, decoders and whether to exclude entries that are not able to be recontructed (additional tracev3 files needed)
     /// Return a reconstructed log entries and any leftover Unified Log entries that could not be reconstructed (data may be stored in other tracev3 files)
     pub fn build_log(
         unified_log_data: &UnifiedLogData,
         strings_data: &[UUIDText],
         shared_strings: &[SharedCacheStrings],
         timesync_data: &[TimesyncBoot],
-        exclude_mssing: bool,
         batteryhealth_offset: u32,
         followup_string_offset: u32,
+        options: &LogOptions,
     ) -> (Vec<LogData>, UnifiedLogData) {
+        let mut log_data_vec: Vec<LogData> = Vec::new();
+        let missing_unified_log_data_vec = LogData::build_log_filtered(
+            unified_log_data,
+            &unified_log_data.catalog_data,
+            strings_data,
+            shared_strings,
+            timesync_data,
+            batteryhealth_offset,
+            followup_string_offset,
+            options,
+            |mut entries| log_data_vec.append(&mut entries),
+        );
+        (log_data_vec, missing_unified_log_data_vec)
+    }
+
+    /// Parse a tracev3 file and pass the reconstructed entries of each chunkset to `on_entries` as soon as the chunkset is decompressed, instead of parsing the whole file first.
+    /// Entries referencing Oversize data from a later chunkset or another tracev3 file are handled like data stored in another file (see `LogOptions.exclude_missing`)
+    /// Return the leftover Unified Log entries that could not be reconstructed, with the Oversize entries of the file so they can be built again once all files are parsed
+    #[allow(clippy::too_many_arguments)]
+    pub fn stream_log<'a, F: FnMut(Vec<LogData>)>(
+        data: &'a [u8],
+        strings_data: &[UUIDText],
+        shared_strings: &[SharedCacheStrings],
+        timesync_data: &[TimesyncBoot],
+        batteryhealth_offset: u32,
+        followup_string_offset: u32,
+        options: &LogOptions,
+        mut on_entries: F,
+    ) -> nom::IResult<&'a [u8], UnifiedLogData> {
+        let mut missing_unified_log_data_vec = UnifiedLogData {
+            header: Vec::new(),
+            catalog_data: Vec::new(),
+            oversize: Vec::new(),
+        };
+        let (input, unified_log_data) = LogData::parse_unified_log_chunks(
+            data,
+            batteryhealth_offset,
+            followup_string_offset,
+            |unified_log_data, catalog_data| {
+                let mut missing = LogData::build_log_filtered(
+                    unified_log_data,
+                    std::slice::from_ref(catalog_data),
+                    strings_data,
+                    shared_strings,
+                    timesync_data,
+                    batteryhealth_offset,
+                    followup_string_offset,
+                    options,
+                    &mut on_entries,
+                );
+                if !missing.catalog_data.is_empty() {
+                    missing_unified_log_data_vec.header = missing.header;
+                    missing_unified_log_data_vec
+                        .catalog_data
+                        .append(&mut missing.catalog_data);
+                }
+                // The entries of the chunkset are built, only keep the Catalog for the next chunkset
+                catalog_data.firehose.clear();
+                catalog_data.statedump.clear();
+            },
+        )?;
+        missing_unified_log_data_vec.oversize = unified_log_data.oversize;
+        Ok((input, missing_unified_log_data_vec))
+    }
+
+    /// Same as `build_log`, but instead of collecting every entry the reconstructed entries of each Catalog in `catalogs` are passed to `on_entries` as soon as they are built
+    /// Files, catalogs and firehose preambles outside of the query time range are skipped. Entries that do not match are not tracked as missing data
+    /// Return any leftover Unified Log entries that could not be reconstructed
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn build_log_filtered<F: FnMut(Vec<LogData>)>(
+        unified_log_data: &UnifiedLogData,
+        catalogs: &[UnifiedLogCatalogData],
+        strings_data: &[UUIDText],
+        shared_strings: &[SharedCacheStrings],
+        timesync_data: &[TimesyncBoot],
+        batteryhealth_offset: u32,
+        followup_string_offset: u32,
+        options: &LogOptions,
+        mut on_entries: F,
+    ) -> UnifiedLogData {
+        let exclude_mssing = options.exclude_missing;
+        let query = &options.query;
+        let decoders = &options.decoders;
+        let format_options = &options.format;
         let mut log_data_vec: Vec<LogData> = Vec::new();
         // Need to keep track of any log entries that fail to find Oversize strings (sometimes the strings may be in other log files that have not been parsed yet)
         let mut missing_unified_log_data_vec = UnifiedLogData {
@@ -238,12 +375,32 @@ impl LogData {
                     "Failed to compile regex for printf format parsing: {:?}",
                     err
                 );
-                return (log_data_vec, missing_unified_log_data_vec);
+                return missing_unified_log_data_vec;
             }
         };
 
-        for catalog_data in &unified_log_data.catalog_data {
+        let window = query.time_window(unified_log_data, timesync_data);
+        if !window.includes_file(unified_log_data, catalogs) {
+            return missing_unified_log_data_vec;
+        }
+
+        for catalog_data in catalogs {
+            if !window.includes_catalog(&catalog_data.catalog) {
+                continue;
+            }
+            let process_filter = query.process_filter(&catalog_data.catalog, strings_data);
+            if process_filter.is_empty() {
+                continue;
+            }
             for (preamble_index, preamble) in catalog_data.firehose.iter().enumerate() {
+                if !window.includes_preamble(&catalog_data.catalog, preamble.base_continous_time)
+                    || !process_filter.includes_process(
+                        preamble.first_number_proc_id,
+                        preamble.second_number_proc_id,
+                    )
+                {
+                    continue;
+                }
                 for (firehose_index, firehose) in preamble.public_data.iter().enumerate() {
                     // The continous time is actually 6 bytes long. Combining 4 bytes and 2 bytes
                    
@@ -267,6 +424,15 @@ impl LogData {
         
                                 let continous_time =
                                     preamble.base_continous_time + firehose_log_entry_continous_time;
+                                if !window.includes_continuous_time(continous_time)
+                                    || !process_filter.includes_subsystem(
+                                        preamble.first_number_proc_id,
+                                        preamble.second_number_proc_id,
+                                        firehose.firehose_non_activity.subsystem_value,
+                                    )
+                                {
+                                    continue;
+                                }
             
                                 // Calculate the timestamp for the log entry
                                 let timestamp = TimesyncBoot::get_timestamp(
@@ -287,7 +453,12 @@ impl LogData {
                                     ),
                                     library: String::new(),
                                     activity_id: 0,
+                                    signpost_id: 0,
+                                    signpost_name: 0,
+                                    ttl: firehose.firehose_non_activity.ttl_value,
                                     time: timestamp,
+                                    continuous_time: continous_time,
+                                    trace_id: LogData::get_trace_id(firehose),
                                     category: String::new(),
                                     log_type: LogData::get_log_type(
                                         &firehose.unknown_log_type,
@@ -338,30 +509,24 @@ impl LogData {
 
 
                                         // If the non-activity log entry has a data ref value then the message strings are stored in an oversize log entry
-                                        let log_message =
-                                            if firehose.firehose_non_activity.data_ref_value != 0 {
-                                                let oversize_strings = Oversize::get_oversize_strings(
-                                                    u32::from(
-                                                        firehose.firehose_non_activity.data_ref_value,
-                                                    ),
-                                                    preamble.first_number_proc_id,
-                                                    preamble.second_number_proc_id,
-                                                    &unified_log_data.oversize,
-                                                );
-                                                // Format and map the log strings with the message format string found UUIDText or shared string file
-                                                format_firehose_log_message(
-                                                    results.format_string,
-                                                    &oversize_strings,
-                                                    &message_re,
-                                                )
-                                            } else {
-                                                // Format and map the log strings with the message format string found UUIDText or shared string file
-                                                format_firehose_log_message(
-                                                    results.format_string,
-                                                    &firehose.message.item_info,
-                                                    &message_re,
-                                                )
-                                        };
+                                        if firehose.firehose_non_activity.data_ref_value != 0 {
+                                            log_data.message_entries = Oversize::get_oversize_strings(
+                                                u32::from(
+                                                    firehose.firehose_non_activity.data_ref_value,
+                                                ),
+                                                preamble.first_number_proc_id,
+                                                preamble.second_number_proc_id,
+                                                &unified_log_data.oversize,
+                                            );
+                                        }
+                                        // Format and map the log strings with the message format string found UUIDText or shared string file
+                                        let log_message = format_firehose_log_message(
+                                            results.format_string,
+                                            &mut log_data.message_entries,
+                                            &message_re,
+                                            decoders,
+                                            format_options,
+                                        );
 
                                         //if log_message.contains("Item approved") {
                                             //println!("{} {} {}",firehose.format_string_location, log_data.raw_message,  log_message);
@@ -399,8 +564,16 @@ impl LogData {
                                     }
                                 }
 
+                                log_data.add_subsystem(
+                                    firehose.firehose_non_activity.subsystem_value,
+                                    preamble.first_number_proc_id,
+                                    preamble.second_number_proc_id,
+                                    &catalog_data.catalog,
+                                );
 
-                                log_data_vec.push(log_data);
+                                if query.matches(&log_data, &window) {
+                                    log_data_vec.push(log_data);
+                                }
                             }
                         
                         }
@@ -408,8 +581,18 @@ impl LogData {
                         /* 0x7 => {
                             // No message data in loss entries
                             log_data.log_type = String::new();
-                        }
-                        0x2 => {
+                        }*/
+                        0x2 if options.activities => {
+                            let mut log_data = LogData::firehose_entry(
+                                unified_log_data,
+                                &catalog_data.catalog,
+                                preamble,
+                                firehose,
+                                timesync_data,
+                            );
+                            if !window.includes_continuous_time(log_data.continuous_time) {
+                                continue;
+                            }
                             log_data.activity_id =
                                 u64::from(firehose.firehose_activity.unknown_activity_id);
                             let message_data = FirehoseActivity::get_firehose_activity_strings(
@@ -431,8 +614,10 @@ impl LogData {
 
                                     let log_message = format_firehose_log_message(
                                         results.format_string,
-                                        &firehose.message.item_info,
+                                        &mut log_data.message_entries,
                                         &message_re,
+                                        decoders,
+                                        format_options,
                                     );
 
                                     if exclude_mssing
@@ -462,10 +647,33 @@ impl LogData {
                                     warn!("[macos-unifiedlogs] Failed to get message string data for firehose activity log entry: {:?}", err);
                                 }
                             }
+
+                            if query.matches(&log_data, &window) {
+                                log_data_vec.push(log_data);
+                            }
                         }
-                        0x6 => {
+                        0x6 if options.signposts => {
+                            let mut log_data = LogData::firehose_entry(
+                                unified_log_data,
+                                &catalog_data.catalog,
+                                preamble,
+                                firehose,
+                                timesync_data,
+                            );
+                            if !window.includes_continuous_time(log_data.continuous_time)
+                                || !process_filter.includes_subsystem(
+                                    preamble.first_number_proc_id,
+                                    preamble.second_number_proc_id,
+                                    firehose.firehose_signpost.subsystem,
+                                )
+                            {
+                                continue;
+                            }
                             log_data.activity_id =
                                 u64::from(firehose.firehose_signpost.unknown_activity_id);
+                            log_data.signpost_id = firehose.firehose_signpost.signpost_id;
+                            log_data.signpost_name = firehose.firehose_signpost.signpost_name;
+                            log_data.ttl = firehose.firehose_signpost.ttl_value;
                             let message_data = FirehoseSignpost::get_firehose_signpost(
                                 &firehose.firehose_signpost,
                                 strings_data,
@@ -483,30 +691,23 @@ impl LogData {
                                     log_data.process_uuid = results.process_uuid;
                                     log_data.raw_message = results.format_string.to_owned();
 
-                                    let mut log_message =
-                                        if firehose.firehose_non_activity.data_ref_value != 0 {
-                                            let oversize_strings = Oversize::get_oversize_strings(
-                                                u32::from(
-                                                    firehose.firehose_non_activity.data_ref_value,
-                                                ),
-                                                preamble.first_number_proc_id,
-                                                preamble.second_number_proc_id,
-                                                &unified_log_data.oversize,
-                                            );
-                                            // Format and map the log strings with the message format string found UUIDText or shared string file
-                                            format_firehose_log_message(
-                                                results.format_string,
-                                                &oversize_strings,
-                                                &message_re,
-                                            )
-                                        } else {
-                                            // Format and map the log strings with the message format string found UUIDText or shared string file
-                                            format_firehose_log_message(
-                                                results.format_string,
-                                                &firehose.message.item_info,
-                                                &message_re,
-                                            )
-                                        };
+                                    // If the signpost log entry has a data ref value then the message strings are stored in an oversize log entry
+                                    if firehose.firehose_signpost.data_ref_value != 0 {
+                                        log_data.message_entries = Oversize::get_oversize_strings(
+                                            u32::from(firehose.firehose_signpost.data_ref_value),
+                                            preamble.first_number_proc_id,
+                                            preamble.second_number_proc_id,
+                                            &unified_log_data.oversize,
+                                        );
+                                    }
+                                    // Format and map the log strings with the message format string found UUIDText or shared string file
+                                    let log_message = format_firehose_log_message(
+                                        results.format_string,
+                                        &mut log_data.message_entries,
+                                        &message_re,
+                                        decoders,
+                                        format_options,
+                                    );
                                     if exclude_mssing
                                         && log_message.contains("<Missing message data>")
                                     {
@@ -521,7 +722,7 @@ impl LogData {
                                         continue;
                                     }
 
-                                    log_message = format!(
+                                    let log_message = format!(
                                         "Signpost ID: {:X} - Signpost Name: {:X}\n {}",
                                         firehose.firehose_signpost.signpost_id,
                                         firehose.firehose_signpost.signpost_name,
@@ -542,26 +743,19 @@ impl LogData {
                                     warn!("[macos-unifiedlogs] Failed to get message string data for firehose signpost log entry: {:?}", err);
                                 }
                             }
-                            if firehose.firehose_signpost.subsystem != 0 {
-                                let results = CatalogChunk::get_subsystem(
-                                    &firehose.firehose_signpost.subsystem,
-                                    &preamble.first_number_proc_id,
-                                    &preamble.second_number_proc_id,
-                                    &catalog_data.catalog,
-                                );
-                                match results {
-                                    Ok((_, subsystem)) => {
-                                        log_data.subsystem = subsystem.subsystem;
-                                        log_data.category = subsystem.category;
-                                    }
-                                    Err(err) => warn!(
-                                        "[macos-unifiedlogs] Failed to get subsystem: {:?}",
-                                        err
-                                    ),
-                                }
+
+                            log_data.add_subsystem(
+                                firehose.firehose_signpost.subsystem,
+                                preamble.first_number_proc_id,
+                                preamble.second_number_proc_id,
+                                &catalog_data.catalog,
+                            );
+
+                            if query.matches(&log_data, &window) {
+                                log_data_vec.push(log_data);
                             }
                         }
-                        0x3 => {
+                        /* 0x3 => {
                             let message_data = FirehoseTrace::get_firehose_trace_strings(
                                 strings_data,
                                 u64::from(firehose.format_string_location),
@@ -580,6 +774,8 @@ impl LogData {
                                         results.format_string,
                                         &firehose.message.item_info,
                                         &message_re,
+                                        decoders,
+                                        format_options,
                                     );
 
                                     if exclude_mssing
@@ -629,12 +825,15 @@ impl LogData {
                     pid: simpledump.first_proc_id,
                     library: String::new(),
                     activity_id: 0,
+                    ttl: 0,
                     time: TimesyncBoot::get_timestamp(
                         timesync_data,
                         &unified_log_data.header[0].boot_uuid,
                         simpledump.continous_time,
                         no_firehose_preamble,
                     ),
+                    continuous_time: simpledump.continous_time,
+                    trace_id: 0,
                     category: String::new(),
                     log_type: String::new(),
                     process: String::new(),
@@ -656,40 +855,28 @@ impl LogData {
                 log_data_vec.push(log_data);
             }
             */
-            /* for statedump in &catalog_data.statedump {
+            for statedump in &catalog_data.statedump {
+                if !options.statedumps
+                    || !window.includes_continuous_time(statedump.continuous_time)
+                    || !process_filter
+                        .includes_process(statedump.first_proc_id, statedump.second_proc_id)
+                {
+                    continue;
+                }
                 let no_firehose_preamble = 1;
-
-                let data_string = match statedump.unknown_data_type {
-                    0x1 => Statedump::parse_statedump_plist(&statedump.statedump_data),
-                    0x2 => String::from("Statedump Protocol Buffer"),
-                    0x3 => Statedump::parse_statedump_object(
-                        &statedump.statedump_data,
-                        &statedump.unknown_name,
-                    ),
-                    _ => {
-                        warn!(
-                            "Unknown statedump data type: {}",
-                            statedump.unknown_data_type
-                        );
-                        let results = extract_string(&statedump.statedump_data);
-                        match results {
-                            Ok((_, string_data)) => string_data,
-                            Err(err) => {
-                                error!(
-                                "[macos-unifiedlogs] Failed to extract string from statedump: {:?}",
-                                err
-                            );
-                                String::from("Failed to extract string from statedump")
-                            }
-                        }
-                    }
-                };
+                let data_string = statedump.parse_statedump_data(options);
 
                 let log_data = LogData {
-                    subsystem: String::new(),
-                    thread_id: 0,
-                    pid: statedump.first_proc_id,
-                    library: String::new(),
+                    pid: CatalogChunk::get_pid(
+                        &statedump.first_proc_id,
+                        &statedump.second_proc_id,
+                        &catalog_data.catalog,
+                    ),
+                    euid: CatalogChunk::get_euid(
+                        &statedump.first_proc_id,
+                        &statedump.second_proc_id,
+                        &catalog_data.catalog,
+                    ),
                     activity_id: statedump.activity_id,
                     time: TimesyncBoot::get_timestamp(
                         timesync_data,
@@ -697,40 +884,38 @@ impl LogData {
                         statedump.continuous_time,
                         no_firehose_preamble,
                     ),
-                    category: String::new(),
+                    continuous_time: statedump.continuous_time,
                     event_type: String::from("Statedump"),
-                    process: String::new(),
                     message: format!(
-                        "title: {:?}\nObject Type: {:?}\n Object Type: {:?}\n{:?}",
+                        "title: {}\nObject Type: {}\nObject Type: {}\n{}",
                         statedump.unknown_name,
                         statedump.unknown_object_type_string_1,
                         statedump.unknown_object_type_string_2,
                         data_string
                     ),
-                    log_type: String::new(),
-                    euid: 0,
                     boot_uuid: unified_log_data.header[0].boot_uuid.to_owned(),
                     timezone_name: unified_log_data.header[0]
                         .timezone_path
                         .split('/')
-                        .last()
+                        .next_back()
                         .unwrap_or("Unknown Timezone Name")
                         .to_string(),
-                    library_uuid: String::new(),
-                    process_uuid: String::new(),
-                    raw_message: String::new(),
-                    message_entries: Vec::new(),
+                    ..Default::default()
                 };
-                log_data_vec.push(log_data);
+                if query.matches(&log_data, &window) {
+                    log_data_vec.push(log_data);
+                }
+            }
+            if !log_data_vec.is_empty() {
+                on_entries(std::mem::take(&mut log_data_vec));
             }
- */
          }
 
-        (log_data_vec, missing_unified_log_data_vec)
+        missing_unified_log_data_vec
     }
 
     /// Return log type based on parsed log data
-    fn get_log_type(log_type: &u8, activity_type: &u8) -> String {
+    pub(crate) fn get_log_type(log_type: &u8, activity_type: &u8) -> String {
         match log_type {
             0x1 => {
                 let activity = 2;
@@ -757,8 +942,94 @@ impl LogData {
         }
     }
 
+    /// Fill in the subsystem and category of a log entry from the catalog process entry that logged it.
+    /// The `subsystems` and `categories` query options and the `subsystem`/`category` predicate keys compare against these fields,
+    /// so the lookup has to happen before the entry is matched against the `LogQuery`
+    fn add_subsystem(
+        &mut self,
+        subsystem_value: u16,
+        first_proc_id: u64,
+        second_proc_id: u32,
+        catalog: &CatalogChunk,
+    ) {
+        // Zero means the entry was not logged with os_log_create()
+        if subsystem_value == 0 {
+            return;
+        }
+        let results = CatalogChunk::get_subsystem(
+            &subsystem_value,
+            &first_proc_id,
+            &second_proc_id,
+            catalog,
+        );
+        match results {
+            Ok((_, subsystem)) => {
+                self.subsystem = subsystem.subsystem;
+                self.category = subsystem.category;
+            }
+            Err(err) => warn!("[macos-unifiedlogs] Failed to get subsystem: {:?}", err),
+        }
+    }
+
+    /// Entry fields shared by the firehose log types. The caller adds the fields and message of the log type
+    fn firehose_entry(
+        unified_log_data: &UnifiedLogData,
+        catalog: &CatalogChunk,
+        preamble: &FirehosePreamble,
+        firehose: &Firehose,
+        timesync_data: &[TimesyncBoot],
+    ) -> LogData {
+        // The continous time is actually 6 bytes long. Combining 4 bytes and 2 bytes
+        let continuous_time = preamble.base_continous_time
+            + (u64::from(firehose.continous_time_delta)
+                | (u64::from(firehose.continous_time_delta_upper) << 32));
+        LogData {
+            thread_id: firehose.thread_id,
+            pid: CatalogChunk::get_pid(
+                &preamble.first_number_proc_id,
+                &preamble.second_number_proc_id,
+                catalog,
+            ),
+            euid: CatalogChunk::get_euid(
+                &preamble.first_number_proc_id,
+                &preamble.second_number_proc_id,
+                catalog,
+            ),
+            time: TimesyncBoot::get_timestamp(
+                timesync_data,
+                &unified_log_data.header[0].boot_uuid,
+                continuous_time,
+                preamble.base_continous_time,
+            ),
+            continuous_time,
+            trace_id: LogData::get_trace_id(firehose),
+            event_type: LogData::get_event_type(&firehose.unknown_log_activity_type),
+            log_type: LogData::get_log_type(
+                &firehose.unknown_log_type,
+                &firehose.unknown_log_activity_type,
+            ),
+            boot_uuid: unified_log_data.header[0].boot_uuid.to_owned(),
+            timezone_name: unified_log_data.header[0]
+                .timezone_path
+                .split('/')
+                .next_back()
+                .unwrap_or("Unknown Timezone Name")
+                .to_string(),
+            message_entries: firehose.message.item_info.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Rebuild the firehose tracepoint ID (`traceID` in `log show` output). It is the first 8 bytes of the tracepoint header
+    fn get_trace_id(firehose: &Firehose) -> u64 {
+        u64::from(firehose.unknown_log_activity_type)
+            | (u64::from(firehose.unknown_log_type) << 8)
+            | (u64::from(firehose.flags) << 16)
+            | (u64::from(firehose.format_string_location) << 32)
+    }
+
     /// Return the log event type based on parsed log data
-    fn get_event_type(event_type: &u8) -> String {
+    pub(crate) fn get_event_type(event_type: &u8) -> String {
         match event_type {
             0x4 => String::from("Log"),
             0x2 => String::from("Activity"),
@@ -875,24 +1146,65 @@ impl LogData {
     }
 }
 
+/// Log entry shared by the exporter, filter and index tests
+#[cfg(test)]
+pub(crate) fn test_log_data() -> LogData {
+    LogData {
+        subsystem: String::from("com.apple.lom"),
+        thread_id: 588,
+        pid: 45,
+        euid: 0,
+        library: String::from("/usr/libexec/lightsoutmanagementd"),
+        library_uuid: String::from("6C3ADF991F033C1C96C4ADFAA12D8CED"),
+        activity_id: 0,
+        signpost_id: 0,
+        signpost_name: 0,
+        ttl: 0,
+        time: 1.6423023264348508e18,
+        continuous_time: 11318876544,
+        trace_id: 0x93773c8310004,
+        category: String::from("device"),
+        event_type: String::from("Log"),
+        log_type: String::from("Default"),
+        process: String::from("/usr/libexec/lightsoutmanagementd"),
+        process_uuid: String::from("6C3ADF991F033C1C96C4ADFAA12D8CED"),
+        message: String::from("LOMD Start 1"),
+        raw_message: String::from("LOMD Start %d"),
+        boot_uuid: String::from("80D194AF56A34C54867449D2130D41BB"),
+        timezone_name: String::from("Pacific"),
+        message_entries: vec![FirehoseItemInfo {
+            message_strings: String::from("1"),
+            item_type: 0,
+            item_size: 4,
+            message_value: crate::message_value::MessageValue::Signed(1),
+        }],
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{fs, path::PathBuf};
 
     use crate::{
-        catalog::CatalogChunk,
+        activity::ActivityGraph,
+        catalog::{test_catalog, test_process_info, CatalogChunk},
+        chunks::statedump::test_statedump,
         chunks::firehose::activity::FirehoseActivity,
-        chunks::firehose::firehose_log::{Firehose, FirehoseItemData},
+        chunks::firehose::firehose_log::{Firehose, FirehoseItemData, FirehoseItemInfo},
         chunks::firehose::flags::FirehoseFormatters,
         chunks::firehose::loss::FirehoseLoss,
         chunks::firehose::nonactivity::FirehoseNonActivity,
         chunks::firehose::signpost::FirehoseSignpost,
         chunks::firehose::trace::FirehoseTrace,
+        exporters::trace_event::{SignpostKind, SignpostMarker},
+        header::HeaderChunk,
+        message_value::MessageValue,
         parser::{collect_shared_strings, collect_strings, collect_timesync, parse_log},
         unified_log::UnifiedLogCatalogData,
+        uuidtext::{UUIDText, UUIDTextEntry},
     };
 
-    use super::{LogData, UnifiedLogData};
+    use super::{LogData, LogOptions, UnifiedLogData};
 
     #[test]
     fn test_parse_unified_log() {
@@ -960,15 +1272,14 @@ mod tests {
 
         let log_data = parse_log(&test_path.display().to_string(), 0, 0).unwrap();
 
-        let exclude_missing = false;
         let (results, _) = LogData::build_log(
             &log_data,
             &string_results,
             &shared_strings_results,
             &timesync_data,
-            exclude_missing,
             0,
             0,
+            &LogOptions::default(),
         );
         assert_eq!(results.len(), 207366);
         assert_eq!(results[0].process, "/usr/libexec/lightsoutmanagementd");
@@ -990,6 +1301,207 @@ mod tests {
         assert_eq!(results[0].raw_message, "LOMD Start");
     }
 
+    #[test]
+    fn test_build_log_statedump() {
+        let mut statedump = test_statedump();
+        statedump.first_proc_id = 4640;
+        statedump.second_proc_id = 464;
+        let mut catalog = test_catalog(0, 5000000000);
+        catalog
+            .catalog_process_info_entries
+            .push(test_process_info(464, "6C3ADF991F033C1C96C4ADFAA12D8CED"));
+        let unified_log = UnifiedLogData {
+            header: vec![HeaderChunk {
+                boot_uuid: String::from("80D194AF56A34C54867449D2130D41BB"),
+                timezone_path: String::from("/var/db/timezone/zoneinfo/America/New_York"),
+                ..Default::default()
+            }],
+            catalog_data: vec![UnifiedLogCatalogData {
+                catalog,
+                firehose: Vec::new(),
+                simpledump: Vec::new(),
+                statedump: vec![statedump],
+                oversize: Vec::new(),
+            }],
+            oversize: Vec::new(),
+        };
+
+        let mut options = LogOptions::default();
+        let (results, _) = LogData::build_log(&unified_log, &[], &[], &[], 0, 0, &options);
+        assert!(results.is_empty());
+
+        options.statedumps = true;
+        let (results, _) = LogData::build_log(&unified_log, &[], &[], &[], 0, 0, &options);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].event_type, "Statedump");
+        assert_eq!(results[0].pid, 464);
+        assert_eq!(results[0].activity_id, 9223372036854776950);
+        assert_eq!(results[0].continuous_time, 3906319117);
+        assert_eq!(results[0].timezone_name, "New_York");
+        assert!(results[0].message.starts_with(
+            "title: CLDaemonStatusStateTracker\nObject Type: location\nObject Type: _CLDaemonStatusStateTrackerState\n{"
+        ));
+        assert!(results[0]
+            .message
+            .contains("\"reachability\":\"kReachabilityLarge\""));
+
+        // Callers can replace the object decoders
+        options.objects.register("CLDaemonStatusStateTracker", |data: &[u8]| {
+            Some(serde_json::json!({ "size": data.len() }))
+        });
+        options
+            .objects
+            .unregister("location:_CLDaemonStatusStateTrackerState");
+        let (results, _) = LogData::build_log(&unified_log, &[], &[], &[], 0, 0, &options);
+        assert!(results[0].message.ends_with("\n{\"size\":40}"));
+
+        options.query.pids = vec![1];
+        let (results, _) = LogData::build_log(&unified_log, &[], &[], &[], 0, 0, &options);
+        assert!(results.is_empty());
+    }
+
+    /// tracev3 data with a single firehose entry logged by pid 45
+    fn test_firehose_log(firehose: Firehose) -> UnifiedLogData {
+        let mut catalog = test_catalog(0, 5000000000);
+        catalog
+            .catalog_process_info_entries
+            .push(test_process_info(45, "6C3ADF991F033C1C96C4ADFAA12D8CED"));
+        UnifiedLogData {
+            header: vec![HeaderChunk {
+                boot_uuid: String::from("80D194AF56A34C54867449D2130D41BB"),
+                timezone_path: String::from("/var/db/timezone/zoneinfo/America/New_York"),
+                ..Default::default()
+            }],
+            catalog_data: vec![UnifiedLogCatalogData {
+                catalog,
+                firehose: vec![LogData::track_missing(450, 45, 1000, firehose)],
+                simpledump: Vec::new(),
+                statedump: Vec::new(),
+                oversize: Vec::new(),
+            }],
+            oversize: Vec::new(),
+        }
+    }
+
+    /// `UUIDText` of pid 45 with the format string "Load %{public}s" at offset 16
+    fn test_format_strings() -> Vec<UUIDText> {
+        vec![UUIDText {
+            uuid: String::from("1F033C1C96C4ADFAA12D8CED"),
+            signature: 0x66778899,
+            unknown_major_version: 2,
+            unknown_minor_version: 1,
+            number_entries: 1,
+            entry_descriptors: vec![UUIDTextEntry {
+                range_start_offset: 0,
+                entry_size: 32,
+            }],
+            // Format string at offset 16, followed by the image path
+            footer_data: b"Padding_16_bytesLoad %{public}s\0/usr/libexec/powerd\0".to_vec(),
+            bh_pos: 0,
+        }]
+    }
+
+    #[test]
+    fn test_build_log_signpost() {
+        let mut firehose = test_firehose();
+        firehose.unknown_log_activity_type = 0x6;
+        firehose.unknown_log_type = 0x81;
+        firehose.format_string_location = 16;
+        firehose.thread_id = 588;
+        firehose.continous_time_delta = 100;
+        firehose.firehose_signpost.subsystem = 2;
+        firehose.firehose_signpost.signpost_id = 0xEEEEB0B5B2B2EEEE;
+        firehose.firehose_signpost.signpost_name = 0x1e3a5;
+        firehose.firehose_signpost.firehose_formatters.main_exe = true;
+        firehose.message.item_info = vec![FirehoseItemInfo {
+            message_strings: String::from("sleep"),
+            item_type: 0x22,
+            item_size: 6,
+            message_value: MessageValue::String(String::from("sleep")),
+        }];
+
+        let unified_log = test_firehose_log(firehose);
+        let strings = test_format_strings();
+
+        let mut options = LogOptions::default();
+        let (results, _) = LogData::build_log(&unified_log, &strings, &[], &[], 0, 0, &options);
+        assert!(results.is_empty());
+
+        options.signposts = true;
+        let (results, _) = LogData::build_log(&unified_log, &strings, &[], &[], 0, 0, &options);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].event_type, "Signpost");
+        assert_eq!(results[0].log_type, "Process Signpost Start");
+        assert_eq!(results[0].pid, 45);
+        assert_eq!(results[0].thread_id, 588);
+        assert_eq!(results[0].continuous_time, 1100);
+        assert_eq!(results[0].process, "/usr/libexec/powerd");
+        assert_eq!(results[0].subsystem, "com.apple.lom");
+        assert_eq!(results[0].category, "device");
+        assert_eq!(results[0].raw_message, "Load %{public}s");
+        assert_eq!(results[0].signpost_id, 0xEEEEB0B5B2B2EEEE);
+        assert_eq!(results[0].signpost_name, 0x1e3a5);
+        assert_eq!(
+            results[0].message,
+            "Signpost ID: EEEEB0B5B2B2EEEE - Signpost Name: 1E3A5\n Load sleep"
+        );
+
+        let marker = SignpostMarker::from_log_data(&results[0]).unwrap();
+        assert_eq!(marker.kind, SignpostKind::Begin);
+        assert_eq!(marker.signpost_id, 0xEEEEB0B5B2B2EEEE);
+        assert_eq!(marker.message, "Load sleep");
+
+        options.query.subsystems = vec![String::from("com.apple.powerd")];
+        let (results, _) = LogData::build_log(&unified_log, &strings, &[], &[], 0, 0, &options);
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_build_log_activity() {
+        let mut firehose = test_firehose();
+        firehose.unknown_log_activity_type = 0x2;
+        firehose.unknown_log_type = 0x1;
+        firehose.format_string_location = 16;
+        firehose.thread_id = 588;
+        firehose.continous_time_delta = 100;
+        firehose.firehose_activity.unknown_activity_id = 1234;
+        firehose.firehose_activity.unknown_activity_id_2 = 12;
+        firehose.message.item_info = vec![FirehoseItemInfo {
+            message_strings: String::from("assets"),
+            item_type: 0x22,
+            item_size: 7,
+            message_value: MessageValue::String(String::from("assets")),
+        }];
+        let unified_log = test_firehose_log(firehose);
+        let strings = test_format_strings();
+
+        let mut options = LogOptions::default();
+        let (results, _) = LogData::build_log(&unified_log, &strings, &[], &[], 0, 0, &options);
+        assert!(results.is_empty());
+
+        options.activities = true;
+        let (results, _) = LogData::build_log(&unified_log, &strings, &[], &[], 0, 0, &options);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].event_type, "Activity");
+        assert_eq!(results[0].log_type, "Create");
+        assert_eq!(results[0].activity_id, 1234);
+        assert_eq!(results[0].pid, 45);
+        assert_eq!(results[0].continuous_time, 1100);
+        assert_eq!(results[0].process, "/usr/libexec/powerd");
+        assert_eq!(results[0].raw_message, "Load %{public}s");
+        assert_eq!(results[0].message, "Load assets");
+
+        // Activity create events are also read from the parsed data by the activity graph
+        let mut graph = ActivityGraph::new();
+        graph.add_activities(&unified_log, &strings, &[], &[]);
+        graph.add_entries(&results);
+        let activity = graph.activity("80D194AF56A34C54867449D2130D41BB", 1234).unwrap();
+        assert!(activity.created);
+        assert_eq!(activity.parent_id, 12);
+        assert_eq!(activity.name, "Load %{public}s");
+        assert_eq!(graph.entries(activity)[0].message, "Load assets");
+    }
+
     #[test]
     fn test_get_log_type() {
         let mut log_type = 0x2;
@@ -1002,6 +1514,23 @@ mod tests {
         assert_eq!(log_string, "Create");
     }
 
+    #[test]
+    fn test_add_subsystem() {
+        let mut catalog = test_catalog(0, 100);
+        catalog
+            .catalog_process_info_entries
+            .push(test_process_info(45, "6C3ADF991F033C1C96C4ADFAA12D8CED"));
+        let mut log_data = LogData::default();
+        log_data.add_subsystem(2, 450, 45, &catalog);
+        assert_eq!(log_data.subsystem, "com.apple.lom");
+        assert_eq!(log_data.category, "device");
+
+        let mut log_data = LogData::default();
+        log_data.add_subsystem(0, 450, 45, &catalog);
+        assert_eq!(log_data.subsystem, "");
+        assert_eq!(log_data.category, "");
+    }
+
     #[test]
     fn test_get_event_type() {
         let event_type = 0x2;
@@ -1175,12 +1704,9 @@ mod tests {
         assert_eq!(unified_log.firehose[0].private_data_virtual_offset, 4096);
     }
 
-    #[test]
-    fn test_track_missing() {
-        let first_proc_id = 1;
-        let second_proc_id = 2;
-        let time = 11;
-        let test_firehose = Firehose {
+    /// Firehose entry with every field zeroed
+    fn test_firehose() -> Firehose {
+        Firehose {
             unknown_log_activity_type: 0,
             unknown_log_type: 0,
             flags: 0,
@@ -1272,7 +1798,15 @@ mod tests {
                 item_info: Vec::new(),
                 backtrace_strings: Vec::new(),
             },
-        };
+        }
+    }
+
+    #[test]
+    fn test_track_missing() {
+        let first_proc_id = 1;
+        let second_proc_id = 2;
+        let time = 11;
+        let test_firehose = test_firehose();
 
         let missing_firehose =
             LogData::track_missing(first_proc_id, second_proc_id, time, test_firehose);
@@ -8,6 +8,7 @@
 use base64::engine::general_purpose;
 use base64::DecodeError;
 use base64::Engine;
+use chrono::{FixedOffset, SecondsFormat, TimeZone, Utc};
 use log::{error, warn};
 use nom::bytes::complete::take;
 use nom::bytes::complete::take_while;
@@ -109,11 +110,46 @@ pub(crate) fn clean_uuid(uuid_format: &str) -> String {
     uuid_format.replace([',', '[', ']', ' '], "")
 }
 
+/// Format a 32 character hex UUID in the 8-4-4-4-12 form used by Apple's `log` command. Other values are returned unchanged
+pub(crate) fn hyphenate_uuid(uuid: &str) -> String {
+    let uuid_length = 32;
+    if uuid.len() != uuid_length || !uuid.chars().all(|c| c.is_ascii_hexdigit()) {
+        return uuid.to_string();
+    }
+    format!(
+        "{}-{}-{}-{}-{}",
+        &uuid[0..8],
+        &uuid[8..12],
+        &uuid[12..16],
+        &uuid[16..20],
+        &uuid[20..32]
+    )
+}
+
+/// Get the file name from an image path (ex: `/usr/lib/libxpc.dylib` -> `libxpc.dylib`)
+pub(crate) fn image_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
 /// Base64 encode data use the STANDARD engine (alphabet along with "+" and "/")
 pub(crate) fn encode_standard(data: &[u8]) -> String {
     general_purpose::STANDARD.encode(data)
 }
 
+/// Convert a `LogData` timestamp (nanoseconds since UNIXEPOCH) to an ISO-8601 (RFC 3339) string in UTC
+pub(crate) fn unixepoch_to_iso(timestamp: &f64) -> String {
+    Utc.timestamp_nanos(*timestamp as i64)
+        .to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+/// Convert a `LogData` timestamp (nanoseconds since UNIXEPOCH) to a string in the provided timezone using a `strftime` format
+pub(crate) fn unixepoch_to_local(timestamp: &f64, timezone: &FixedOffset, format: &str) -> String {
+    timezone
+        .timestamp_nanos(*timestamp as i64)
+        .format(format)
+        .to_string()
+}
+
 /// Base64 decode data use the STANDARD engine (alphabet along with "+" and "/")
 pub(crate) fn decode_standard(data: &str) -> Result<Vec<u8>, DecodeError> {
     general_purpose::STANDARD.decode(data)
@@ -121,7 +157,11 @@ pub(crate) fn decode_standard(data: &str) -> Result<Vec<u8>, DecodeError> {
 
 #[cfg(test)]
 mod tests {
-    use crate::util::{extract_string, extract_string_size, padding_size, padding_size_four};
+    use crate::util::{
+        extract_string, extract_string_size, hyphenate_uuid, padding_size, padding_size_four,
+        unixepoch_to_iso, unixepoch_to_local,
+    };
+    use chrono::FixedOffset;
 
     use super::{decode_standard, encode_standard};
 
@@ -167,4 +207,29 @@ mod tests {
         let result = decode_standard(test).unwrap();
         assert_eq!(result, b"Hello word!")
     }
+
+    #[test]
+    fn test_unixepoch_to_iso() {
+        let test_time = 1.6423023264348508e18;
+        let result = unixepoch_to_iso(&test_time);
+        assert_eq!(result, "2022-01-16T03:05:26.434850816Z")
+    }
+
+    #[test]
+    fn test_unixepoch_to_local() {
+        let test_time = 1.6423023264348508e18;
+        let pacific = FixedOffset::west_opt(8 * 3600).unwrap();
+        let result = unixepoch_to_local(&test_time, &pacific, "%Y-%m-%d %H:%M:%S%.6f%z");
+        assert_eq!(result, "2022-01-15 19:05:26.434850-0800")
+    }
+
+    #[test]
+    fn test_hyphenate_uuid() {
+        assert_eq!(
+            hyphenate_uuid("6C3ADF991F033C1C96C4ADFAA12D8CED"),
+            "6C3ADF99-1F03-3C1C-96C4-ADFAA12D8CED"
+        );
+        assert_eq!(hyphenate_uuid(""), "");
+        assert_eq!(hyphenate_uuid("Unknown"), "Unknown");
+    }
 }